@@ -58,6 +58,13 @@ static INSTANCE: OnceLock<EdenFsInstance> = OnceLock::new();
 const CLIENTS_DIR: &str = "clients";
 const CONFIG_JSON: &str = "config.json";
 
+/// Initial delay before the first retry when the daemon's socket isn't ready yet.
+const CONNECT_RETRY_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+/// Cap on the exponential backoff between connection retries.
+const CONNECT_RETRY_MAX_BACKOFF: Duration = Duration::from_secs(2);
+/// Give up retrying once we've waited this long in total, and surface the last error instead.
+const CONNECT_RETRY_MAX_WAIT: Duration = Duration::from_secs(10);
+
 #[derive(Debug)]
 pub struct EdenFsInstance {
     config_dir: PathBuf,
@@ -108,10 +115,34 @@ impl EdenFsInstance {
         Ok(client)
     }
 
+    /// Connect to the EdenFS daemon, retrying with exponential backoff if the socket isn't ready
+    /// yet. This is especially useful right after `eden start` on slow hosts, where the daemon
+    /// may take a few seconds to bind its Thrift socket.
+    async fn _connect_with_retry(&self, socket_path: &PathBuf) -> Result<EdenFsClient> {
+        let mut backoff = CONNECT_RETRY_INITIAL_BACKOFF;
+        let mut waited = Duration::ZERO;
+
+        loop {
+            match self._connect(socket_path).await {
+                Ok(client) => return Ok(client),
+                Err(e) if waited + backoff > CONNECT_RETRY_MAX_WAIT => return Err(e),
+                Err(_) => {
+                    eprintln!(
+                        "edenfs daemon starting, waited {}s...",
+                        waited.as_secs()
+                    );
+                    tokio::time::sleep(backoff).await;
+                    waited += backoff;
+                    backoff = std::cmp::min(backoff * 2, CONNECT_RETRY_MAX_BACKOFF);
+                }
+            }
+        }
+    }
+
     pub async fn connect(&self, timeout: Option<Duration>) -> Result<EdenFsClient> {
         let socket_path = self.config_dir.join("socket");
 
-        let connect = self._connect(&socket_path);
+        let connect = self._connect_with_retry(&socket_path);
         let res = if let Some(timeout) = timeout {
             tokio::time::timeout(timeout, connect)
                 .await
@@ -161,7 +192,7 @@ impl EdenFsInstance {
     }
 
     /// Read the pid from the EdenFS lockfile
-    fn pid(&self) -> Result<sysinfo::Pid, anyhow::Error> {
+    pub fn pid(&self) -> Result<sysinfo::Pid, anyhow::Error> {
         let pidfile = self.pidfile();
         let pid_bytes = std::fs::read(&pidfile)
             .with_context(|| format!("Unable to read from pid file '{}'", pidfile.display()))?;
@@ -19,11 +19,14 @@ use thrift_types::edenfs_clients::EdenService;
 pub mod checkout;
 pub mod fsutil;
 pub mod instance;
+pub mod journal;
 mod mounttable;
 pub mod redirect;
+pub mod resilient_client;
 
 pub use instance::DaemonHealthy;
 pub use instance::EdenFsInstance;
+pub use resilient_client::ResilientClient;
 
 pub type EdenFsClient = Arc<dyn EdenService + Sync>;
 
@@ -0,0 +1,108 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! A [`ResilientClient`] wraps an [`EdenFsClient`] connection and reconnects automatically when
+//! the daemon it's talking to restarts underneath it.
+//!
+//! [`EdenFsInstance::connect`](crate::EdenFsInstance::connect) already retries with backoff
+//! while the daemon's socket isn't ready yet (right after `eden start`). `ResilientClient`
+//! covers the other half of a long-running tool's lifetime: a connection that worked fine and
+//! then dropped, because the daemon it was talking to restarted out from under it.
+
+use std::future::Future;
+use std::time::Duration;
+
+use edenfs_error::Result;
+use thrift_types::edenfs::DaemonInfo;
+use tokio::sync::Mutex;
+use tracing::event;
+use tracing::Level;
+
+use crate::DaemonHealthy;
+use crate::EdenFsClient;
+use crate::EdenFsInstance;
+
+/// Delay between a failed call and the reconnect-and-retry attempt.
+const CALL_RETRY_BACKOFF: Duration = Duration::from_millis(200);
+
+/// A Thrift client that transparently reconnects on failure, for tools that stay running across
+/// an EdenFS daemon restart.
+pub struct ResilientClient {
+    instance: &'static EdenFsInstance,
+    connect_timeout: Option<Duration>,
+    client: Mutex<Option<EdenFsClient>>,
+}
+
+impl ResilientClient {
+    pub fn new(instance: &'static EdenFsInstance, connect_timeout: Option<Duration>) -> Self {
+        Self {
+            instance,
+            connect_timeout,
+            client: Mutex::new(None),
+        }
+    }
+
+    /// Returns a clone of the cached connection, connecting first if there isn't one yet.
+    async fn client(&self) -> Result<EdenFsClient> {
+        let mut guard = self.client.lock().await;
+        if let Some(client) = guard.as_ref() {
+            return Ok(client.clone());
+        }
+        let client = self.instance.connect(self.connect_timeout).await?;
+        *guard = Some(client.clone());
+        Ok(client)
+    }
+
+    /// Drops the cached connection, so the next call re-resolves the daemon's socket instead of
+    /// reusing one that may point at a daemon that has since restarted.
+    async fn invalidate(&self) {
+        *self.client.lock().await = None;
+    }
+
+    /// Probes whether the daemon is alive and responsive. Always re-resolves the socket first,
+    /// so a stale cached connection can't report a dead daemon as healthy.
+    pub async fn probe_health(&self) -> Result<DaemonInfo> {
+        self.invalidate().await;
+        self.instance.get_health(self.connect_timeout).await
+    }
+
+    /// Convenience wrapper over [`Self::probe_health`] for callers that just want a bool.
+    pub async fn is_healthy(&self) -> bool {
+        self.probe_health()
+            .await
+            .map(|info| info.is_healthy())
+            .unwrap_or(false)
+    }
+
+    /// Runs `call` against the current connection. If it fails, assumes the connection was
+    /// dropped (e.g. the daemon restarted), re-resolves the socket, and retries once.
+    ///
+    /// Only use this for idempotent calls: on a genuine connection drop the daemon can't tell
+    /// whether the first attempt was ever received, so a non-idempotent call could be applied
+    /// twice.
+    pub async fn call_with_retry<F, Fut, T>(&self, call: F) -> Result<T>
+    where
+        F: Fn(EdenFsClient) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let client = self.client().await?;
+        match call(client).await {
+            Ok(value) => Ok(value),
+            Err(e) => {
+                event!(
+                    Level::DEBUG,
+                    error = ?e,
+                    "thrift call failed, reconnecting and retrying once"
+                );
+                self.invalidate().await;
+                tokio::time::sleep(CALL_RETRY_BACKOFF).await;
+                let client = self.client().await?;
+                call(client).await
+            }
+        }
+    }
+}
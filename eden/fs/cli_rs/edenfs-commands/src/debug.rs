@@ -14,9 +14,15 @@ use clap::Parser;
 use crate::ExitCode;
 use crate::Subcommand;
 
+mod blob;
+mod changes_since;
 mod clear_local_caches;
 mod compact_local_storage;
+pub(crate) mod object;
+mod reload_config;
 mod subscribe;
+mod thrift;
+mod tree;
 
 #[derive(Parser, Debug)]
 #[clap(
@@ -30,9 +36,14 @@ pub struct DebugCmd {
 
 #[derive(Parser, Debug)]
 pub enum DebugSubcommand {
+    Blob(blob::BlobCmd),
+    ChangesSince(changes_since::ChangesSinceCmd),
     ClearLocalCaches(clear_local_caches::ClearLocalCachesCmd),
     CompactLocalStorage(compact_local_storage::CompactLocalStorageCmd),
+    ReloadConfig(reload_config::ReloadConfigCmd),
     Subscribe(subscribe::SubscribeCmd),
+    Thrift(thrift::ThriftCmd),
+    Tree(tree::TreeCmd),
 }
 
 #[async_trait]
@@ -40,9 +51,14 @@ impl Subcommand for DebugCmd {
     async fn run(&self) -> Result<ExitCode> {
         use DebugSubcommand::*;
         let sc: &(dyn Subcommand + Send + Sync) = match &self.subcommand {
+            Blob(cmd) => cmd,
+            ChangesSince(cmd) => cmd,
             ClearLocalCaches(cmd) => cmd,
             CompactLocalStorage(cmd) => cmd,
+            ReloadConfig(cmd) => cmd,
             Subscribe(cmd) => cmd,
+            Thrift(cmd) => cmd,
+            Tree(cmd) => cmd,
         };
         sc.run().await
     }
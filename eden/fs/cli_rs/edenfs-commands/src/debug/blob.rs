@@ -0,0 +1,110 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! edenfsctl debug blob
+
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+use anyhow::Context;
+use anyhow::Result;
+use async_trait::async_trait;
+use clap::Parser;
+use edenfs_client::checkout::find_checkout;
+use edenfs_client::EdenFsInstance;
+use hg_util::path::expand_path;
+use thrift_types::edenfs as edenfs_thrift;
+
+use crate::debug::object::origin_name;
+use crate::debug::object::parse_object_id;
+use crate::debug::object::OriginOptions;
+use crate::ExitCode;
+
+/// Fetch a source control blob by id via Thrift, optionally trying multiple storage locations to
+/// help debug fetch problems.
+#[derive(Parser, Debug)]
+#[clap(about = "Show EdenFS's data for a source control blob")]
+pub struct BlobCmd {
+    #[clap(flatten)]
+    origins: OriginOptions,
+
+    #[clap(parse(from_str = expand_path))]
+    /// The EdenFS mount point path
+    mount: PathBuf,
+
+    /// The blob ID
+    id: String,
+
+    #[clap(long, parse(from_str = expand_path))]
+    /// Write the blob contents to this file instead of stdout
+    output: Option<PathBuf>,
+}
+
+fn write_blob(output: &Option<PathBuf>, data: &[u8]) -> Result<()> {
+    match output {
+        Some(path) => std::fs::write(path, data).with_context(|| {
+            anyhow!("failed to write blob contents to {}", path.display())
+        }),
+        None => std::io::stdout()
+            .write_all(data)
+            .context("failed to write blob contents to stdout"),
+    }
+}
+
+fn print_blob_or_error(blob_or_error: &edenfs_thrift::ScmBlobOrError, output: &Option<PathBuf>) -> Result<()> {
+    match blob_or_error {
+        edenfs_thrift::ScmBlobOrError::blob(data) => write_blob(output, data),
+        edenfs_thrift::ScmBlobOrError::error(e) => {
+            eprintln!("ERROR fetching data: {:?}", e);
+            Ok(())
+        }
+        edenfs_thrift::ScmBlobOrError::UnknownField(_) => Err(anyhow!("unknown response variant")),
+    }
+}
+
+#[async_trait]
+impl crate::Subcommand for BlobCmd {
+    async fn run(&self) -> Result<ExitCode> {
+        let instance = EdenFsInstance::global();
+        let checkout = find_checkout(instance, &self.mount)
+            .with_context(|| anyhow!("unable to resolve checkout for {}", self.mount.display()))?;
+
+        let client = instance
+            .connect(None)
+            .await
+            .with_context(|| anyhow!("unable to establish Thrift connection to EdenFS server"))?;
+
+        let request = edenfs_thrift::DebugGetScmBlobRequest {
+            mountId: edenfs_thrift::MountId {
+                mountPoint: checkout.path().to_string_lossy().into_owned().into_bytes(),
+                ..Default::default()
+            },
+            id: parse_object_id(&self.id),
+            origins: self.origins.origins(),
+            ..Default::default()
+        };
+
+        let response = client
+            .debugGetBlob(&request)
+            .await
+            .with_context(|| anyhow!("debugGetBlob thrift call failed"))?;
+
+        if self.origins.all() {
+            for entry in &response.blobs {
+                println!("From {}:", origin_name(entry.origin));
+                print_blob_or_error(&entry.blob, &self.output)?;
+            }
+        } else if let Some(entry) = response.blobs.first() {
+            print_blob_or_error(&entry.blob, &self.output)?;
+        } else {
+            return Err(anyhow!("EdenFS returned no data for blob {}", self.id));
+        }
+
+        Ok(0)
+    }
+}
@@ -0,0 +1,438 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! edenfsctl debug changes-since
+
+use std::collections::BTreeMap;
+#[cfg(unix)]
+use std::ffi::OsStr;
+use std::fs;
+use std::io::Write;
+#[cfg(unix)]
+use std::os::unix::ffi::OsStringExt;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+use anyhow::Context;
+use anyhow::Result;
+use async_trait::async_trait;
+use atomicfile::atomic_write;
+use clap::Parser;
+use edenfs_client::checkout::get_mounts;
+use edenfs_client::journal::ChangeKind;
+use edenfs_client::journal::JournalDelta;
+use edenfs_client::EdenFsClient;
+use edenfs_client::EdenFsInstance;
+use hg_util::path::expand_path;
+use serde::Deserialize;
+use serde::Serialize;
+use thrift_types::edenfs as edenfs_thrift;
+
+use crate::ExitCode;
+
+/// Per-mount journal positions persisted by `--state-file`, so cron-style consumers don't need
+/// to track their own "since" position between runs.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PositionState {
+    positions: BTreeMap<String, i64>,
+}
+
+impl PositionState {
+    fn load(path: &Path) -> Result<Self> {
+        match fs::read(path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .with_context(|| anyhow!("could not parse state file {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| anyhow!("could not read state file {}", path.display())),
+        }
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_vec_pretty(self)?;
+        atomic_write(path, 0o644, false, |f| f.write_all(&json))
+            .map(|_| ())
+            .with_context(|| anyhow!("could not write state file {}", path.display()))
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct MountChanges {
+    changed_paths: Vec<String>,
+    created_paths: Vec<String>,
+    removed_paths: Vec<String>,
+    unclean_paths: Vec<String>,
+    to_sequence_number: i64,
+    /// Populated only when `--with-metadata` is given; keyed by path, covering every path
+    /// reported above. Absent entirely (rather than an empty map) when the flag isn't set, so
+    /// the JSON shape for existing consumers is unchanged.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<BTreeMap<String, PathMetadata>>,
+}
+
+/// `dtype`/`size`/`mtime` for a single path, batch-fetched via Thrift so callers (e.g. build
+/// systems reacting to a journal delta) don't need to immediately issue their own per-file
+/// `stat()` follow-up after every delta.
+///
+/// Fields are `None` when the underlying Thrift call errored for that path (most commonly
+/// ENOENT, e.g. for a path in `removed_paths` that no longer exists to stat).
+#[derive(Debug, Serialize)]
+struct PathMetadata {
+    dtype: Option<String>,
+    size: Option<u64>,
+    mtime_seconds: Option<i64>,
+    mtime_nanos: Option<i64>,
+}
+
+impl From<edenfs_thrift::FileDelta> for MountChanges {
+    fn from(delta: edenfs_thrift::FileDelta) -> Self {
+        let delta = JournalDelta::from(&delta);
+        let paths_of = |kind: ChangeKind| {
+            delta
+                .changes
+                .iter()
+                .filter(|change| change.kind == kind)
+                .map(|change| change.path.clone())
+                .collect()
+        };
+        Self {
+            changed_paths: paths_of(ChangeKind::Changed),
+            created_paths: paths_of(ChangeKind::Created),
+            removed_paths: paths_of(ChangeKind::Removed),
+            unclean_paths: paths_of(ChangeKind::Unclean),
+            to_sequence_number: delta.to_position.sequence_number,
+            metadata: None,
+        }
+    }
+}
+
+impl MountChanges {
+    /// Every path reported by this delta, across all four change kinds, in no particular order.
+    fn all_paths(&self) -> impl Iterator<Item = &String> {
+        self.changed_paths
+            .iter()
+            .chain(self.created_paths.iter())
+            .chain(self.removed_paths.iter())
+            .chain(self.unclean_paths.iter())
+    }
+}
+
+/// Number of top-churned directories to report in a summary, so huge deltas stay cheap to print.
+const TOP_DIRS_LIMIT: usize = 10;
+
+#[derive(Debug, Serialize)]
+struct MountChangesSummary {
+    changed_count: usize,
+    created_count: usize,
+    removed_count: usize,
+    unclean_count: usize,
+    top_directories: Vec<(String, usize)>,
+    to_sequence_number: i64,
+}
+
+/// Reported in place of [`MountChanges`]/[`MountChangesSummary`] when the requested journal
+/// position has fallen off the front of the journal, instead of surfacing the raw Thrift error.
+///
+/// `EdenError` carries no "earliest available position" - the only position this schema exposes
+/// is the *current* one - so `current_sequence_number` is the closest honest substitute: it's
+/// not where the journal now starts, but it is a position the caller knows is valid to diff
+/// against going forward.
+#[derive(Debug, Serialize)]
+struct TruncatedResult {
+    truncated: bool,
+    requested_since: i64,
+    current_sequence_number: i64,
+    hint: String,
+}
+
+impl TruncatedResult {
+    fn new(requested_since: i64, current_sequence_number: i64) -> Self {
+        Self {
+            truncated: true,
+            requested_since,
+            current_sequence_number,
+            hint: "the requested journal range has been compacted; rescan the checkout in \
+                   full, then query again with --since set to current_sequence_number"
+                .to_owned(),
+        }
+    }
+}
+
+/// Either the normal result for a mount, or a [`TruncatedResult`] when its journal no longer
+/// covers the requested range. Untagged so a successful result's JSON shape is unchanged from
+/// before this type existed; only the truncated case adds anything new.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum MountResult<T> {
+    Truncated(TruncatedResult),
+    Ok(T),
+}
+
+fn parent_dir(path: &str) -> String {
+    match path.rsplit_once('/') {
+        Some((dir, _)) => dir.to_owned(),
+        None => String::new(),
+    }
+}
+
+impl From<edenfs_thrift::FileDelta> for MountChangesSummary {
+    fn from(delta: edenfs_thrift::FileDelta) -> Self {
+        let delta = JournalDelta::from(&delta);
+        let count_of = |kind: ChangeKind| {
+            delta.changes.iter().filter(|change| change.kind == kind).count()
+        };
+
+        let mut churn: BTreeMap<String, usize> = BTreeMap::new();
+        for change in delta
+            .changes
+            .iter()
+            .filter(|change| change.kind != ChangeKind::Unclean)
+        {
+            *churn.entry(parent_dir(&change.path)).or_insert(0) += 1;
+        }
+
+        let mut top_directories: Vec<(String, usize)> = churn.into_iter().collect();
+        top_directories.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top_directories.truncate(TOP_DIRS_LIMIT);
+
+        Self {
+            changed_count: count_of(ChangeKind::Changed),
+            created_count: count_of(ChangeKind::Created),
+            removed_count: count_of(ChangeKind::Removed),
+            unclean_count: count_of(ChangeKind::Unclean),
+            top_directories,
+            to_sequence_number: delta.to_position.sequence_number,
+        }
+    }
+}
+
+/// Report the set of files changed in one or more EdenFS mounts since a given journal sequence
+/// number, so tooling that watches several checkouts doesn't need to spawn one process per mount.
+#[derive(Parser, Debug)]
+#[clap(about = "Get the files changed since a given journal position, for one or more mounts")]
+pub struct ChangesSinceCmd {
+    #[clap(parse(from_str = expand_path), multiple_values = true)]
+    /// Path(s) to the mount point(s) to query. Ignored if --all-mounts is given.
+    mount_points: Vec<PathBuf>,
+
+    #[clap(long)]
+    /// Query every currently mounted checkout instead of the paths given on the command line.
+    all_mounts: bool,
+
+    #[clap(long, default_value = "0")]
+    /// Journal sequence number to compute changes since. Defaults to 0, the start of the
+    /// mount's journal; if older entries have since been dropped, a truncated result is reported.
+    since: u64,
+
+    #[clap(long)]
+    /// Report aggregate statistics (counts by change type, top directories by churn) instead of
+    /// listing every changed path. Useful for telemetry and inspecting huge deltas.
+    summarize: bool,
+
+    #[clap(long, conflicts_with = "summarize")]
+    /// Enrich each reported path with dtype, size, and mtime, fetched via batched Thrift calls.
+    /// Saves consumers like build systems from issuing their own wave of per-file stats after
+    /// every delta. Not compatible with --summarize, which doesn't report individual paths.
+    with_metadata: bool,
+
+    #[clap(long, parse(from_str = expand_path))]
+    /// Persist each mount's journal position across runs. When given, the previous position
+    /// read from this file (if any) overrides --since for that mount, and the new position is
+    /// atomically written back on success, so incremental consumers don't need their own state
+    /// management.
+    state_file: Option<PathBuf>,
+}
+
+impl ChangesSinceCmd {
+    async fn mount_points(&self, instance: &EdenFsInstance) -> Result<Vec<PathBuf>> {
+        if self.all_mounts {
+            Ok(get_mounts(instance).await?.into_keys().collect())
+        } else if self.mount_points.is_empty() {
+            Err(anyhow!(
+                "no mount points given; pass paths explicitly or use --all-mounts"
+            ))
+        } else {
+            Ok(self.mount_points.clone())
+        }
+    }
+}
+
+fn to_thrift_mount_point(path: &Path) -> Vec<u8> {
+    #[cfg(unix)]
+    let bytes = <Path as AsRef<OsStr>>::as_ref(path).to_os_string().into_vec();
+    // SAFETY: paths on Windows are Unicode
+    #[cfg(windows)]
+    let bytes = path.to_string_lossy().into_owned().into_bytes();
+    bytes
+}
+
+#[async_trait]
+impl crate::Subcommand for ChangesSinceCmd {
+    async fn run(&self) -> Result<ExitCode> {
+        let instance = EdenFsInstance::global();
+        let mount_points = self.mount_points(instance).await?;
+        let client = instance
+            .connect(None)
+            .await
+            .with_context(|| anyhow!("unable to establish Thrift connection to EdenFS server"))?;
+
+        let mut state = match &self.state_file {
+            Some(path) => PositionState::load(path)?,
+            None => PositionState::default(),
+        };
+
+        let mut changes = BTreeMap::new();
+        let mut summaries = BTreeMap::new();
+        let mut had_error = false;
+
+        for mount_point in mount_points {
+            let mount_bytes = to_thrift_mount_point(&mount_point);
+            let display = mount_point.display().to_string();
+            let since = state
+                .positions
+                .get(&display)
+                .copied()
+                .unwrap_or(self.since as i64);
+
+            let from_position = match client.getCurrentJournalPosition(&mount_bytes).await {
+                Ok(mut position) => {
+                    position.sequenceNumber = since;
+                    position
+                }
+                Err(e) => {
+                    eprintln!("error getting journal position for {}: {:?}", display, e);
+                    had_error = true;
+                    continue;
+                }
+            };
+
+            match client
+                .getFilesChangedSince(&mount_bytes, &from_position)
+                .await
+            {
+                Ok(delta) if self.summarize => {
+                    state
+                        .positions
+                        .insert(display.clone(), delta.toPosition.sequenceNumber);
+                    summaries.insert(display, MountResult::Ok(MountChangesSummary::from(delta)));
+                }
+                Ok(delta) => {
+                    state
+                        .positions
+                        .insert(display.clone(), delta.toPosition.sequenceNumber);
+                    let mut mount_changes = MountChanges::from(delta);
+                    if self.with_metadata {
+                        let paths: Vec<String> = mount_changes.all_paths().cloned().collect();
+                        mount_changes.metadata =
+                            Some(fetch_metadata(&client, &mount_bytes, &paths).await);
+                    }
+                    changes.insert(display, MountResult::Ok(mount_changes));
+                }
+                Err(e) if is_journal_truncated_error(&e) => {
+                    // The daemon's current position is the closest thing to an "earliest
+                    // available" anchor this schema exposes; re-fetch it rather than reusing
+                    // `from_position`, since it may have moved on while we were erroring out.
+                    let current_sequence_number = client
+                        .getCurrentJournalPosition(&mount_bytes)
+                        .await
+                        .map(|p| p.sequenceNumber)
+                        .unwrap_or(from_position.sequenceNumber);
+                    state
+                        .positions
+                        .insert(display.clone(), current_sequence_number);
+                    let truncated = MountResult::Truncated(TruncatedResult::new(
+                        from_position.sequenceNumber,
+                        current_sequence_number,
+                    ));
+                    if self.summarize {
+                        summaries.insert(display, truncated);
+                    } else {
+                        changes.insert(display, truncated);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("error getting changes for {}: {:?}", display, e);
+                    had_error = true;
+                }
+            }
+        }
+
+        if self.summarize {
+            println!("{}", serde_json::to_string_pretty(&summaries)?);
+        } else {
+            println!("{}", serde_json::to_string_pretty(&changes)?);
+        }
+
+        if let Some(path) = &self.state_file {
+            state.save(path)?;
+        }
+
+        Ok(if had_error { 1 } else { 0 })
+    }
+}
+
+/// Batch-fetch dtype/size/mtime for `paths` via `getEntryInformation`/`getFileInformation`, both
+/// of which return one result per input path in the same order. A path that errors on either
+/// call (most commonly ENOENT, for a path that no longer exists) gets `None` for the fields that
+/// call would have supplied, rather than dropping the path from the result.
+async fn fetch_metadata(
+    client: &EdenFsClient,
+    mount_bytes: &Vec<u8>,
+    paths: &[String],
+) -> BTreeMap<String, PathMetadata> {
+    if paths.is_empty() {
+        return BTreeMap::new();
+    }
+
+    let thrift_paths: Vec<Vec<u8>> = paths.iter().map(|path| path.clone().into_bytes()).collect();
+    let sync = edenfs_thrift::SyncBehavior::default();
+
+    let entries = client
+        .getEntryInformation(mount_bytes, &thrift_paths, &sync)
+        .await
+        .unwrap_or_default();
+    let files = client
+        .getFileInformation(mount_bytes, &thrift_paths, &sync)
+        .await
+        .unwrap_or_default();
+
+    paths
+        .iter()
+        .enumerate()
+        .map(|(i, path)| {
+            let dtype = match entries.get(i) {
+                Some(edenfs_thrift::EntryInformationOrError::info(info)) => {
+                    Some(format!("{:?}", info.dtype))
+                }
+                _ => None,
+            };
+            let (size, mtime_seconds, mtime_nanos) = match files.get(i) {
+                Some(edenfs_thrift::FileInformationOrError::info(info)) => (
+                    Some(info.size as u64),
+                    Some(info.mtime.seconds),
+                    Some(info.mtime.nanoSeconds),
+                ),
+                _ => (None, None, None),
+            };
+            (
+                path.clone(),
+                PathMetadata {
+                    dtype,
+                    size,
+                    mtime_seconds,
+                    mtime_nanos,
+                },
+            )
+        })
+        .collect()
+}
+
+fn is_journal_truncated_error<E: std::fmt::Debug>(error: &E) -> bool {
+    // The generated Thrift error enum wraps `EdenError` behind a service-specific variant; we
+    // can't name that type without codegen, so fall back to matching on its error message.
+    format!("{:?}", error).contains("JOURNAL_TRUNCATED")
+}
@@ -0,0 +1,105 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! Shared bits for `edenfsctl debug blob` and `edenfsctl debug tree`: parsing the
+//! `--object-cache-only`/`--local-store-only`/`--hgcache-only`/`--remote-only`/`--all` origin
+//! flags into a `DataFetchOriginSet`, and printing where data came from.
+
+use clap::Parser;
+use thrift_types::edenfs as edenfs_thrift;
+
+#[derive(Parser, Debug)]
+pub struct OriginOptions {
+    #[clap(
+        short = 'c',
+        long,
+        help = "Only check the in-memory cache",
+        conflicts_with_all = &["local-store-only", "hgcache-only", "remote-only", "all"]
+    )]
+    object_cache_only: bool,
+
+    #[clap(
+        short = 'l',
+        long,
+        help = "Only check the local store on disk",
+        conflicts_with_all = &["object-cache-only", "hgcache-only", "remote-only", "all"]
+    )]
+    local_store_only: bool,
+
+    #[clap(
+        short = 'd',
+        long,
+        help = "Only check the hgcache",
+        conflicts_with_all = &["object-cache-only", "local-store-only", "remote-only", "all"]
+    )]
+    hgcache_only: bool,
+
+    #[clap(
+        short,
+        long,
+        help = "Only fetch from the remote backing store",
+        conflicts_with_all = &["object-cache-only", "local-store-only", "hgcache-only", "all"]
+    )]
+    remote_only: bool,
+
+    #[clap(
+        short,
+        long,
+        help = "Fetch from every location and display the origin and contents of each",
+        conflicts_with_all = &["object-cache-only", "local-store-only", "hgcache-only", "remote-only"]
+    )]
+    all: bool,
+}
+
+impl OriginOptions {
+    pub fn origins(&self) -> edenfs_thrift::DataFetchOriginSet {
+        use edenfs_thrift::DataFetchOrigin::*;
+
+        let origin = if self.object_cache_only {
+            MEMORY_CACHE
+        } else if self.local_store_only {
+            DISK_CACHE
+        } else if self.hgcache_only {
+            LOCAL_BACKING_STORE
+        } else if self.remote_only {
+            REMOTE_BACKING_STORE
+        } else if self.all {
+            return (MEMORY_CACHE as i64
+                | DISK_CACHE as i64
+                | LOCAL_BACKING_STORE as i64
+                | REMOTE_BACKING_STORE as i64
+                | ANYWHERE as i64) as edenfs_thrift::DataFetchOriginSet;
+        } else {
+            ANYWHERE
+        };
+
+        origin as edenfs_thrift::DataFetchOriginSet
+    }
+
+    pub fn all(&self) -> bool {
+        self.all
+    }
+}
+
+pub fn origin_name(origin: edenfs_thrift::DataFetchOrigin) -> &'static str {
+    use edenfs_thrift::DataFetchOrigin::*;
+
+    match origin {
+        MEMORY_CACHE => "object cache",
+        DISK_CACHE => "local store",
+        LOCAL_BACKING_STORE => "hgcache",
+        REMOTE_BACKING_STORE => "servers",
+        ANYWHERE => "EdenFS complete data fetching behavior",
+        _ => "<unknown>",
+    }
+}
+
+/// EdenFS stores object ids as arbitrary-length human-readable strings, not raw hashes, so we
+/// just take the argument's bytes directly rather than hex-decoding it.
+pub fn parse_object_id(id: &str) -> Vec<u8> {
+    id.as_bytes().to_vec()
+}
@@ -0,0 +1,85 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! edenfsctl debug reload-config
+//!
+//! `edenfsctl reloadconfig` already exists as a top-level command, but it regenerates the
+//! *dynamic* (Configerator-sourced) system config via `edenfs_config_manager`; it never talks to
+//! the running daemon. This instead asks the already-running daemon itself to re-read its
+//! system/user config files from disk (via the `reloadConfig` Thrift call) and reports which
+//! config values actually changed as a result, then re-fetches the config a second time to
+//! verify the new values stuck rather than being a one-off transient read.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use clap::Parser;
+use edenfs_client::EdenFsClient;
+use edenfs_client::EdenFsInstance;
+use thrift_types::edenfs::GetConfigParams;
+use thrift_types::edenfs_config::ConfigValue;
+
+use crate::ExitCode;
+
+#[derive(Parser, Debug)]
+#[clap(about = "Ask the EdenFS daemon to reload its config from disk and report what changed")]
+pub struct ReloadConfigCmd {}
+
+async fn get_config(client: &EdenFsClient) -> Result<BTreeMap<String, ConfigValue>> {
+    let params: GetConfigParams = Default::default();
+    Ok(client.getConfig(&params).await?.values.into_iter().collect())
+}
+
+#[async_trait]
+impl crate::Subcommand for ReloadConfigCmd {
+    async fn run(&self) -> Result<ExitCode> {
+        let client = EdenFsInstance::global().connect(None).await?;
+
+        let before = get_config(&client).await?;
+        client.reloadConfig().await?;
+        let after = get_config(&client).await?;
+
+        let mut changed_keys: Vec<&String> = after
+            .iter()
+            .filter(|(key, value)| before.get(*key).map(|v| &v.parsedValue) != Some(&value.parsedValue))
+            .map(|(key, _)| key)
+            .collect();
+        changed_keys.sort();
+
+        if changed_keys.is_empty() {
+            println!("No config values changed.");
+        } else {
+            println!("{} config value(s) changed:", changed_keys.len());
+            for key in &changed_keys {
+                let old = before.get(*key).map(|v| v.parsedValue.as_str()).unwrap_or("<unset>");
+                let new = after.get(*key).map(|v| v.parsedValue.as_str()).unwrap_or("<unset>");
+                println!("  {}: {:?} -> {:?}", key, old, new);
+            }
+        }
+
+        // Re-fetch once more to confirm the new values are actually in effect, rather than
+        // being a transient result of the reload itself.
+        let verify = get_config(&client).await?;
+        let unstable: Vec<&String> = changed_keys
+            .iter()
+            .filter(|key| after.get(**key).map(|v| &v.parsedValue) != verify.get(**key).map(|v| &v.parsedValue))
+            .copied()
+            .collect();
+
+        if !unstable.is_empty() {
+            eprintln!(
+                "warning: {} value(s) did not remain stable after reload: {:?}",
+                unstable.len(),
+                unstable
+            );
+            return Ok(1);
+        }
+
+        Ok(0)
+    }
+}
@@ -0,0 +1,76 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! edenfsctl debug thrift
+
+use anyhow::anyhow;
+use anyhow::Context;
+use anyhow::Result;
+use async_trait::async_trait;
+use clap::Parser;
+use edenfs_client::EdenFsClient;
+use edenfs_client::EdenFsInstance;
+use serde_json::Value;
+
+use crate::ExitCode;
+
+/// Invoke one of a small set of known EdenFS Thrift methods by name, with JSON-encoded
+/// arguments, and print the JSON response. Meant for poking at a new daemon API from the CLI
+/// before a dedicated `edenfsctl debug` subcommand exists for it.
+///
+/// fbthrift's Rust codegen has no runtime method reflection, so this can't dispatch to an
+/// arbitrary EdenService method purely by name: each method's call still has to be written out
+/// in `call` below. This only covers a handful of already-used-elsewhere debug-oriented
+/// methods; add a case there as more methods need ad hoc access here.
+#[derive(Parser, Debug)]
+#[clap(about = "Invoke an EdenFS Thrift method by name with JSON arguments")]
+pub struct ThriftCmd {
+    /// The Thrift method name, e.g. `debugClearLocalStoreCaches` or `getRegexCounters`
+    method: String,
+
+    /// JSON-encoded arguments for the method, as a JSON array
+    #[clap(default_value = "[]")]
+    args: String,
+}
+
+async fn call(client: &EdenFsClient, method: &str, args: &[Value]) -> Result<Value> {
+    match method {
+        "debugClearLocalStoreCaches" => {
+            client.debugClearLocalStoreCaches().await?;
+            Ok(Value::Null)
+        }
+        "debugCompactLocalStorage" => {
+            client.debugCompactLocalStorage().await?;
+            Ok(Value::Null)
+        }
+        "getRegexCounters" => {
+            let pattern = args
+                .first()
+                .and_then(Value::as_str)
+                .ok_or_else(|| anyhow!("getRegexCounters takes one argument: a regex string"))?;
+            let counters = client.getRegexCounters(pattern).await?;
+            Ok(serde_json::to_value(counters)?)
+        }
+        _ => Err(anyhow!(
+            "unknown or unsupported method {:?}; only a curated set of debug methods is wired \
+             up here, since Thrift methods can't be dispatched by name at runtime",
+            method
+        )),
+    }
+}
+
+#[async_trait]
+impl crate::Subcommand for ThriftCmd {
+    async fn run(&self) -> Result<ExitCode> {
+        let args: Vec<Value> = serde_json::from_str(&self.args)
+            .with_context(|| anyhow!("failed to parse args as a JSON array"))?;
+        let client = EdenFsInstance::global().connect(None).await?;
+        let response = call(&client, &self.method, &args).await?;
+        println!("{}", serde_json::to_string_pretty(&response)?);
+        Ok(0)
+    }
+}
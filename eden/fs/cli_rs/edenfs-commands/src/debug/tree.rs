@@ -0,0 +1,107 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! edenfsctl debug tree
+
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+use anyhow::Context;
+use anyhow::Result;
+use async_trait::async_trait;
+use clap::Parser;
+use edenfs_client::checkout::find_checkout;
+use edenfs_client::EdenFsInstance;
+use hg_util::path::expand_path;
+use thrift_types::edenfs as edenfs_thrift;
+
+use crate::debug::object::origin_name;
+use crate::debug::object::parse_object_id;
+use crate::debug::object::OriginOptions;
+use crate::ExitCode;
+
+/// Fetch a source control tree by id via Thrift, optionally trying multiple storage locations to
+/// help debug fetch problems.
+#[derive(Parser, Debug)]
+#[clap(about = "Show EdenFS's data for a source control tree")]
+pub struct TreeCmd {
+    #[clap(flatten)]
+    origins: OriginOptions,
+
+    #[clap(parse(from_str = expand_path))]
+    /// The EdenFS mount point path
+    mount: PathBuf,
+
+    /// The tree ID
+    id: String,
+}
+
+fn print_entries(entries: &[edenfs_thrift::ScmTreeEntry]) {
+    for entry in entries {
+        println!(
+            "{} {}",
+            String::from_utf8_lossy(&entry.name),
+            hex::encode(&entry.id),
+        );
+    }
+}
+
+fn print_tree_or_error(tree_or_error: &edenfs_thrift::ScmTreeOrError) -> Result<()> {
+    match tree_or_error {
+        edenfs_thrift::ScmTreeOrError::treeEntries(entries) => {
+            print_entries(entries);
+            Ok(())
+        }
+        edenfs_thrift::ScmTreeOrError::error(e) => {
+            eprintln!("ERROR fetching data: {:?}", e);
+            Ok(())
+        }
+        edenfs_thrift::ScmTreeOrError::UnknownField(_) => Err(anyhow!("unknown response variant")),
+    }
+}
+
+#[async_trait]
+impl crate::Subcommand for TreeCmd {
+    async fn run(&self) -> Result<ExitCode> {
+        let instance = EdenFsInstance::global();
+        let checkout = find_checkout(instance, &self.mount)
+            .with_context(|| anyhow!("unable to resolve checkout for {}", self.mount.display()))?;
+
+        let client = instance
+            .connect(None)
+            .await
+            .with_context(|| anyhow!("unable to establish Thrift connection to EdenFS server"))?;
+
+        let request = edenfs_thrift::DebugGetScmTreeRequest {
+            mountId: edenfs_thrift::MountId {
+                mountPoint: checkout.path().to_string_lossy().into_owned().into_bytes(),
+                ..Default::default()
+            },
+            id: parse_object_id(&self.id),
+            origins: self.origins.origins(),
+            ..Default::default()
+        };
+
+        let response = client
+            .debugGetTree(&request)
+            .await
+            .with_context(|| anyhow!("debugGetTree thrift call failed"))?;
+
+        if self.origins.all() {
+            for entry in &response.trees {
+                println!("From {}:", origin_name(entry.origin));
+                print_tree_or_error(&entry.scmTreeData)?;
+            }
+        } else if let Some(entry) = response.trees.first() {
+            print_tree_or_error(&entry.scmTreeData)?;
+        } else {
+            return Err(anyhow!("EdenFS returned no data for tree {}", self.id));
+        }
+
+        Ok(0)
+    }
+}
@@ -11,8 +11,6 @@ use std::collections::BTreeSet;
 use std::collections::HashSet;
 use std::fmt;
 use std::fs;
-#[cfg(target_os = "macos")]
-use std::io::IsTerminal;
 use std::path::PathBuf;
 #[cfg(target_os = "macos")]
 use std::process::Command;
@@ -31,8 +29,6 @@ use comfy_table::CellAlignment;
 use comfy_table::Color;
 use comfy_table::Row;
 use comfy_table::Table;
-#[cfg(target_os = "macos")]
-use dialoguer::Confirm;
 use edenfs_client::checkout::find_checkout;
 use edenfs_client::checkout::EdenFsCheckout;
 use edenfs_client::redirect;
@@ -937,14 +933,8 @@ impl crate::Subcommand for DiskUsageCmd {
             }
 
             #[cfg(target_os = "macos")]
-            if self.purgeable
-                && std::io::stdin().is_terminal()
-                && aggregated_usage_counts.purgeable_space > 0
-            {
-                if Confirm::new()
-                    .with_prompt("Would you like to clear purgeable space?")
-                    .interact()?
-                {
+            if self.purgeable && aggregated_usage_counts.purgeable_space > 0 {
+                if crate::prompt::confirm("Would you like to clear purgeable space?", false)? {
                     clear_purgeable_space(aggregated_usage_counts.purgeable_space.to_string())?;
                 }
             }
@@ -0,0 +1,137 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! edenfsctl fsck
+//!
+//! The actual overlay consistency check (scanning for orphaned/corrupt entries and repairing
+//! them) lives in the `eden_fsck` C++ binary, not in this CLI; `eden/fs/cli/main.py`'s `FsckCmd`
+//! doesn't reimplement that logic either, it just locates each checkout's overlay directory and
+//! shells out to `eden_fsck` for it. Reimplementing overlay scanning/repair itself in Rust is a
+//! separate, much larger effort (it would duplicate `eden/fs/inodes/fscatalog/eden_fsck.cpp`)
+//! that can't be done honestly without the ability to build and test it here, so this only ports
+//! the CLI-side checkout discovery and invocation, which is a faithful, testable port on its own.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+use anyhow::Result;
+use async_trait::async_trait;
+use clap::Parser;
+use edenfs_client::checkout::find_checkout;
+use edenfs_client::EdenFsInstance;
+use subprocess::Exec;
+use subprocess::ExitStatus;
+
+use crate::ExitCode;
+
+const EXIT_OK: ExitCode = 0;
+
+#[derive(Parser, Debug)]
+#[clap(about = "Perform a filesystem check for EdenFS")]
+pub struct FsckCmd {
+    /// Force fsck to scan for errors even on checkouts that appear to currently be mounted. It
+    /// will not attempt to fix any problems, but will only scan and report possible issues.
+    #[clap(long)]
+    force: bool,
+
+    /// Only report errors, and do not attempt to fix any problems found.
+    #[clap(long, short = 'n', alias = "check-only")]
+    check_only: bool,
+
+    /// Print more verbose information about issues found.
+    #[clap(long, short = 'v')]
+    verbose: bool,
+
+    /// The path to an EdenFS checkout to verify. If omitted, all configured checkouts are
+    /// checked.
+    #[clap(parse(from_os_str))]
+    path: Vec<PathBuf>,
+}
+
+/// Locate the `eden_fsck` binary. In the integration test environment `EDENFS_FSCK` points to
+/// the binary under test (mirrors `mkscratch_bin()` in edenfs-client/src/redirect.rs).
+fn fsck_command() -> PathBuf {
+    match std::env::var("EDENFS_FSCK") {
+        Ok(s) => PathBuf::from(s),
+        Err(_) => PathBuf::from("/usr/local/libexec/eden/eden_fsck"),
+    }
+}
+
+impl FsckCmd {
+    /// Run `eden_fsck` against a single checkout's overlay, given its state (data) directory.
+    fn check_one(&self, checkout_path: &Path, data_dir: &Path) -> Result<ExitCode> {
+        println!("Checking {}...", checkout_path.display());
+        let overlay_path = data_dir.join("local");
+
+        let mut cmd = Exec::cmd(fsck_command())
+            .arg(&overlay_path)
+            .arg(format!("--dry-run={}", self.check_only))
+            .arg(format!("--force={}", self.force));
+        if self.verbose {
+            cmd = cmd.arg("--verbose");
+        }
+
+        let status = cmd
+            .join()
+            .map_err(|e| anyhow!("failed to run {}: {}", fsck_command().display(), e))?;
+
+        Ok(match status {
+            ExitStatus::Exited(code) => code as ExitCode,
+            ExitStatus::Signaled(_) | ExitStatus::Other(_) | ExitStatus::Undetermined => 1,
+        })
+    }
+
+    fn check_explicit_paths(&self, instance: &EdenFsInstance) -> Result<Vec<ExitCode>> {
+        let mut results = Vec::new();
+        for path in &self.path {
+            // Check to see if this looks like an EdenFS checkout state directory directly,
+            // as opposed to a mounted checkout path.
+            if path.join("local").join("info").exists() && path.join("config.toml").exists() {
+                results.push(self.check_one(path, path)?);
+            } else {
+                let checkout = find_checkout(instance, path)?;
+                results.push(self.check_one(&checkout.path(), &checkout.data_dir())?);
+            }
+        }
+        Ok(results)
+    }
+
+    fn check_all(&self, instance: &EdenFsInstance) -> Result<Vec<ExitCode>> {
+        let mut results = Vec::new();
+        for (checkout_path, checkout_name) in instance.get_configured_mounts_map()? {
+            let data_dir = instance.config_directory(&checkout_name);
+            results.push(self.check_one(&checkout_path, &data_dir)?);
+        }
+        Ok(results)
+    }
+}
+
+#[async_trait]
+impl crate::Subcommand for FsckCmd {
+    async fn run(&self) -> Result<ExitCode> {
+        if cfg!(windows) {
+            eprintln!("`edenfsctl fsck` is not supported on Windows.");
+            eprintln!("If you are looking to fix your EdenFS mount, try `edenfsctl doctor`.");
+            return Ok(1);
+        }
+
+        let instance = EdenFsInstance::global();
+        let results = if self.path.is_empty() {
+            let results = self.check_all(instance)?;
+            if results.is_empty() {
+                eprintln!("No EdenFS checkouts are configured.  Nothing to check.");
+                return Ok(EXIT_OK);
+            }
+            results
+        } else {
+            self.check_explicit_paths(instance)?
+        };
+
+        Ok(results.into_iter().max().unwrap_or(EXIT_OK))
+    }
+}
@@ -24,14 +24,19 @@ use tracing::Level;
 mod config;
 mod debug;
 mod du;
+mod fsck;
 mod gc;
 mod handles;
 mod list;
 mod minitop;
 mod pid;
 mod prefetch_profile;
+mod progress;
+mod prompt;
 mod redirect;
 mod status;
+mod stop;
+mod telemetry;
 mod top;
 mod uptime;
 mod util;
@@ -80,6 +85,10 @@ pub struct MainCommand {
     #[clap(global = true, long)]
     pub debug: bool,
 
+    /// Assume yes to all interactive prompts (e.g. confirmations before destructive operations)
+    #[clap(global = true, long, alias = "no-prompt", short = 'y')]
+    pub yes: bool,
+
     #[clap(subcommand)]
     pub subcommand: TopLevelSubcommand,
 }
@@ -102,6 +111,7 @@ pub enum TopLevelSubcommand {
     Config(crate::config::CliConfigCmd),
     Debug(crate::debug::DebugCmd),
     Du(crate::du::DiskUsageCmd),
+    Fsck(crate::fsck::FsckCmd),
     Fsconfig(crate::config::FsConfigCmd),
     // Gc(crate::gc::GcCmd),
     List(crate::list::ListCmd),
@@ -109,6 +119,7 @@ pub enum TopLevelSubcommand {
     Pid(crate::pid::PidCmd),
     #[clap(subcommand, alias = "pp")]
     PrefetchProfile(crate::prefetch_profile::PrefetchCmd),
+    Progress(crate::progress::ProgressCmd),
     #[clap(subcommand, alias = "redir")]
     Redirect(crate::redirect::RedirectCmd),
     #[cfg(target_os = "windows")]
@@ -116,6 +127,7 @@ pub enum TopLevelSubcommand {
     Reloadconfig(crate::config::ReloadConfigCmd),
     #[clap(alias = "health")]
     Status(crate::status::StatusCmd),
+    Stop(crate::stop::StopCmd),
     // Top(crate::top::TopCmd),
     Uptime(crate::uptime::UptimeCmd),
 }
@@ -130,15 +142,18 @@ impl TopLevelSubcommand {
             Fsconfig(cmd) => cmd,
             Debug(cmd) => cmd,
             Du(cmd) => cmd,
+            Fsck(cmd) => cmd,
             // Gc(cmd) => cmd,
             List(cmd) => cmd,
             Minitop(cmd) => cmd,
             Pid(cmd) => cmd,
             PrefetchProfile(cmd) => cmd,
+            Progress(cmd) => cmd,
             Redirect(cmd) => cmd,
             #[cfg(target_os = "windows")]
             Handles(cmd) => cmd,
             Status(cmd) => cmd,
+            Stop(cmd) => cmd,
             // Top(cmd) => cmd,
             Uptime(cmd) => cmd,
         }
@@ -151,6 +166,7 @@ impl TopLevelSubcommand {
             TopLevelSubcommand::Config(_) => "config",
             TopLevelSubcommand::Debug(_) => "debug",
             TopLevelSubcommand::Du(_) => "du",
+            TopLevelSubcommand::Fsck(_) => "fsck",
             TopLevelSubcommand::Fsconfig(_) => "fsconfig",
             //TopLevelSubcommand::Gc(_) => "gc",
             #[cfg(target_os = "windows")]
@@ -159,9 +175,11 @@ impl TopLevelSubcommand {
             TopLevelSubcommand::Minitop(_) => "minitop",
             TopLevelSubcommand::Pid(_) => "pid",
             TopLevelSubcommand::PrefetchProfile(_) => "prefetch-profile",
+            TopLevelSubcommand::Progress(_) => "progress",
             TopLevelSubcommand::Redirect(_) => "redirect",
             TopLevelSubcommand::Reloadconfig(_) => "reloadconfig",
             TopLevelSubcommand::Status(_) => "status",
+            TopLevelSubcommand::Stop(_) => "stop",
             //TopLevelSubcommand::Top(_) => "top",
             TopLevelSubcommand::Uptime(_) => "uptime",
         }
@@ -252,13 +270,25 @@ impl MainCommand {
     async fn dispatch(self) -> Result<ExitCode> {
         event!(Level::TRACE, cmd = ?self, "Dispatching");
 
+        crate::prompt::set_assume_yes(self.yes);
+
         EdenFsInstance::init(
             self.get_config_dir()?,
             get_etc_eden_dir(&self.etc_eden_dir),
             self.get_home_dir(),
         );
         // Use EdenFsInstance::global() to access the instance from now on
-        self.subcommand.run().await
+        let instance = EdenFsInstance::global();
+        let command_name = self.subcommand.name();
+        let start = std::time::Instant::now();
+        let result = self.subcommand.run().await;
+        let exit_class = match &result {
+            Ok(0) => "success",
+            Ok(_) => "failure",
+            Err(_) => "error",
+        };
+        telemetry::record_invocation(instance, command_name, start.elapsed(), exit_class).await;
+        result
     }
 }
 
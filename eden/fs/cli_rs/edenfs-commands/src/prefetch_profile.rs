@@ -31,7 +31,7 @@ use crate::util::expand_path_or_cwd;
 use crate::ExitCode;
 use crate::Subcommand;
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 pub struct ActivationOptions {
     #[clap(short, long, help = "Print extra info and warnings.")]
     verbose: bool,
@@ -44,7 +44,7 @@ pub struct ActivationOptions {
     checkout: PathBuf,
 }
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 pub struct FetchOptions {
     #[clap(flatten)]
     options: ActivationOptions,
@@ -127,6 +127,12 @@ pub enum PrefetchCmd {
         options: ActivationOptions,
         #[clap(help = "Profile to activate.")]
         profile_name: String,
+        #[clap(
+            long,
+            help = "Immediately fetch the files and directories for this \
+            profile, instead of waiting for the next `prefetch-profile fetch`."
+        )]
+        fetch: bool,
     },
     #[clap(hide = true)]
     ActivatePredictive {
@@ -279,7 +285,12 @@ impl PrefetchCmd {
         }
     }
 
-    async fn activate(&self, options: &ActivationOptions, profile_name: &str) -> Result<ExitCode> {
+    async fn activate(
+        &self,
+        options: &ActivationOptions,
+        profile_name: &str,
+        fetch: bool,
+    ) -> Result<ExitCode> {
         let instance = EdenFsInstance::global();
         let client_name = instance.client_name(&options.checkout).with_context(|| {
             anyhow!(
@@ -311,6 +322,21 @@ impl PrefetchCmd {
             }
         };
 
+        if fetch {
+            self.fetch(
+                &vec![profile_name.to_string()],
+                &FetchOptions {
+                    options: options.clone(),
+                    directories_only: false,
+                    foreground: false,
+                    commits: vec![],
+                    predict_commits: false,
+                },
+                false,
+            )
+            .await?;
+        }
+
         Ok(0)
     }
 
@@ -575,7 +601,8 @@ impl Subcommand for PrefetchCmd {
             Self::Activate {
                 options,
                 profile_name,
-            } => self.activate(options, profile_name).await,
+                fetch,
+            } => self.activate(options, profile_name, *fetch).await,
             Self::ActivatePredictive { options, num_dirs } => {
                 self.activate_predictive(options, *num_dirs).await
             }
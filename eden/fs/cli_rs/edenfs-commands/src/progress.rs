@@ -0,0 +1,114 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! edenfsctl progress
+//!
+//! EdenFS doesn't expose a dedicated "checkout progress" Thrift endpoint (no files-materialized
+//! / files-remaining counters), so this polls the two signals that do exist: `getScmStatusV2`
+//! fails with `CHECKOUT_IN_PROGRESS` for as long as a checkout is running (see
+//! `EdenMount::diff` in the daemon), and the `store.sapling.pending_import.*` counters (already
+//! used by `edenfsctl minitop`) give a rough sense of how much object-import work is still
+//! outstanding while that's true. This is an approximation, not an exact file count.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use clap::Parser;
+use edenfs_client::checkout::find_checkout;
+use edenfs_client::EdenFsClient;
+use edenfs_client::EdenFsInstance;
+use edenfs_utils::bytes_from_path;
+use thrift_types::edenfs::GetScmStatusParams;
+use tokio::time;
+
+use crate::util::expand_path_or_cwd;
+use crate::ExitCode;
+
+const PENDING_IMPORT_COUNTER_REGEX: &str = r"store\.sapling\.pending_import\..*\.count";
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Parser, Debug)]
+#[clap(about = "Report the progress of an in-flight checkout")]
+pub struct ProgressCmd {
+    /// The EdenFS mount point path. Defaults to the current directory.
+    #[clap(long, parse(from_os_str))]
+    mount: Option<PathBuf>,
+
+    /// Keep polling until the checkout finishes instead of reporting once and exiting.
+    #[clap(long)]
+    wait: bool,
+}
+
+/// Sum of all `store.sapling.pending_import.*.count` counters, as a rough proxy for how many
+/// object fetches are still outstanding.
+async fn pending_import_count(client: &EdenFsClient) -> Result<i64> {
+    let counters = client.getRegexCounters(PENDING_IMPORT_COUNTER_REGEX).await?;
+    Ok(counters.values().sum())
+}
+
+fn is_checkout_in_progress_error<E: std::fmt::Debug>(error: &E) -> bool {
+    // The generated Thrift error enum wraps `EdenError` behind a service-specific variant; we
+    // can't name that type without codegen, so fall back to matching on its error message (see
+    // the same trick in debug/changes_since.rs).
+    format!("{:?}", error).contains("CHECKOUT_IN_PROGRESS")
+}
+
+impl ProgressCmd {
+    /// Returns `true` if a checkout is currently in progress on this mount.
+    async fn poll_once(&self, client: &EdenFsClient, mount: &PathBuf, commit: &[u8]) -> Result<bool> {
+        let result = client
+            .getScmStatusV2(&GetScmStatusParams {
+                mountPoint: bytes_from_path(mount.clone())?,
+                commit: commit.to_vec(),
+                listIgnored: false,
+                ..Default::default()
+            })
+            .await;
+
+        match result {
+            Ok(_) => Ok(false),
+            Err(e) if is_checkout_in_progress_error(&e) => Ok(true),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[async_trait]
+impl crate::Subcommand for ProgressCmd {
+    async fn run(&self) -> Result<ExitCode> {
+        let instance = EdenFsInstance::global();
+        let mount = match &self.mount {
+            Some(mount) => mount.clone(),
+            None => expand_path_or_cwd("")?,
+        };
+        let checkout = find_checkout(instance, &mount)?;
+        let commit = checkout.get_snapshot()?.working_copy_parent.into_bytes();
+        let client = instance.connect(None).await?;
+
+        loop {
+            let in_progress = self.poll_once(&client, &mount, &commit).await?;
+            if !in_progress {
+                println!("checkout complete");
+                return Ok(0);
+            }
+
+            let pending = pending_import_count(&client).await.unwrap_or(0);
+            println!("checkout in progress: {} object(s) still being fetched", pending);
+
+            if !self.wait {
+                return Ok(0);
+            }
+            time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    fn get_mount_path_override(&self) -> Option<PathBuf> {
+        self.mount.clone()
+    }
+}
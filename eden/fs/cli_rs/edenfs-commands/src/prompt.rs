@@ -0,0 +1,48 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! Shared interactive confirmation helper used by destructive commands (e.g. `redirect`, `du
+//! --purgeable`) so that they all interpret `-y`/`--no-prompt` and non-interactive terminals the
+//! same way, instead of each command growing its own `is_terminal()` + `dialoguer::Confirm`
+//! dance.
+
+use std::io::IsTerminal as _;
+use std::sync::OnceLock;
+
+use anyhow::Result;
+use dialoguer::Confirm;
+
+static ASSUME_YES: OnceLock<bool> = OnceLock::new();
+
+/// Record whether the user passed `-y`/`--no-prompt` on the command line. Called once from
+/// [`crate::MainCommand::dispatch`] before any subcommand runs.
+pub fn set_assume_yes(assume_yes: bool) {
+    // Only the top-level dispatch should set this, but tests may run more than one command in
+    // the same process, so don't panic if it's already set.
+    let _ = ASSUME_YES.set(assume_yes);
+}
+
+fn assume_yes() -> bool {
+    ASSUME_YES.get().copied().unwrap_or(false)
+}
+
+/// Ask the user a yes/no question. Returns `true` without prompting when `-y`/`--no-prompt` was
+/// given, and `default` without prompting when stdin isn't a TTY (e.g. running from a script or
+/// CI).
+pub fn confirm(prompt: &str, default: bool) -> Result<bool> {
+    if assume_yes() {
+        return Ok(true);
+    }
+    if !std::io::stdin().is_terminal() {
+        return Ok(default);
+    }
+
+    Ok(Confirm::new()
+        .with_prompt(prompt)
+        .default(default)
+        .interact()?)
+}
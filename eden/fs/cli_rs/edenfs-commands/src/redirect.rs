@@ -10,8 +10,6 @@
 use std::collections::BTreeMap;
 #[cfg(target_os = "macos")]
 use std::ffi::OsStr;
-#[cfg(target_os = "macos")]
-use std::io::IsTerminal;
 use std::path::Path;
 use std::path::PathBuf;
 #[cfg(target_os = "macos")]
@@ -23,8 +21,6 @@ use anyhow::Context;
 use anyhow::Result;
 use async_trait::async_trait;
 use clap::Parser;
-#[cfg(target_os = "macos")]
-use dialoguer::Confirm;
 use edenfs_client::checkout::find_checkout;
 use edenfs_client::checkout::CheckoutConfig;
 use edenfs_client::fsutil::forcefully_remove_dir_all;
@@ -507,19 +503,18 @@ impl RedirectCmd {
             return Ok(0);
         }
 
-        if std::io::stdin().is_terminal() {
-            println!("Warning: this operation will permanently delete the following volumes:");
-            for volume in stale_volumes.iter() {
-                println!("    {}", volume.as_str().unwrap_or(""));
-            }
+        println!("Warning: this operation will permanently delete the following volumes:");
+        for volume in stale_volumes.iter() {
+            println!("    {}", volume.as_str().unwrap_or(""));
+        }
 
-            if !Confirm::new()
-                .with_prompt("Do you want to continue?")
-                .interact()?
-            {
-                println!("Not deleting volumes");
-                return Ok(2);
-            }
+        // `default` is `true` here (unlike most other destructive confirmations in this crate)
+        // to preserve this command's pre-existing non-interactive behavior: a non-interactive
+        // invocation (e.g. from a script, no `-y`) used to skip the prompt entirely and proceed
+        // with the delete, rather than aborting.
+        if !crate::prompt::confirm("Do you want to continue?", true)? {
+            println!("Not deleting volumes");
+            return Ok(2);
         }
 
         let mut res = 0;
@@ -0,0 +1,180 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! edenfsctl stop
+//!
+//! This only ports the `stop` half of Python's `eden stop` / `start` / `restart` trio.
+//! `start` and `restart` also need to spawn and daemonize the `edenfs` process itself
+//! (platform-specific launch code, readiness polling, and on Windows a takeover handshake),
+//! none of which exists yet anywhere in `cli_rs`; porting those blind, with no way to build or
+//! run this crate in this environment, would be guesswork rather than a real port. `stop` needs
+//! none of that: it just talks to an already-running daemon over Thrift (or, if that's
+//! unreachable, the lockfile) and waits for the process to go away, so it's the part that can be
+//! ported honestly on its own.
+
+use std::time::Duration;
+use std::time::Instant;
+
+use anyhow::anyhow;
+use anyhow::Result;
+use async_trait::async_trait;
+use clap::Parser;
+use edenfs_client::EdenFsInstance;
+use sysinfo::Pid;
+use sysinfo::System;
+use tokio::time;
+
+use crate::ExitCode;
+
+const SHUTDOWN_EXIT_CODE_NORMAL: ExitCode = 0;
+const SHUTDOWN_EXIT_CODE_REQUESTED_SHUTDOWN: ExitCode = 0;
+const SHUTDOWN_EXIT_CODE_NOT_RUNNING_ERROR: ExitCode = 2;
+const SHUTDOWN_EXIT_CODE_TERMINATED_VIA_SIGKILL: ExitCode = 3;
+const SHUTDOWN_EXIT_CODE_ERROR: ExitCode = 4;
+
+/// How long to wait, after sending SIGKILL, for the process to actually disappear.
+const SIGKILL_TIMEOUT: Duration = Duration::from_secs(30);
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Parser, Debug)]
+#[clap(about = "Stop the EdenFS daemon")]
+pub struct StopCmd {
+    /// Wait up to TIMEOUT seconds for the daemon to exit. If 0, request the shutdown and return
+    /// immediately without waiting.
+    #[clap(long, short = 't', default_value = "15.0")]
+    timeout: f64,
+
+    /// Don't attempt a graceful shutdown; send SIGKILL right away.
+    #[clap(long)]
+    kill: bool,
+}
+
+/// Wait for the process identified by `pid` to exit, polling every [`POLL_INTERVAL`] up to
+/// `timeout`. Returns `true` if the process exited within the deadline.
+async fn wait_for_process_exit(pid: Pid, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    let mut system = System::new();
+    loop {
+        system.refresh_process(pid);
+        if system.process(pid).is_none() {
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Send SIGKILL to `pid` and wait up to [`SIGKILL_TIMEOUT`] for it to actually exit.
+async fn sigkill_process(pid: Pid) -> Result<()> {
+    let mut system = System::new();
+    system.refresh_process(pid);
+    if let Some(process) = system.process(pid) {
+        process.kill();
+    }
+    if wait_for_process_exit(pid, SIGKILL_TIMEOUT).await {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "edenfs process {} did not terminate within {} seconds of SIGKILL",
+            pid,
+            SIGKILL_TIMEOUT.as_secs()
+        ))
+    }
+}
+
+impl StopCmd {
+    /// Ask the running daemon for its pid over Thrift, falling back to the lockfile if the
+    /// daemon isn't responding (it may already be dead, or stuck during startup/shutdown).
+    async fn get_pid(&self, instance: &EdenFsInstance) -> Result<Pid> {
+        match instance.connect(Some(Duration::from_secs(3))).await {
+            Ok(client) => Ok(Pid::from_u32(client.getPid().await? as u32)),
+            Err(e) => {
+                eprintln!("warning: edenfs daemon is not responding: {}", e);
+                Ok(instance.pid()?)
+            }
+        }
+    }
+
+    async fn stop(&self, instance: &EdenFsInstance) -> Result<ExitCode> {
+        let pid = match self.get_pid(instance).await {
+            Ok(pid) => pid,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                return Ok(SHUTDOWN_EXIT_CODE_NOT_RUNNING_ERROR);
+            }
+        };
+        println!("Stopping edenfs daemon (pid {})...", pid);
+
+        if let Ok(client) = instance.connect(Some(Duration::from_secs(3))).await {
+            let reason = format!("stopped by pid {}", std::process::id());
+            if let Err(e) = client.initiateShutdown(reason).await {
+                eprintln!("warning: could not request clean shutdown: {}", e);
+            }
+        }
+
+        if self.timeout <= 0.0 {
+            println!("Sent async shutdown request to edenfs.");
+            return Ok(SHUTDOWN_EXIT_CODE_REQUESTED_SHUTDOWN);
+        }
+
+        if wait_for_process_exit(pid, Duration::from_secs_f64(self.timeout)).await {
+            println!("edenfs exited.");
+            return Ok(SHUTDOWN_EXIT_CODE_NORMAL);
+        }
+
+        eprintln!(
+            "error: sent shutdown request, but edenfs did not exit within {} seconds. \
+             Sending SIGKILL...",
+            self.timeout
+        );
+        match sigkill_process(pid).await {
+            Ok(()) => {
+                println!("edenfs was killed.");
+                Ok(SHUTDOWN_EXIT_CODE_TERMINATED_VIA_SIGKILL)
+            }
+            Err(e) => {
+                eprintln!("error: {}", e);
+                Ok(SHUTDOWN_EXIT_CODE_ERROR)
+            }
+        }
+    }
+
+    async fn kill(&self, instance: &EdenFsInstance) -> Result<ExitCode> {
+        let pid = match instance.pid() {
+            Ok(pid) => pid,
+            Err(_) => {
+                eprintln!("error: edenfs is not running");
+                return Ok(SHUTDOWN_EXIT_CODE_NOT_RUNNING_ERROR);
+            }
+        };
+        println!("Sending SIGKILL to edenfs daemon (pid {})...", pid);
+        match sigkill_process(pid).await {
+            Ok(()) => {
+                println!("edenfs was killed.");
+                Ok(SHUTDOWN_EXIT_CODE_NORMAL)
+            }
+            Err(e) => {
+                eprintln!("error: {}", e);
+                Ok(SHUTDOWN_EXIT_CODE_ERROR)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl crate::Subcommand for StopCmd {
+    async fn run(&self) -> Result<ExitCode> {
+        let instance = EdenFsInstance::global();
+        if self.kill {
+            self.kill(instance).await
+        } else {
+            self.stop(instance).await
+        }
+    }
+}
@@ -0,0 +1,122 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! Local, opt-in invocation telemetry for edenfsctl.
+//!
+//! Mirrors the JSON sample shape written by the Python CLI's `LocalTelemetryLogger`
+//! (`eden/fs/cli/telemetry.py`) - one `{"int": {...}, "normal": {...}, "double": {...}}` object
+//! per line - so the two CLIs' local logs can be compared while the Rust CLI rolls out. Sending
+//! samples on to the daemon, like `ExternalTelemetryLogger` does for the Python CLI, is left for
+//! later: it would need a new Thrift method, and isn't needed to measure local failure rates.
+//!
+//! Samples record the daemon's pid rather than a version string: `DaemonInfo`, the only
+//! daemon-info RPC this CLI binds today, doesn't carry one.
+
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use edenfs_client::EdenFsInstance;
+use serde_json::json;
+use tracing::event;
+use tracing::Level;
+
+/// Set to opt a run into local telemetry logging. Unset (the default) means `record_invocation`
+/// never touches disk.
+const TELEMETRY_ENV_VAR: &str = "EDENFSCTL_RUST_TELEMETRY_LOG";
+
+const LOG_FILE_NAME: &str = "edenfsctl_rust_telemetry.log";
+
+/// Once the log reaches this size, the previous generation is dropped and a new one started,
+/// rather than letting it grow without bound over the life of a long-lived checkout.
+const MAX_LOG_SIZE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Records one command invocation to the local telemetry log, if enabled. Best-effort: a failure
+/// to write the sample is logged at debug level and otherwise ignored, since telemetry should
+/// never be the reason a command fails.
+pub async fn record_invocation(
+    instance: &EdenFsInstance,
+    command: &str,
+    duration: Duration,
+    exit_class: &str,
+) {
+    if std::env::var_os(TELEMETRY_ENV_VAR).is_none() {
+        return;
+    }
+
+    // `DaemonInfo` (the only daemon-info RPC this CLI currently binds) doesn't carry a version
+    // string, only `pid`/`status`/`uptime`; the pid doubles as a cheap proxy for "is this the
+    // same daemon process as last time", which is the thing a version field would mostly be used
+    // for here.
+    let daemon_pid = instance
+        .get_health(Some(Duration::from_millis(500)))
+        .await
+        .ok()
+        .map(|info| info.pid);
+
+    let sample = json!({
+        "int": {
+            "time": SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            "daemon_pid": daemon_pid,
+        },
+        "normal": {
+            "logged_by": "cli_rs",
+            "type": "invocation",
+            "command": command,
+            "exit_class": exit_class,
+        },
+        "double": {
+            "duration": duration.as_secs_f64(),
+        },
+    });
+
+    if let Err(e) = append_sample(&log_path(instance), &sample.to_string()) {
+        event!(
+            Level::DEBUG,
+            error = ?e,
+            "failed to write local telemetry sample"
+        );
+    }
+}
+
+fn log_path(instance: &EdenFsInstance) -> PathBuf {
+    instance.logs_dir().join(LOG_FILE_NAME)
+}
+
+fn append_sample(path: &Path, line: &str) -> std::io::Result<()> {
+    rotate_if_too_large(path)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    use std::io::Write;
+    writeln!(file, "{}", line)
+}
+
+/// Keeps one previous generation around (`<name>.1`), the same rotation scheme `debug logs`
+/// style tools in this repo use elsewhere, rather than letting the log grow forever.
+fn rotate_if_too_large(path: &Path) -> std::io::Result<()> {
+    match fs::metadata(path) {
+        Ok(metadata) if metadata.len() > MAX_LOG_SIZE_BYTES => {
+            let mut rotated = path.as_os_str().to_owned();
+            rotated.push(".1");
+            fs::rename(path, rotated)
+        }
+        Ok(_) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,237 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! A caching [`Blobstore`] wrapper built on [`caching_ext`]'s `EntityStore` machinery, as an
+//! alternative to the older, `CacheOps`-based [`CacheBlobstore`](crate::CacheBlobstore). Several
+//! stores in this codebase (e.g. `bonsai_hg_mapping`, `phases`) already cache their own small,
+//! structured values this way; `CachingExtBlobstore` gives plain blobstores the same mechanism,
+//! with one cachelib pool and memcache keyspace shared across every instance constructed from the
+//! same [`CacheHandlerFactory`], instead of each ad-hoc caching blobstore bringing its own.
+//!
+//! Only blobs up to [`MAX_CACHED_SIZE`] are cached; anything bigger is always served from (and
+//! written straight through to) the inner blobstore, so a handful of large blobs can't push the
+//! rest of the working set out of cachelib.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+use std::iter;
+
+use abomonation_derive::Abomonation;
+use anyhow::Result;
+use async_trait::async_trait;
+use blobstore::Blobstore;
+use blobstore::BlobstoreBytes;
+use blobstore::BlobstoreGetData;
+use blobstore::BlobstoreMetadata;
+use bytes::Bytes;
+use caching_ext::fill_cache;
+use caching_ext::get_or_fill;
+use caching_ext::CacheDisposition;
+use caching_ext::CacheHandlerFactory;
+use caching_ext::CacheTtl;
+use caching_ext::CachelibHandler;
+use caching_ext::EntityStore;
+use caching_ext::InvalidationChannel;
+use caching_ext::KeyedEntityStore;
+use caching_ext::McResult;
+use caching_ext::MemcacheEntity;
+use caching_ext::MemcacheHandler;
+use context::CoreContext;
+use memcache::KeyGen;
+
+/// Blobs larger than this are not cached: the one-time saving isn't worth the churn they'd cause
+/// in cachelib's working set.
+const MAX_CACHED_SIZE: usize = 100 * 1024;
+
+// Memcache constants, should be changed when we want to invalidate memcache entries.
+const MC_CODEVER: u32 = 0;
+const MC_SITEVER: u32 = 0;
+
+/// The cached form of a blob's contents. Stored as raw bytes rather than reusing
+/// [`BlobstoreBytes`] directly, since `BlobstoreBytes` doesn't implement `Abomonation`.
+/// Caching drops the inner blobstore's metadata (e.g. ctime) - callers that need it should go
+/// to the inner blobstore directly.
+#[derive(Abomonation, Clone)]
+struct CachedBlob(Vec<u8>);
+
+impl From<&BlobstoreBytes> for CachedBlob {
+    fn from(bytes: &BlobstoreBytes) -> Self {
+        CachedBlob(bytes.as_bytes().to_vec())
+    }
+}
+
+impl From<CachedBlob> for BlobstoreBytes {
+    fn from(blob: CachedBlob) -> Self {
+        BlobstoreBytes::from_bytes(blob.0)
+    }
+}
+
+impl MemcacheEntity for CachedBlob {
+    fn serialize(&self) -> Bytes {
+        Bytes::copy_from_slice(&self.0)
+    }
+
+    fn deserialize(bytes: Bytes) -> McResult<Self> {
+        Ok(CachedBlob(bytes.to_vec()))
+    }
+}
+
+pub struct CachingExtBlobstore<T> {
+    blobstore: T,
+    cachelib: CachelibHandler<CachedBlob>,
+    memcache: MemcacheHandler,
+    keygen: KeyGen,
+    invalidation: InvalidationChannel,
+}
+
+impl<T: fmt::Debug> fmt::Debug for CachingExtBlobstore<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CachingExtBlobstore")
+            .field("blobstore", &self.blobstore)
+            .finish()
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for CachingExtBlobstore<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CachingExtBlobstore<{}>", &self.blobstore)
+    }
+}
+
+impl<T> CachingExtBlobstore<T> {
+    pub fn new(blobstore: T, cache_handler_factory: CacheHandlerFactory) -> Self {
+        Self::with_invalidation_channel(
+            blobstore,
+            cache_handler_factory,
+            InvalidationChannel::new_local(),
+        )
+    }
+
+    /// Like [`new`](Self::new), but using an `InvalidationChannel` shared with other instances
+    /// (e.g. one per repo, all layered over the same underlying blobstore), so a `put` on one
+    /// evicts the stale entry from all of them straight away instead of waiting for the TTL.
+    pub fn with_invalidation_channel(
+        blobstore: T,
+        cache_handler_factory: CacheHandlerFactory,
+        invalidation: InvalidationChannel,
+    ) -> Self {
+        let cachelib = cache_handler_factory.cachelib();
+        caching_ext::spawn_invalidation_listener(&invalidation, cachelib.clone());
+        Self {
+            blobstore,
+            cachelib,
+            memcache: cache_handler_factory.memcache(),
+            keygen: KeyGen::new("scm.mononoke.blobstore", MC_CODEVER, MC_SITEVER),
+            invalidation,
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.blobstore
+    }
+
+    pub fn as_inner(&self) -> &T {
+        &self.blobstore
+    }
+}
+
+#[async_trait]
+impl<T: Blobstore> Blobstore for CachingExtBlobstore<T> {
+    async fn get<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: &'a str,
+    ) -> Result<Option<BlobstoreGetData>> {
+        let cache_request = (ctx, self);
+        let keys = iter::once(key.to_string()).collect();
+        let mut fetched = get_or_fill(&cache_request, keys).await?;
+        Ok(fetched
+            .remove(key)
+            .map(|blob| BlobstoreGetData::new(BlobstoreMetadata::new(None, None), blob.into())))
+    }
+
+    async fn put<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+    ) -> Result<()> {
+        // Invalidate first: if a concurrent reader fills the cache with the old value between
+        // the underlying put and a fill_cache() placed after it, it would stick around for the
+        // cache's TTL. Publishing the tombstone up front, before the new value exists anywhere,
+        // means the worst a racing reader can do is re-fetch the old value from the inner
+        // blobstore, which is always safe.
+        self.invalidation.publish(&get_cache_key(&key));
+        self.blobstore.put(ctx, key.clone(), value.clone()).await?;
+
+        let cache_request = (ctx, self);
+        let cached = CachedBlob::from(&value);
+        fill_cache(&cache_request, iter::once((&key, &cached))).await;
+        Ok(())
+    }
+}
+
+fn get_cache_key(key: &str) -> String {
+    format!("blobstore.{}", key)
+}
+
+type CacheRequest<'a, T> = (&'a CoreContext, &'a CachingExtBlobstore<T>);
+
+impl<T> EntityStore<CachedBlob> for CacheRequest<'_, T> {
+    fn cachelib(&self) -> &CachelibHandler<CachedBlob> {
+        let (_, store) = self;
+        &store.cachelib
+    }
+
+    fn keygen(&self) -> &KeyGen {
+        let (_, store) = self;
+        &store.keygen
+    }
+
+    fn memcache(&self) -> &MemcacheHandler {
+        let (_, store) = self;
+        &store.memcache
+    }
+
+    fn cache_determinator(
+        &self,
+        _key: &str,
+        v: &CachedBlob,
+        _serialized_size: &dyn Fn() -> usize,
+    ) -> Result<CacheDisposition> {
+        if v.0.len() > MAX_CACHED_SIZE {
+            Ok(CacheDisposition::Ignore)
+        } else {
+            Ok(CacheDisposition::Cache(CacheTtl::NoTtl))
+        }
+    }
+
+    fn cache_value_size(&self, v: &CachedBlob) -> usize {
+        v.0.len()
+    }
+
+    caching_ext::impl_singleton_stats!("blobstore.caching_ext");
+}
+
+#[async_trait]
+impl<T: Blobstore> KeyedEntityStore<String, CachedBlob> for CacheRequest<'_, T> {
+    fn get_cache_key(&self, key: &String) -> String {
+        get_cache_key(key)
+    }
+
+    async fn get_from_db(&self, keys: HashSet<String>) -> Result<HashMap<String, CachedBlob>> {
+        let (ctx, store) = self;
+        let mut ret = HashMap::with_capacity(keys.len());
+        for key in keys {
+            if let Some(data) = store.blobstore.get(ctx, &key).await? {
+                ret.insert(key, CachedBlob::from(data.as_bytes()));
+            }
+        }
+        Ok(ret)
+    }
+}
@@ -10,6 +10,9 @@ pub use crate::cachelib_cache::new_cachelib_blobstore;
 pub use crate::cachelib_cache::new_cachelib_blobstore_no_lease;
 pub use crate::cachelib_cache::CachelibBlobstoreOptions;
 
+mod caching_ext_blobstore;
+pub use crate::caching_ext_blobstore::CachingExtBlobstore;
+
 pub mod dummy;
 
 mod in_process_lease;
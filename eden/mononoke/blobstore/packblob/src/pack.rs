@@ -81,10 +81,23 @@ impl SingleCompressed {
 #[derive(Debug)]
 pub struct EmptyPack(i32);
 
+/// Delta chains longer than this are capped: once a blob's chosen base is already this deep,
+/// the new blob is stored as a fresh, independently-compressed base instead of extending the
+/// chain further. This bounds how many `ZstdFromDict` hops `decode_pack` has to follow to
+/// reconstruct any one entry.
+///
+/// These deltas are zstd-dictionary compression against the base blob; the binary (copy/insert)
+/// delta codec in `git_types::delta` is not used here. Chain depth limiting is applied to the
+/// existing zstd-dictionary chaining.
+const MAX_DELTA_CHAIN_DEPTH: u32 = 32;
+
 /// A pack containing multiple entries, ready to extend or upload
 pub struct Pack {
     zstd_level: i32,
     dictionaries: HashMap<String, EncoderDictionary<'static>>,
+    // Length of the delta chain each key in `dictionaries` sits at the end of; 0 for an
+    // independently-compressed (non-delta) entry.
+    chain_depth: HashMap<String, u32>,
     entries: Vec<PackedEntry>,
 }
 
@@ -107,17 +120,22 @@ impl EmptyPack {
 
         let mut dictionaries = HashMap::new();
         dictionaries.insert(key.clone(), dictionary);
+        let mut chain_depth = HashMap::new();
+        chain_depth.insert(key.clone(), 0);
         let entries = vec![PackedEntry { key, data }];
         Ok(Pack {
             zstd_level,
             dictionaries,
+            chain_depth,
             entries,
         })
     }
 }
 
 impl Pack {
-    /// Adds another data blob to a pack, delta'd against a previous key
+    /// Adds another data blob to a pack, delta'd against a previous key. If `dict_key`'s delta
+    /// chain has already reached [`MAX_DELTA_CHAIN_DEPTH`], `blob` is stored as a fresh,
+    /// independent base instead, so the chain doesn't grow any deeper.
     pub fn add_delta_blob(
         &mut self,
         dict_key: String,
@@ -127,6 +145,14 @@ impl Pack {
         if self.dictionaries.contains_key(&key) {
             bail!("Key {} cannot appear in the same pack twice", key);
         }
+        let base_depth = *self
+            .chain_depth
+            .get(&dict_key)
+            .ok_or_else(|| format_err!("Cannot find dictionary for blob {}", dict_key))?;
+        if base_depth >= MAX_DELTA_CHAIN_DEPTH {
+            return self.add_independent_blob(key, blob);
+        }
+
         let zstd = {
             let dictionary = self
                 .dictionaries
@@ -147,6 +173,21 @@ impl Pack {
         let dictionary = EncoderDictionary::copy(blob.as_bytes(), self.zstd_level);
         let data = PackedValue::ZstdFromDict(ZstdFromDictValue { dict_key, zstd });
         self.dictionaries.insert(key.clone(), dictionary);
+        self.chain_depth.insert(key.clone(), base_depth + 1);
+        self.entries.push(PackedEntry { key, data });
+        Ok(())
+    }
+
+    /// Stores `blob` independently (not as a delta), and registers it as a depth-0 base that
+    /// later `add_delta_blob` calls can chain from.
+    fn add_independent_blob(&mut self, key: String, blob: BlobstoreBytes) -> Result<()> {
+        let bytes = blob.into_bytes();
+        let dictionary = EncoderDictionary::copy(&bytes, self.zstd_level);
+        let cursor = Cursor::new(&bytes);
+        let compressed = zstd::encode_all(cursor, self.zstd_level)?;
+        let data = PackedValue::Single(SingleValue::Zstd(Bytes::from(compressed)));
+        self.dictionaries.insert(key.clone(), dictionary);
+        self.chain_depth.insert(key.clone(), 0);
         self.entries.push(PackedEntry { key, data });
         Ok(())
     }
@@ -653,4 +694,62 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn pack_delta_chain_depth_limit_test() -> Result<()> {
+        let mut rng = XorShiftRng::seed_from_u64(0); // reproducable Rng
+
+        let mut raw_data = vec![];
+        let pack = EmptyPack::new(0);
+
+        let base_version = vec![7u8; 1000];
+        raw_data.push(base_version.clone());
+        let base_blob = BlobstoreBytes::from_bytes(base_version.clone());
+        let mut pack = pack.add_base_blob("0".to_string(), base_blob)?;
+
+        // Chain twice as many entries as the depth limit allows, always against the
+        // previous key, so the chain would otherwise grow past the limit.
+        let chain_len = (MAX_DELTA_CHAIN_DEPTH * 2) as usize;
+        let mut prev_version = base_version;
+        for i in 1..=chain_len {
+            let mut this_version = prev_version;
+            rng.fill(&mut this_version[..10]);
+            raw_data.push(this_version.clone());
+            prev_version = this_version.clone();
+            pack.add_delta_blob(
+                (i - 1).to_string(),
+                i.to_string(),
+                BlobstoreBytes::from_bytes(this_version),
+            )?;
+        }
+
+        // The entry right after the limit was hit must have been stored independently, not
+        // chained onto an already-maximal-depth base.
+        let reset_entry = pack
+            .entries
+            .iter()
+            .find(|e| e.key == (MAX_DELTA_CHAIN_DEPTH + 1).to_string())
+            .expect("entry should be in the pack");
+        assert!(
+            matches!(reset_entry.data, PackedValue::Single(_)),
+            "chain should have been capped and restarted with an independent base"
+        );
+
+        // All entries, including ones on both sides of the reset, must still round-trip.
+        let (_key, _links, blob) = pack.into_blobstore_bytes(String::new())?;
+        let packed = {
+            let envelope: PackEnvelope = blob.try_into()?;
+            if let StorageFormat::Packed(pack) = envelope.0.storage {
+                pack
+            } else {
+                bail!("Packing resulted in a single value, not a pack");
+            }
+        };
+        for (raw_data, i) in raw_data.into_iter().zip(0..=chain_len) {
+            let (value, _size_meta) = decode_pack(packed.clone(), &i.to_string())?;
+            assert_eq!(value.into_bytes(), Bytes::from(raw_data));
+        }
+
+        Ok(())
+    }
 }
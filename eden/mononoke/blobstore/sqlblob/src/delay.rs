@@ -8,10 +8,11 @@
 use std::cmp::max;
 use std::cmp::min;
 use std::num::NonZeroUsize;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
 use std::time::Duration;
-use std::time::Instant;
 
-use futures::stream::StreamExt;
 use rand::thread_rng;
 use rand::Rng;
 use stats::prelude::*;
@@ -20,22 +21,97 @@ use tokio::sync::watch;
 // This can be tweaked later.
 pub(crate) const MAX_LAG: Duration = Duration::from_secs(5);
 
+/// Below this, lag is low enough that coalescing writes into bigger, less frequent statements
+/// isn't worth the added latency. Between this and [`MAX_LAG`], [`AimdAdmission`] may still be
+/// imposing some delay on writes, but callers doing lots of small writes (e.g. chunk inserts)
+/// may choose to batch them instead.
+pub(crate) const MODERATE_LAG: Duration = Duration::from_millis(500);
+
+/// Ceiling on the delay [`AimdAdmission`] will impose before a write once lag is at or only
+/// moderately above [`MAX_LAG`]. Matches the old jitter cap, so a writer that just tipped over
+/// [`MAX_LAG`] waits about as long as one that used to block on a single big jitter.
+const MAX_ADMISSION_DELAY: Duration = Duration::from_secs(1);
+
+/// Hard backstop on the delay ceiling even when lag is severely above [`MAX_LAG`]. Keeps
+/// [`AimdAdmission::delay_ceiling`]'s scaling from growing without bound, while still being far
+/// above [`MAX_ADMISSION_DELAY`] so sustained, severe lag produces real, escalating backpressure
+/// rather than plateauing at the same delay as lag that only just crossed [`MAX_LAG`].
+const MAX_SEVERE_ADMISSION_DELAY: Duration = Duration::from_secs(30);
+
+/// Amount the per-shard admission delay moves by on each observation: added (and doubled, for a
+/// net multiplicative effect) when lag is at or above [`MAX_LAG`], subtracted when it's below.
+const ADMISSION_STEP: Duration = Duration::from_millis(20);
+
 define_stats! {
     prefix = "mononoke.sqlblob.lag_delay";
     total_delay_ms: dynamic_timeseries("{}.total_delay_ms", (entity: String); Rate, Sum),
     raw_lag_ms: dynamic_timeseries("{}.raw_lag_ms", (entity: String); Rate, Sum),
 }
 
+/// Per-shard AIMD write admission controller.
+///
+/// Rather than blocking indefinitely until lag drops back under [`MAX_LAG`], each call to
+/// [`AimdAdmission::observe`] nudges a per-shard delay towards whatever keeps lag roughly at
+/// bay: additive increase of the admitted write rate (the delay decreases by [`ADMISSION_STEP`])
+/// while lag stays under [`MAX_LAG`], multiplicative decrease of the admitted rate (the delay at
+/// least doubles) the moment lag is observed at or above it. This smooths the delay imposed on
+/// writers instead of oscillating between full-speed and fully-blocked. The delay's ceiling
+/// itself scales with how severe the observed lag is (see [`AimdAdmission::delay_ceiling`]), so
+/// sustained, severe lag still produces real, escalating backpressure rather than settling at the
+/// same cap as lag that only just crossed [`MAX_LAG`].
+struct AimdAdmission {
+    current_delay_ms: AtomicU64,
+}
+
+impl AimdAdmission {
+    fn new() -> Self {
+        Self {
+            current_delay_ms: AtomicU64::new(0),
+        }
+    }
+
+    /// Record an observed replication lag sample and return the delay a writer should now wait
+    /// before proceeding.
+    fn observe(&self, lag: Duration) -> Duration {
+        let current = Duration::from_millis(self.current_delay_ms.load(Ordering::Relaxed));
+        let next = if lag >= MAX_LAG {
+            min(max(current * 2, ADMISSION_STEP), Self::delay_ceiling(lag))
+        } else {
+            current.saturating_sub(ADMISSION_STEP)
+        };
+        self.current_delay_ms
+            .store(next.as_millis() as u64, Ordering::Relaxed);
+        next
+    }
+
+    /// Ceiling the admission delay is allowed to grow to for a given observed `lag`. Scales
+    /// linearly with how far `lag` has climbed past [`MAX_LAG`], so lag that's merely crossed the
+    /// threshold is capped at [`MAX_ADMISSION_DELAY`] as before, while sustained, severe lag keeps
+    /// imposing meaningfully more backpressure instead of plateauing at that same delay forever,
+    /// up to the [`MAX_SEVERE_ADMISSION_DELAY`] backstop.
+    fn delay_ceiling(lag: Duration) -> Duration {
+        if lag <= MAX_LAG {
+            return MAX_ADMISSION_DELAY;
+        }
+        let severity = lag.as_secs_f64() / MAX_LAG.as_secs_f64();
+        min(MAX_ADMISSION_DELAY.mul_f64(severity), MAX_SEVERE_ADMISSION_DELAY)
+    }
+}
+
 #[derive(Clone)]
 pub struct BlobDelay {
     lag_receivers: Vec<watch::Receiver<Duration>>,
+    // Shared across clones of `BlobDelay`, so every writer to a given shard sees (and
+    // contributes to) the same admission state, the same way they already share `lag_receivers`.
+    admission: Arc<Vec<AimdAdmission>>,
     entity: Option<String>,
 }
 
-// Adds a small amount of random delay to desynchronise when waiting
-async fn jitter_delay(raw_lag: Duration) {
+/// Adds a small amount of random delay to desynchronise concurrent writers waiting on the same
+/// shard's shared admission delay, so they don't all wake up and retry at once.
+async fn jitter_delay(base: Duration) {
     // Delay should not be greater than 1 second
-    let delay = min(Duration::from_millis(1000), raw_lag);
+    let delay = min(Duration::from_millis(1000), base);
     // Delay should also not be less than or equal to 0 seconds since that
     // will make the range empty and lead to panic
     let delay = max(Duration::from_millis(50), delay);
@@ -45,15 +121,18 @@ async fn jitter_delay(raw_lag: Duration) {
 
 impl BlobDelay {
     pub fn dummy(shard_count: NonZeroUsize) -> Self {
+        let shard_count: usize = shard_count.into();
         let lag_receivers = vec![
             {
                 let (_, ch) = watch::channel(Duration::new(0, 0));
                 ch
             };
-            shard_count.into()
+            shard_count
         ];
+        let admission = Arc::new((0..shard_count).map(|_| AimdAdmission::new()).collect());
         Self {
             lag_receivers,
+            admission,
             entity: None,
         }
     }
@@ -61,38 +140,105 @@ impl BlobDelay {
     #[cfg(fbcode_build)]
     pub fn from_channels(lag_receivers: Vec<watch::Receiver<Duration>>, name: String) -> Self {
         let entity = Some(name);
+        let admission = Arc::new(
+            (0..lag_receivers.len())
+                .map(|_| AimdAdmission::new())
+                .collect(),
+        );
         Self {
             lag_receivers,
+            admission,
             entity,
         }
     }
 
     pub async fn delay(&self, shard_id: usize) {
-        let mut lag_receiver =
-            tokio_stream::wrappers::WatchStream::new(self.lag_receivers[shard_id].clone());
-        let start_time = Instant::now();
-
-        while let Some(raw_lag) = lag_receiver.next().await {
-            if raw_lag < MAX_LAG {
-                if start_time.elapsed() > Duration::from_secs(1) {
-                    // No jittering for short delays, but jitter us about a bit if we've seen
-                    // lag and waited for it to die down, so that next request is random
-                    jitter_delay(raw_lag).await;
-                }
-                break;
-            }
-            if let Some(entity) = &self.entity {
-                let raw_lag_ms = raw_lag.as_millis().try_into();
-                if let Ok(raw_lag_ms) = raw_lag_ms {
-                    STATS::raw_lag_ms.add_value(raw_lag_ms, (entity.clone(),))
-                }
+        let raw_lag = self.current_lag(shard_id);
+        let wait = self.admission[shard_id].observe(raw_lag);
+
+        if let Some(entity) = &self.entity {
+            let raw_lag_ms = raw_lag.as_millis().try_into();
+            if let Ok(raw_lag_ms) = raw_lag_ms {
+                STATS::raw_lag_ms.add_value(raw_lag_ms, (entity.clone(),))
             }
         }
+
+        if wait > Duration::ZERO {
+            tokio::time::sleep(wait).await;
+            jitter_delay(ADMISSION_STEP).await;
+        }
+
         if let Some(entity) = &self.entity {
-            let total_delay_ms = start_time.elapsed().as_millis().try_into();
+            let total_delay_ms = wait.as_millis().try_into();
             if let Ok(total_delay_ms) = total_delay_ms {
                 STATS::total_delay_ms.add_value(total_delay_ms, (entity.clone(),));
             }
         }
     }
+
+    /// Current replication lag for `shard_id`, without waiting for it to change.
+    pub(crate) fn current_lag(&self, shard_id: usize) -> Duration {
+        *self.lag_receivers[shard_id].borrow()
+    }
+
+    /// Whether `shard_id` is lagged enough that batching writes is worth the added latency, but
+    /// not so lagged that it's at the admission controller's backoff ceiling.
+    pub(crate) fn is_moderately_lagged(&self, shard_id: usize) -> bool {
+        let lag = self.current_lag(shard_id);
+        lag >= MODERATE_LAG && lag < MAX_LAG
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn admission_backs_off_multiplicatively_on_lag() {
+        let admission = AimdAdmission::new();
+        assert_eq!(admission.observe(Duration::from_secs(0)), Duration::ZERO);
+
+        let first_backoff = admission.observe(MAX_LAG);
+        assert!(first_backoff > Duration::ZERO);
+
+        let second_backoff = admission.observe(MAX_LAG);
+        assert!(second_backoff >= first_backoff * 2 || second_backoff == MAX_ADMISSION_DELAY);
+    }
+
+    #[test]
+    fn admission_backoff_is_capped() {
+        let admission = AimdAdmission::new();
+        for _ in 0..1000 {
+            admission.observe(MAX_LAG * 10);
+        }
+        assert_eq!(admission.observe(MAX_LAG), MAX_ADMISSION_DELAY);
+    }
+
+    #[test]
+    fn admission_recovers_additively_once_lag_clears() {
+        let admission = AimdAdmission::new();
+        let backed_off = admission.observe(MAX_LAG);
+        assert!(backed_off > Duration::ZERO);
+
+        let recovered = admission.observe(Duration::ZERO);
+        assert!(recovered < backed_off);
+    }
+
+    #[test]
+    fn admission_backoff_ceiling_scales_with_severe_sustained_lag() {
+        let admission = AimdAdmission::new();
+        for _ in 0..1000 {
+            admission.observe(MAX_LAG);
+        }
+        assert_eq!(admission.observe(MAX_LAG), MAX_ADMISSION_DELAY);
+
+        let admission = AimdAdmission::new();
+        let severe_lag = MAX_LAG * 10;
+        for _ in 0..1000 {
+            admission.observe(severe_lag);
+        }
+        let severe_backoff = admission.observe(severe_lag);
+        assert!(severe_backoff > MAX_ADMISSION_DELAY);
+        assert!(severe_backoff <= MAX_SEVERE_ADMISSION_DELAY);
+    }
 }
@@ -103,6 +103,17 @@ pub struct Sqlblob {
     ctime_inline_grace: i64,
 }
 
+/// A single key's data plus enough metadata to faithfully restore it.
+/// Produced by [`Sqlblob::export_shard`] and consumed by
+/// [`Sqlblob::import_record`].
+#[derive(Clone)]
+pub struct ExportRecord {
+    pub key: String,
+    pub ctime: i64,
+    pub generation: Option<u64>,
+    pub data: BlobstoreBytes,
+}
+
 impl std::fmt::Display for Sqlblob {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "Sqlblob")
@@ -405,6 +416,23 @@ impl Sqlblob {
         self.chunk_store.set_initial_generation(shard_num).await
     }
 
+    /// The mark_generation that `shard_num` last fully completed the GC mark phase for, if any.
+    /// A mark run resuming after a restart uses this to skip shards it already finished.
+    pub async fn get_mark_checkpoint(&self, shard_num: usize) -> Result<Option<u64>> {
+        self.data_store.get_mark_checkpoint(shard_num).await
+    }
+
+    /// Record that `shard_num` has fully completed the GC mark phase for `mark_generation`.
+    pub async fn set_mark_checkpoint(&self, shard_num: usize, mark_generation: u64) -> Result<()> {
+        let completed_at: i64 = match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(offset) => offset.as_secs().try_into(),
+            Err(negative) => negative.duration().as_secs().try_into().map(|v: i64| -v),
+        }?;
+        self.data_store
+            .set_mark_checkpoint(shard_num, mark_generation, completed_at)
+            .await
+    }
+
     #[cfg(test)]
     pub async fn get_chunk_generations(&self, key: &str) -> Result<Vec<Option<u64>>> {
         let chunked = self.data_store.get(key).await?;
@@ -500,6 +528,63 @@ impl Sqlblob {
         }
     }
 
+    /// Stream every key in `shard_num`, with its raw blob bytes and GC
+    /// generation mark, in a form suitable for a backup or a cross-tier
+    /// migration copy that doesn't go through a MySQL dump.
+    ///
+    /// Keys that disappear between being listed and being fetched (e.g.
+    /// because they were unlinked or GC'd concurrently) are silently skipped,
+    /// the same way a plain `get` racing a delete would be.
+    pub fn export_shard<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        shard_num: usize,
+    ) -> impl Stream<Item = Result<ExportRecord, Error>> + 'a {
+        self.get_keys_from_shard(shard_num)
+            .and_then(move |key| self.export_key(ctx, key))
+            .try_filter_map(|record| async move { Ok(record) })
+    }
+
+    async fn export_key(
+        &self,
+        ctx: &CoreContext,
+        key: String,
+    ) -> Result<Option<ExportRecord>, Error> {
+        let chunked = match self.data_store.get(&key).await? {
+            Some(chunked) => chunked,
+            None => return Ok(None),
+        };
+        // All chunks of a key are marked with the same generation together (see
+        // `set_generation` above), so the first chunk's mark is representative.
+        let generation = self
+            .chunk_store
+            .get_generation(&chunked.id, 0, chunked.chunking_method)
+            .await?;
+        let data = match self.get(ctx, &key).await? {
+            Some(data) => data.into_bytes(),
+            None => return Ok(None),
+        };
+        Ok(Some(ExportRecord {
+            key,
+            ctime: chunked.ctime,
+            generation,
+            data,
+        }))
+    }
+
+    /// Re-insert a record previously produced by `export_shard`.
+    ///
+    /// The write gets a fresh ctime, same as any other `put` would; only the GC
+    /// generation mark is carried over from the record, so data imported from an
+    /// already-scanned source isn't immediately treated as unscanned.
+    pub async fn import_record(&self, ctx: &CoreContext, record: ExportRecord) -> Result<()> {
+        self.put(ctx, record.key.clone(), record.data).await?;
+        if let Some(generation) = record.generation {
+            self.set_generation(&record.key, false, generation).await?;
+        }
+        Ok(())
+    }
+
     async fn get_impl<'a>(&'a self, key: &'a str) -> Result<Option<BlobstoreGetData>> {
         let chunked = self.data_store.get(key).await?;
         if let Some(chunked) = chunked {
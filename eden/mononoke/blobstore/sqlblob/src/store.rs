@@ -5,10 +5,14 @@
  * GNU General Public License version 2.
  */
 
+use std::cmp::min;
 use std::collections::HashMap;
+use std::collections::VecDeque;
 use std::hash::Hasher;
 use std::num::NonZeroUsize;
 use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
 
 use anyhow::bail;
 use anyhow::format_err;
@@ -20,12 +24,27 @@ use futures::stream;
 use futures::stream::Stream;
 use sql::Connection;
 use sql_ext::mononoke_queries;
+use tokio::sync::oneshot;
 use twox_hash::XxHash32;
 use vec1::Vec1;
 use xdb_gc_structs::XdbGc;
 
 use crate::delay::BlobDelay;
 
+/// How long a small chunk insert may wait to be coalesced with others into one multi-row INSERT,
+/// when its shard is under moderate replication lag. Bounded so the extra latency a single put
+/// can incur stays predictable even under sustained lag.
+const COALESCE_WINDOW: Duration = Duration::from_millis(20);
+
+/// Chunks larger than this always insert on their own: batching a large chunk doesn't
+/// meaningfully reduce the number of binlog events relative to the chunk's own size, and would
+/// only add latency to it.
+const COALESCE_MAX_CHUNK_SIZE: usize = 8 * 1024;
+
+/// Most rows to combine into a single multi-row INSERT, so one busy, lagged shard can't build an
+/// unbounded statement.
+const COALESCE_MAX_BATCH: usize = 32;
+
 mod types {
     use sql::mysql;
     use sql::mysql_async::prelude::ConvIr;
@@ -199,6 +218,28 @@ mononoke_queries! {
         FROM chunk_generation
         GROUP BY chunk_generation.last_seen_generation"
     }
+
+    read GetShardMarkCheckpoint(shard_num: usize) -> (u64) {
+        "SELECT mark_generation
+        FROM shard_mark_checkpoint
+        WHERE shard_num = {shard_num}"
+    }
+
+    write SetShardMarkCheckpoint(values: (shard_num: usize, mark_generation: u64, completed_at: i64)) {
+        insert_or_ignore,
+        "{insert_or_ignore} INTO shard_mark_checkpoint (
+            shard_num
+            , mark_generation
+            , completed_at
+        ) VALUES {values}"
+    }
+
+    write UpdateShardMarkCheckpoint(shard_num: usize, mark_generation: u64, completed_at: i64) {
+        none,
+        "UPDATE shard_mark_checkpoint
+        SET mark_generation = {mark_generation}, completed_at = {completed_at}
+        WHERE shard_num = {shard_num}"
+    }
 }
 
 pub struct Chunked {
@@ -367,12 +408,56 @@ impl DataSqlStore {
         hasher.write(key.as_bytes());
         (hasher.finish() % self.shard_count.get() as u64) as usize
     }
+
+    /// The mark_generation a shard's GC mark phase last completed for, if it's ever finished one.
+    /// A resuming mark run uses this to skip shards it already fully marked.
+    pub(crate) async fn get_mark_checkpoint(
+        &self,
+        shard_num: usize,
+    ) -> Result<Option<u64>, Error> {
+        Ok(
+            GetShardMarkCheckpoint::query(&self.read_master_connection[shard_num], &shard_num)
+                .await?
+                .into_iter()
+                .next()
+                .map(|(mark_generation,)| mark_generation),
+        )
+    }
+
+    /// Record that `shard_num` has fully completed marking for `mark_generation`.
+    pub(crate) async fn set_mark_checkpoint(
+        &self,
+        shard_num: usize,
+        mark_generation: u64,
+        completed_at: i64,
+    ) -> Result<(), Error> {
+        let conn = &self.write_connection[shard_num];
+        let res = SetShardMarkCheckpoint::query(
+            conn,
+            &[(&shard_num, &mark_generation, &completed_at)],
+        )
+        .await?;
+        if res.affected_rows() == 0 {
+            UpdateShardMarkCheckpoint::query(conn, &shard_num, &mark_generation, &completed_at)
+                .await?;
+        }
+        Ok(())
+    }
 }
 pub(crate) enum ChunkGenerationState {
     NeedsInsertToShard(usize),
     Updated,
 }
 
+/// A chunk insert waiting to be coalesced with others from the same shard into one multi-row
+/// INSERT.
+struct PendingChunkInsert {
+    key: String,
+    chunk_num: u32,
+    value: Vec<u8>,
+    reply: oneshot::Sender<Result<(), Error>>,
+}
+
 #[derive(Clone)]
 pub(crate) struct ChunkSqlStore {
     shard_count: NonZeroUsize,
@@ -381,6 +466,7 @@ pub(crate) struct ChunkSqlStore {
     read_master_connection: Arc<Vec1<Connection>>,
     delay: BlobDelay,
     gc_generations: ConfigHandle<XdbGc>,
+    coalesce_queues: Arc<Vec<Mutex<VecDeque<PendingChunkInsert>>>>,
 }
 
 impl ChunkSqlStore {
@@ -392,6 +478,11 @@ impl ChunkSqlStore {
         delay: BlobDelay,
         gc_generations: ConfigHandle<XdbGc>,
     ) -> Self {
+        let coalesce_queues = Arc::new(
+            (0..shard_count.get())
+                .map(|_| Mutex::new(VecDeque::new()))
+                .collect(),
+        );
         Self {
             shard_count,
             write_connection,
@@ -399,6 +490,7 @@ impl ChunkSqlStore {
             read_master_connection,
             delay,
             gc_generations,
+            coalesce_queues,
         }
     }
 
@@ -448,7 +540,12 @@ impl ChunkSqlStore {
             let conn = &self.write_connection[shard_id];
             // Update generation incase it already exists
             let updated = UpdateGeneration::query(conn, &key, &generation, &full_value_len).await?;
-            InsertChunk::query(conn, &[(&key, &chunk_num, &value)]).await?;
+            if value.len() <= COALESCE_MAX_CHUNK_SIZE && self.delay.is_moderately_lagged(shard_id) {
+                self.insert_chunk_coalesced(shard_id, key, chunk_num, value)
+                    .await?;
+            } else {
+                InsertChunk::query(conn, &[(&key, &chunk_num, &value)]).await?;
+            }
             if updated.affected_rows() > 0 {
                 Ok(Some(ChunkGenerationState::Updated))
             } else {
@@ -459,6 +556,90 @@ impl ChunkSqlStore {
         }
     }
 
+    /// Queues a chunk insert to be combined with others from the same shard into one multi-row
+    /// INSERT, instead of each put issuing its own statement at exactly the moment the shard is
+    /// least able to absorb binlog pressure. The first put to find the shard's queue empty waits
+    /// out `COALESCE_WINDOW` and flushes it; everyone else just waits for that flush.
+    async fn insert_chunk_coalesced(
+        &self,
+        shard_id: usize,
+        key: &str,
+        chunk_num: u32,
+        value: &[u8],
+    ) -> Result<(), Error> {
+        let (reply, receiver) = oneshot::channel();
+        let is_leader = {
+            let mut queue = self.coalesce_queues[shard_id]
+                .lock()
+                .expect("coalesce queue lock poisoned");
+            let is_leader = queue.is_empty();
+            queue.push_back(PendingChunkInsert {
+                key: key.to_owned(),
+                chunk_num,
+                value: value.to_vec(),
+                reply,
+            });
+            is_leader
+        };
+
+        if is_leader {
+            tokio::time::sleep(COALESCE_WINDOW).await;
+            self.flush_coalesced(shard_id).await;
+        }
+
+        receiver.await.unwrap_or_else(|_| {
+            bail!(
+                "chunk insert coalescing leader for shard {} dropped its reply",
+                shard_id
+            )
+        })
+    }
+
+    /// Drains `shard_id`'s coalescing queue and inserts everything in it, in statements of at
+    /// most `COALESCE_MAX_BATCH` rows, then reports the outcome back to every waiting put.
+    async fn flush_coalesced(&self, shard_id: usize) {
+        let pending: Vec<PendingChunkInsert> = {
+            let mut queue = self.coalesce_queues[shard_id]
+                .lock()
+                .expect("coalesce queue lock poisoned");
+            queue.drain(..).collect()
+        };
+        if pending.is_empty() {
+            return;
+        }
+
+        let conn = &self.write_connection[shard_id];
+        let key_refs: Vec<&str> = pending.iter().map(|p| p.key.as_str()).collect();
+        let value_refs: Vec<&[u8]> = pending.iter().map(|p| p.value.as_slice()).collect();
+
+        let mut outcome: Result<(), Error> = Ok(());
+        for start in (0..pending.len()).step_by(COALESCE_MAX_BATCH) {
+            let end = min(start + COALESCE_MAX_BATCH, pending.len());
+            let rows: Vec<_> = key_refs[start..end]
+                .iter()
+                .zip(pending[start..end].iter().map(|p| &p.chunk_num))
+                .zip(value_refs[start..end].iter())
+                .map(|((k, n), v)| (k, n, v))
+                .collect();
+            if let Err(e) = InsertChunk::query(conn, &rows).await {
+                outcome = Err(e);
+                break;
+            }
+        }
+
+        for p in pending {
+            let result = match &outcome {
+                Ok(()) => Ok(()),
+                Err(e) => Err(format_err!(
+                    "coalesced chunk insert failed for shard {}: {}",
+                    shard_id,
+                    e
+                )),
+            };
+            let _ = p.reply.send(result);
+        }
+    }
+
     pub(crate) fn get_mark_generation(&self) -> u64 {
         self.gc_generations.get().mark_generation as u64
     }
@@ -20,35 +20,25 @@ use bytes::Bytes;
 use caching_ext::get_or_fill_chunked;
 use caching_ext::CacheDisposition;
 use caching_ext::CacheHandlerFactory;
+use caching_ext::CacheStats;
 use caching_ext::CacheTtl;
 use caching_ext::CachelibHandler;
 use caching_ext::EntityStore;
 use caching_ext::KeyedEntityStore;
-use caching_ext::McErrorKind;
-use caching_ext::McResult;
-use caching_ext::MemcacheEntity;
 use caching_ext::MemcacheHandler;
+use caching_ext::ThriftMemcacheEntity;
 use context::CoreContext;
 use fbthrift::compact_protocol;
 use memcache::KeyGen;
 use mononoke_types::hash::GitSha1;
 use mononoke_types::ChangesetId;
 use mononoke_types::RepositoryId;
-use stats::prelude::*;
 
 use super::BonsaiGitMapping;
 use super::BonsaiGitMappingEntry;
 use super::BonsaisOrGitShas;
 use crate::AddGitMappingErrorKind;
 
-define_stats! {
-    prefix = "mononoke.bonsai_git_mapping";
-    memcache_hit: timeseries("memcache.hit"; Rate, Sum),
-    memcache_miss: timeseries("memcache.miss"; Rate, Sum),
-    memcache_internal_err: timeseries("memcache.internal_err"; Rate, Sum),
-    memcache_deserialize_err: timeseries("memcache.deserialize_err"; Rate, Sum),
-}
-
 #[derive(Abomonation, Clone, Debug, Eq, Hash, PartialEq)]
 pub struct BonsaiGitMappingCacheEntry {
     pub repo_id: RepositoryId,
@@ -134,6 +124,7 @@ pub struct CachingBonsaiGitMapping {
     cachelib: CachelibHandler<BonsaiGitMappingCacheEntry>,
     memcache: MemcacheHandler,
     keygen: KeyGen,
+    stats: CacheStats,
 }
 
 impl CachingBonsaiGitMapping {
@@ -142,6 +133,7 @@ impl CachingBonsaiGitMapping {
         cache_handler_factory: CacheHandlerFactory,
     ) -> Result<Self> {
         Ok(Self {
+            stats: CacheStats::new(format!("bonsai_git_mapping.{}", mapping.repo_id())),
             mapping,
             cachelib: cache_handler_factory.cachelib(),
             memcache: cache_handler_factory.memcache(),
@@ -151,6 +143,7 @@ impl CachingBonsaiGitMapping {
 
     pub fn new_test(mapping: Arc<dyn BonsaiGitMapping>) -> Self {
         Self {
+            stats: CacheStats::new(format!("bonsai_git_mapping.{}", mapping.repo_id())),
             mapping,
             cachelib: CacheHandlerFactory::Mocked.cachelib(),
             memcache: CacheHandlerFactory::Mocked.memcache(),
@@ -173,16 +166,16 @@ impl CachingBonsaiGitMapping {
     }
 }
 
-fn memcache_deserialize(bytes: Bytes) -> McResult<BonsaiGitMappingCacheEntry> {
-    let thrift_entry =
-        compact_protocol::deserialize(bytes).map_err(|_| McErrorKind::Deserialization);
-    thrift_entry.and_then(|entry| {
-        BonsaiGitMappingCacheEntry::from_thrift(entry).map_err(|_| McErrorKind::Deserialization)
-    })
-}
+impl ThriftMemcacheEntity for BonsaiGitMappingCacheEntry {
+    fn into_bytes(&self) -> Bytes {
+        compact_protocol::serialize(&self.clone().into_thrift())
+    }
 
-fn memcache_serialize(entry: &BonsaiGitMappingCacheEntry) -> Bytes {
-    compact_protocol::serialize(&entry.clone().into_thrift())
+    fn from_bytes(bytes: Bytes) -> Result<Self> {
+        compact_protocol::deserialize(bytes)
+            .map_err(|_| anyhow!("failed to deserialize BonsaiGitMappingCacheEntry from thrift"))
+            .and_then(Self::from_thrift)
+    }
 }
 
 const CHUNK_SIZE: usize = 1000;
@@ -280,16 +273,6 @@ fn get_cache_key(repo_id: RepositoryId, cs: &BonsaiOrGitSha) -> String {
     format!("{}.{:?}", repo_id.prefix(), cs)
 }
 
-impl MemcacheEntity for BonsaiGitMappingCacheEntry {
-    fn serialize(&self) -> Bytes {
-        memcache_serialize(self)
-    }
-
-    fn deserialize(bytes: Bytes) -> McResult<Self> {
-        memcache_deserialize(bytes)
-    }
-}
-
 type CacheRequest<'a> = (&'a CoreContext, &'a CachingBonsaiGitMapping);
 
 impl EntityStore<BonsaiGitMappingCacheEntry> for CacheRequest<'_> {
@@ -308,11 +291,19 @@ impl EntityStore<BonsaiGitMappingCacheEntry> for CacheRequest<'_> {
         &mapping.memcache
     }
 
-    fn cache_determinator(&self, _: &BonsaiGitMappingCacheEntry) -> CacheDisposition {
-        CacheDisposition::Cache(CacheTtl::NoTtl)
+    fn cache_determinator(
+        &self,
+        _key: &str,
+        _: &BonsaiGitMappingCacheEntry,
+        _serialized_size: &dyn Fn() -> usize,
+    ) -> Result<CacheDisposition, Error> {
+        Ok(CacheDisposition::Cache(CacheTtl::NoTtl))
     }
 
-    caching_ext::impl_singleton_stats!("bonsai_git_mapping");
+    fn stats(&self) -> &CacheStats {
+        let (_, mapping) = self;
+        &mapping.stats
+    }
 }
 
 #[async_trait]
@@ -185,19 +185,19 @@ impl MemcacheEntity for BonsaiGlobalrevMappingCacheEntry {
             repo_id,
             bcs_id,
             globalrev,
-        } = compact_protocol::deserialize(bytes).map_err(|_| McErrorKind::Deserialization)?;
+        } = compact_protocol::deserialize(bytes)
+            .map_err(|e| McErrorKind::Deserialization(e.to_string()))?;
 
         let repo_id = RepositoryId::new(repo_id);
         let bcs_id = bcs_id
             .map(|bcs_id| {
-                ChangesetId::from_thrift(bcs_id).map_err(|_| McErrorKind::Deserialization)
+                ChangesetId::from_thrift(bcs_id)
+                    .map_err(|e| McErrorKind::Deserialization(e.to_string()))
             })
             .transpose()?;
-        let globalrev = Globalrev::new(
-            globalrev
-                .try_into()
-                .map_err(|_| McErrorKind::Deserialization)?,
-        );
+        let globalrev = Globalrev::new(globalrev.try_into().map_err(
+            |e: std::num::TryFromIntError| McErrorKind::Deserialization(e.to_string()),
+        )?);
 
         Ok(BonsaiGlobalrevMappingCacheEntry {
             repo_id,
@@ -225,8 +225,13 @@ impl EntityStore<BonsaiGlobalrevMappingCacheEntry> for CacheRequest<'_> {
         &mapping.memcache
     }
 
-    fn cache_determinator(&self, _: &BonsaiGlobalrevMappingCacheEntry) -> CacheDisposition {
-        CacheDisposition::Cache(CacheTtl::NoTtl)
+    fn cache_determinator(
+        &self,
+        _key: &str,
+        _: &BonsaiGlobalrevMappingCacheEntry,
+        _serialized_size: &dyn Fn() -> usize,
+    ) -> Result<CacheDisposition, Error> {
+        Ok(CacheDisposition::Cache(CacheTtl::NoTtl))
     }
 
     caching_ext::impl_singleton_stats!("bonsai_globalrev_mapping");
@@ -7,6 +7,7 @@
 
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::iter;
 use std::sync::Arc;
 
 use abomonation_derive::Abomonation;
@@ -16,6 +17,7 @@ use anyhow::Result;
 use async_trait::async_trait;
 use bonsai_hg_mapping_entry_thrift as thrift;
 use bytes::Bytes;
+use caching_ext::fill_cache;
 use caching_ext::get_or_fill_chunked;
 use caching_ext::CacheDisposition;
 use caching_ext::CacheHandlerFactory;
@@ -23,10 +25,8 @@ use caching_ext::CacheTtl;
 use caching_ext::CachelibHandler;
 use caching_ext::EntityStore;
 use caching_ext::KeyedEntityStore;
-use caching_ext::McErrorKind;
-use caching_ext::McResult;
-use caching_ext::MemcacheEntity;
 use caching_ext::MemcacheHandler;
+use caching_ext::ThriftMemcacheEntity;
 use context::CoreContext;
 use fbthrift::compact_protocol;
 use memcache::KeyGen;
@@ -34,20 +34,11 @@ use mercurial_types::HgChangesetId;
 use mercurial_types::HgNodeHash;
 use mononoke_types::ChangesetId;
 use mononoke_types::RepositoryId;
-use stats::prelude::*;
 
 use super::BonsaiHgMapping;
 use super::BonsaiHgMappingEntry;
 use super::BonsaiOrHgChangesetIds;
 
-define_stats! {
-    prefix = "mononoke.bonsai_hg_mapping";
-    memcache_hit: timeseries("memcache.hit"; Rate, Sum),
-    memcache_miss: timeseries("memcache.miss"; Rate, Sum),
-    memcache_internal_err: timeseries("memcache.internal_err"; Rate, Sum),
-    memcache_deserialize_err: timeseries("memcache.deserialize_err"; Rate, Sum),
-}
-
 #[derive(Abomonation, Clone, Debug, Eq, Hash, PartialEq)]
 pub struct BonsaiHgMappingCacheEntry {
     pub repo_id: RepositoryId,
@@ -109,9 +100,10 @@ impl BonsaiHgMappingCacheEntry {
     }
 }
 
-/// Used for cache key generation
+/// Used for cache key generation, and to let callers (e.g. admin tooling) look up either side of
+/// the mapping's cache without needing to know which key type it was originally filled under.
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
-enum BonsaiOrHgChangesetId {
+pub enum BonsaiOrHgChangesetId {
     Bonsai(ChangesetId),
     Hg(HgChangesetId),
 }
@@ -128,6 +120,13 @@ impl From<HgChangesetId> for BonsaiOrHgChangesetId {
     }
 }
 
+/// A point-in-time snapshot of what is cached for a single changeset id, for admin tooling that
+/// needs to diagnose stale-cache issues without going through the normal read path.
+pub struct CacheSummary {
+    pub cachelib: Option<BonsaiHgMappingEntry>,
+    pub memcache: Option<BonsaiHgMappingEntry>,
+}
+
 pub struct CachingBonsaiHgMapping {
     mapping: Arc<dyn BonsaiHgMapping>,
     cachelib: CachelibHandler<BonsaiHgMappingCacheEntry>,
@@ -152,6 +151,39 @@ impl CachingBonsaiHgMapping {
         Self::new(mapping, CacheHandlerFactory::Mocked).unwrap()
     }
 
+    /// Report what is cached for `id` in cachelib and memcache, without going to the database.
+    /// Intended for admin tooling diagnosing stale-cache issues.
+    pub async fn cache_summary(&self, id: BonsaiOrHgChangesetId) -> Result<CacheSummary> {
+        let repo_id = self.repo_id();
+        let cache_key = get_cache_key(repo_id, &id);
+
+        let cachelib = self
+            .cachelib
+            .get_cached(&cache_key)?
+            .map(|entry| entry.into_entry(repo_id))
+            .transpose()?;
+
+        let memcache_key = self.keygen.key(&cache_key);
+        let memcache = match self.memcache.get(memcache_key).await? {
+            Some(bytes) => Some(
+                BonsaiHgMappingCacheEntry::from_bytes(bytes)
+                    .map_err(|_| anyhow!("Failed to deserialize memcache entry for {:?}", id))?
+                    .into_entry(repo_id)?,
+            ),
+            None => None,
+        };
+
+        Ok(CacheSummary { cachelib, memcache })
+    }
+
+    /// Evict `id` from cachelib, so that the next lookup misses and goes to the database. Unlike
+    /// cachelib, memcache entries here have no removal API and are left to expire on their own
+    /// TTL or be corrected on the next write.
+    pub fn purge_cachelib(&self, id: BonsaiOrHgChangesetId) -> Result<()> {
+        let cache_key = get_cache_key(self.repo_id(), &id);
+        self.cachelib.remove_cached(&cache_key)
+    }
+
     fn create_key_gen() -> Result<KeyGen> {
         let key_prefix = "scm.mononoke.bonsai_hg_mapping";
 
@@ -162,16 +194,16 @@ impl CachingBonsaiHgMapping {
     }
 }
 
-fn memcache_deserialize(bytes: Bytes) -> McResult<BonsaiHgMappingCacheEntry> {
-    let thrift_entry =
-        compact_protocol::deserialize(bytes).map_err(|_| McErrorKind::Deserialization);
-    thrift_entry.and_then(|entry| {
-        BonsaiHgMappingCacheEntry::from_thrift(entry).map_err(|_| McErrorKind::Deserialization)
-    })
-}
+impl ThriftMemcacheEntity for BonsaiHgMappingCacheEntry {
+    fn into_bytes(&self) -> Bytes {
+        compact_protocol::serialize(&self.clone().into_thrift())
+    }
 
-fn memcache_serialize(entry: &BonsaiHgMappingCacheEntry) -> Bytes {
-    compact_protocol::serialize(&entry.clone().into_thrift())
+    fn from_bytes(bytes: Bytes) -> Result<Self> {
+        compact_protocol::deserialize(bytes)
+            .map_err(|_| anyhow!("failed to deserialize BonsaiHgMappingCacheEntry from thrift"))
+            .and_then(Self::from_thrift)
+    }
 }
 
 const CHUNK_SIZE: usize = 1000;
@@ -184,7 +216,18 @@ impl BonsaiHgMapping for CachingBonsaiHgMapping {
     }
 
     async fn add(&self, ctx: &CoreContext, entry: BonsaiHgMappingEntry) -> Result<bool, Error> {
-        self.mapping.add(ctx, entry).await
+        let added = self.mapping.add(ctx, entry.clone()).await?;
+
+        // Warm the cache with the entry we just wrote, so that a subsequent `get()` (e.g. from a
+        // client pulling the commit we just derived hg data for) doesn't have to pay for a cold
+        // cachelib/memcache miss and a round trip to the DB. Both key spaces are filled, since
+        // `get()` can be looked up by either bonsai or hg changeset id.
+        let cache_request = (ctx, self);
+        let cache_entry = BonsaiHgMappingCacheEntry::from_entry(entry, self.repo_id());
+        fill_cache(&cache_request, iter::once((&cache_entry.bcs_id, &cache_entry))).await;
+        fill_cache(&cache_request, iter::once((&cache_entry.hg_cs_id, &cache_entry))).await;
+
+        Ok(added)
     }
 
     async fn get(
@@ -246,16 +289,6 @@ fn get_cache_key(repo_id: RepositoryId, cs: &BonsaiOrHgChangesetId) -> String {
     format!("{}.{:?}", repo_id.prefix(), cs)
 }
 
-impl MemcacheEntity for BonsaiHgMappingCacheEntry {
-    fn serialize(&self) -> Bytes {
-        memcache_serialize(self)
-    }
-
-    fn deserialize(bytes: Bytes) -> McResult<Self> {
-        memcache_deserialize(bytes)
-    }
-}
-
 type CacheRequest<'a> = (&'a CoreContext, &'a CachingBonsaiHgMapping);
 
 impl EntityStore<BonsaiHgMappingCacheEntry> for CacheRequest<'_> {
@@ -274,8 +307,13 @@ impl EntityStore<BonsaiHgMappingCacheEntry> for CacheRequest<'_> {
         &mapping.memcache
     }
 
-    fn cache_determinator(&self, _: &BonsaiHgMappingCacheEntry) -> CacheDisposition {
-        CacheDisposition::Cache(CacheTtl::NoTtl)
+    fn cache_determinator(
+        &self,
+        _key: &str,
+        _: &BonsaiHgMappingCacheEntry,
+        _serialized_size: &dyn Fn() -> usize,
+    ) -> Result<CacheDisposition, Error> {
+        Ok(CacheDisposition::Cache(CacheTtl::NoTtl))
     }
 
     caching_ext::impl_singleton_stats!("bonsai_hg_mapping");
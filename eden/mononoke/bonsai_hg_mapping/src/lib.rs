@@ -5,6 +5,7 @@
  * GNU General Public License version 2.
  */
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use anyhow::Error;
@@ -34,11 +35,17 @@ use stats::prelude::*;
 mod caching;
 mod errors;
 mod mem_writes_bonsai_hg_mapping;
+mod verify;
 use futures::FutureExt;
 
+pub use crate::caching::BonsaiOrHgChangesetId;
+pub use crate::caching::CacheSummary;
 pub use crate::caching::CachingBonsaiHgMapping;
 pub use crate::errors::ErrorKind;
 pub use crate::mem_writes_bonsai_hg_mapping::MemWritesBonsaiHgMapping;
+pub use crate::verify::verify_mapping_batch;
+pub use crate::verify::MappingMismatch;
+pub use crate::verify::VERIFY_BATCH_SIZE;
 
 define_stats! {
     prefix = "mononoke.bonsai_hg_mapping";
@@ -46,6 +53,7 @@ define_stats! {
     gets_master: timeseries(Rate, Sum),
     adds: timeseries(Rate, Sum),
     get_many_hg_by_prefix: timeseries(Rate, Sum),
+    get_many_hg_by_prefixes: timeseries(Rate, Sum),
 }
 
 #[derive(Clone, Debug, Hash, Eq, PartialEq)]
@@ -154,6 +162,27 @@ pub trait BonsaiHgMapping: Send + Sync {
         high: HgChangesetId,
         limit: usize,
     ) -> Result<Vec<HgChangesetId>, Error>;
+
+    /// Resolve multiple hg changeset id prefixes at once. Each prefix is resolved via the same
+    /// limit-pushed range scan as [`get_many_hg_by_prefix`], but all prefixes are dispatched
+    /// concurrently and the results are grouped per prefix, so batch ambiguity checks (e.g.
+    /// resolving several short hashes from one client request) don't pay for a strictly
+    /// sequential one-query-per-prefix round trip.
+    async fn get_many_hg_by_prefixes(
+        &self,
+        ctx: &CoreContext,
+        cs_prefixes: Vec<HgChangesetIdPrefix>,
+        limit: usize,
+    ) -> Result<HashMap<HgChangesetIdPrefix, HgChangesetIdsResolvedFromPrefix>, Error> {
+        STATS::get_many_hg_by_prefixes.add_value(1);
+        let resolved = future::try_join_all(
+            cs_prefixes
+                .iter()
+                .map(|cs_prefix| self.get_many_hg_by_prefix(ctx, *cs_prefix, limit)),
+        )
+        .await?;
+        Ok(cs_prefixes.into_iter().zip(resolved).collect())
+    }
 }
 
 #[derive(Clone)]
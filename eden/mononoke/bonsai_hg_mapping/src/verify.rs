@@ -0,0 +1,85 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::collections::HashMap;
+
+use anyhow::Error;
+use context::CoreContext;
+use mercurial_types::HgChangesetId;
+use mononoke_types::ChangesetId;
+
+use crate::BonsaiHgMapping;
+
+/// The number of (bcs_id, hg_cs_id) pairs looked up per `get` call in
+/// [`verify_mapping_batch`]. Chosen to match the batch size `get` is
+/// routinely called with elsewhere in this crate's callers (e.g. backfills).
+pub const VERIFY_BATCH_SIZE: usize = 1000;
+
+/// The result of cross-checking one independently-derived (bcs_id, hg_cs_id)
+/// pair against what's actually stored in a [`BonsaiHgMapping`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MappingMismatch {
+    /// No mapping is stored for this changeset at all.
+    Missing {
+        bcs_id: ChangesetId,
+        expected_hg_cs_id: HgChangesetId,
+    },
+    /// A mapping is stored, but it disagrees with what was expected.
+    Conflicting {
+        bcs_id: ChangesetId,
+        expected_hg_cs_id: HgChangesetId,
+        stored_hg_cs_id: HgChangesetId,
+    },
+}
+
+/// Cross-check a batch of independently-derived (bcs_id, expected hg_cs_id)
+/// pairs against what's stored in `mapping`, in chunks of
+/// [`VERIFY_BATCH_SIZE`], and return every mismatch found.
+///
+/// This is a verification primitive, not a derivation one: it doesn't
+/// recompute anything itself, since doing so needs the derivation stack,
+/// which this crate can't depend on without creating a dependency cycle
+/// (derived data crates depend on `bonsai_hg_mapping`, not the other way
+/// around). Callers that can derive hg changesets -- e.g. a backfill job
+/// or a standalone verification tool -- are expected to compute `expected`
+/// themselves and pass it in here to validate backfills or detect
+/// historical corruption in the mapping table.
+pub async fn verify_mapping_batch(
+    ctx: &CoreContext,
+    mapping: &dyn BonsaiHgMapping,
+    expected: &[(ChangesetId, HgChangesetId)],
+) -> Result<Vec<MappingMismatch>, Error> {
+    let mut mismatches = Vec::new();
+
+    for chunk in expected.chunks(VERIFY_BATCH_SIZE) {
+        let bcs_ids: Vec<ChangesetId> = chunk.iter().map(|(bcs_id, _)| *bcs_id).collect();
+        let stored = mapping.get(ctx, bcs_ids.into()).await?;
+        let stored_by_bcs: HashMap<ChangesetId, HgChangesetId> = stored
+            .into_iter()
+            .map(|entry| (entry.bcs_id, entry.hg_cs_id))
+            .collect();
+
+        for (bcs_id, expected_hg_cs_id) in chunk {
+            match stored_by_bcs.get(bcs_id) {
+                None => mismatches.push(MappingMismatch::Missing {
+                    bcs_id: *bcs_id,
+                    expected_hg_cs_id: *expected_hg_cs_id,
+                }),
+                Some(stored_hg_cs_id) if stored_hg_cs_id != expected_hg_cs_id => {
+                    mismatches.push(MappingMismatch::Conflicting {
+                        bcs_id: *bcs_id,
+                        expected_hg_cs_id: *expected_hg_cs_id,
+                        stored_hg_cs_id: *stored_hg_cs_id,
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    Ok(mismatches)
+}
@@ -19,7 +19,9 @@ use bonsai_hg_mapping::BonsaiHgMapping;
 use bonsai_hg_mapping::BonsaiHgMappingEntry;
 use bonsai_hg_mapping::BonsaiOrHgChangesetIds;
 use bonsai_hg_mapping::CachingBonsaiHgMapping;
+use bonsai_hg_mapping::verify_mapping_batch;
 use bonsai_hg_mapping::ErrorKind;
+use bonsai_hg_mapping::MappingMismatch;
 use bonsai_hg_mapping::SqlBonsaiHgMappingBuilder;
 use context::CoreContext;
 use fbinit::FacebookInit;
@@ -233,6 +235,55 @@ async fn get_many_hg_by_prefix<M: BonsaiHgMapping>(fb: FacebookInit, mapping: M)
     assert_eq!(result, HgChangesetIdsResolvedFromPrefix::NoMatch);
 }
 
+async fn get_many_hg_by_prefixes<M: BonsaiHgMapping>(fb: FacebookInit, mapping: M) {
+    let ctx = CoreContext::test_mock(fb);
+
+    let entry1 = BonsaiHgMappingEntry {
+        hg_cs_id: hg::ONES_CSID,
+        bcs_id: bonsai::ONES_CSID,
+    };
+    let entry2 = BonsaiHgMappingEntry {
+        hg_cs_id: hg::TWOS_CSID,
+        bcs_id: bonsai::TWOS_CSID,
+    };
+
+    assert!(
+        mapping
+            .add(&ctx, entry1.clone())
+            .await
+            .expect("Adding entry1 failed")
+    );
+    assert!(
+        mapping
+            .add(&ctx, entry2.clone())
+            .await
+            .expect("Adding entry2 failed")
+    );
+
+    let ones_prefix = HgChangesetIdPrefix::from_bytes(&hg::ONES_CSID.as_ref()[0..8]).unwrap();
+    let twos_prefix = HgChangesetIdPrefix::from_bytes(&hg::TWOS_CSID.as_ref()[0..8]).unwrap();
+    let threes_prefix =
+        HgChangesetIdPrefix::from_bytes(&hg::THREES_CSID.as_ref()[0..16]).unwrap();
+
+    let result = mapping
+        .get_many_hg_by_prefixes(&ctx, vec![ones_prefix, twos_prefix, threes_prefix], 10)
+        .await
+        .expect("Failed to get hg changesets by their prefixes");
+
+    assert_eq!(
+        result.get(&ones_prefix),
+        Some(&HgChangesetIdsResolvedFromPrefix::Single(hg::ONES_CSID))
+    );
+    assert_eq!(
+        result.get(&twos_prefix),
+        Some(&HgChangesetIdsResolvedFromPrefix::Single(hg::TWOS_CSID))
+    );
+    assert_eq!(
+        result.get(&threes_prefix),
+        Some(&HgChangesetIdsResolvedFromPrefix::NoMatch)
+    );
+}
+
 async fn get_hg_in_range<M: BonsaiHgMapping>(fb: FacebookInit, mapping: M) {
     let ctx = CoreContext::test_mock(fb);
 
@@ -439,6 +490,17 @@ async fn test_get_many_hg_by_prefix(fb: FacebookInit) {
     .await;
 }
 
+#[fbinit::test]
+async fn test_get_many_hg_by_prefixes(fb: FacebookInit) {
+    get_many_hg_by_prefixes(
+        fb,
+        SqlBonsaiHgMappingBuilder::with_sqlite_in_memory()
+            .unwrap()
+            .build(REPO_ZERO, RendezVousOptions::for_test()),
+    )
+    .await;
+}
+
 #[fbinit::test]
 async fn test_get_hg_in_range(fb: FacebookInit) {
     get_hg_in_range(
@@ -450,6 +512,72 @@ async fn test_get_hg_in_range(fb: FacebookInit) {
     .await;
 }
 
+async fn verify_batch<M: BonsaiHgMapping>(fb: FacebookInit, mapping: M) {
+    let ctx = CoreContext::test_mock(fb);
+
+    mapping
+        .add(
+            &ctx,
+            BonsaiHgMappingEntry {
+                hg_cs_id: hg::ONES_CSID,
+                bcs_id: bonsai::ONES_CSID,
+            },
+        )
+        .await
+        .expect("Adding entry failed");
+    mapping
+        .add(
+            &ctx,
+            BonsaiHgMappingEntry {
+                hg_cs_id: hg::TWOS_CSID,
+                bcs_id: bonsai::TWOS_CSID,
+            },
+        )
+        .await
+        .expect("Adding entry failed");
+
+    // ONES is correct, TWOS disagrees with what's stored, THREES is missing entirely.
+    let expected = vec![
+        (bonsai::ONES_CSID, hg::ONES_CSID),
+        (bonsai::TWOS_CSID, hg::THREES_CSID),
+        (bonsai::THREES_CSID, hg::FOURS_CSID),
+    ];
+
+    let mut mismatches = verify_mapping_batch(&ctx, &mapping, &expected)
+        .await
+        .expect("verify_mapping_batch failed");
+    mismatches.sort_by_key(|m| match m {
+        MappingMismatch::Missing { bcs_id, .. } => *bcs_id,
+        MappingMismatch::Conflicting { bcs_id, .. } => *bcs_id,
+    });
+
+    assert_eq!(
+        mismatches,
+        vec![
+            MappingMismatch::Conflicting {
+                bcs_id: bonsai::TWOS_CSID,
+                expected_hg_cs_id: hg::THREES_CSID,
+                stored_hg_cs_id: hg::TWOS_CSID,
+            },
+            MappingMismatch::Missing {
+                bcs_id: bonsai::THREES_CSID,
+                expected_hg_cs_id: hg::FOURS_CSID,
+            },
+        ]
+    );
+}
+
+#[fbinit::test]
+async fn test_verify_batch(fb: FacebookInit) {
+    verify_batch(
+        fb,
+        SqlBonsaiHgMappingBuilder::with_sqlite_in_memory()
+            .unwrap()
+            .build(REPO_ZERO, RendezVousOptions::for_test()),
+    )
+    .await;
+}
+
 #[fbinit::test]
 async fn test_overwrite(fb: FacebookInit) -> Result<(), Error> {
     let mapping = SqlBonsaiHgMappingBuilder::with_sqlite_in_memory()
@@ -182,14 +182,16 @@ impl MemcacheEntity for BonsaiSvnrevMappingCacheEntry {
             repo_id,
             bcs_id,
             svnrev,
-        } = compact_protocol::deserialize(bytes).map_err(|_| McErrorKind::Deserialization)?;
+        } = compact_protocol::deserialize(bytes)
+            .map_err(|e| McErrorKind::Deserialization(e.to_string()))?;
 
         let repo_id = RepositoryId::new(repo_id);
-        let bcs_id = ChangesetId::from_thrift(bcs_id).map_err(|_| McErrorKind::Deserialization)?;
+        let bcs_id = ChangesetId::from_thrift(bcs_id)
+            .map_err(|e| McErrorKind::Deserialization(e.to_string()))?;
         let svnrev = Svnrev::new(
             svnrev
                 .try_into()
-                .map_err(|_| McErrorKind::Deserialization)?,
+                .map_err(|e: std::num::TryFromIntError| McErrorKind::Deserialization(e.to_string()))?,
         );
 
         Ok(BonsaiSvnrevMappingCacheEntry {
@@ -218,8 +220,13 @@ impl EntityStore<BonsaiSvnrevMappingCacheEntry> for CacheRequest<'_> {
         &mapping.memcache
     }
 
-    fn cache_determinator(&self, _: &BonsaiSvnrevMappingCacheEntry) -> CacheDisposition {
-        CacheDisposition::Cache(CacheTtl::NoTtl)
+    fn cache_determinator(
+        &self,
+        _key: &str,
+        _: &BonsaiSvnrevMappingCacheEntry,
+        _serialized_size: &dyn Fn() -> usize,
+    ) -> Result<CacheDisposition, Error> {
+        Ok(CacheDisposition::Cache(CacheTtl::NoTtl))
     }
 
     caching_ext::impl_singleton_stats!("bonsai_svnrev_mapping");
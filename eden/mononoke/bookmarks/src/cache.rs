@@ -349,10 +349,30 @@ impl Bookmarks for CachedBookmarks {
         ctx: CoreContext,
         bookmark: &BookmarkKey,
     ) -> BoxFuture<'static, Result<Option<ChangesetId>>> {
-        // NOTE: If you to implement a Freshness notion here and try to fetch from cache, be
-        // mindful that not all bookmarks are cached, so a cache miss here does not necessarily
-        // mean that the Bookmark does not exist.
-        self.bookmarks.get(ctx, bookmark)
+        // Not all bookmarks are cached (e.g. non-publishing kinds), so a cache miss here does
+        // not necessarily mean that the bookmark does not exist: fall back to the underlying
+        // store in that case rather than reporting it as absent.
+        let ttl = match ttl() {
+            Some(ttl) => ttl,
+            None => return self.bookmarks.get(ctx, bookmark),
+        };
+
+        let cache = self.cache(ctx.clone(), ttl);
+        let bookmark = bookmark.clone();
+        let bookmarks = self.bookmarks.clone();
+
+        async move {
+            match &*cache.current.await {
+                Ok(cached) => {
+                    if let Some((_kind, cs_id)) = cached.get(&bookmark) {
+                        return Ok(Some(*cs_id));
+                    }
+                    bookmarks.get(ctx, &bookmark).await
+                }
+                Err(err) => Err(Error::from(err.clone())),
+            }
+        }
+        .boxed()
     }
 
     /// Drop this cache without kicking off a refresh right now.
@@ -1051,4 +1071,102 @@ mod tests {
             have == want
         }
     }
+
+    struct CountingBookmarks {
+        bookmarks: BTreeMap<BookmarkKey, (BookmarkKind, ChangesetId)>,
+        get_calls: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Bookmarks for CountingBookmarks {
+        fn get(
+            &self,
+            _ctx: CoreContext,
+            bookmark: &BookmarkKey,
+        ) -> BoxFuture<'static, Result<Option<ChangesetId>>> {
+            self.get_calls
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            let result = self.bookmarks.get(bookmark).map(|(_, cs_id)| *cs_id);
+            future::ok(result).boxed()
+        }
+
+        async fn create_subscription(
+            &self,
+            _: &CoreContext,
+            _: Freshness,
+        ) -> Result<Box<dyn BookmarksSubscription>> {
+            unimplemented!()
+        }
+
+        fn list(
+            &self,
+            _ctx: CoreContext,
+            _freshness: Freshness,
+            prefix: &BookmarkPrefix,
+            categories: &[BookmarkCategory],
+            kinds: &[BookmarkKind],
+            pagination: &BookmarkPagination,
+            limit: u64,
+        ) -> BoxStream<'static, Result<(Bookmark, ChangesetId)>> {
+            let result = mock_bookmarks_response(
+                &self.bookmarks,
+                prefix,
+                categories,
+                kinds,
+                pagination,
+                limit,
+            );
+            stream::iter(result.into_iter().map(Ok)).boxed()
+        }
+
+        fn create_transaction(&self, _ctx: CoreContext) -> Box<dyn BookmarkTransaction> {
+            unimplemented!()
+        }
+    }
+
+    #[fbinit::test]
+    async fn test_cached_get_hits_cache(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        let repo_id = RepositoryId::new(0);
+
+        let cached_key = BookmarkKey::new("cached").unwrap();
+        let missing_key = BookmarkKey::new("missing").unwrap();
+
+        let mut bookmarks = BTreeMap::new();
+        bookmarks.insert(
+            cached_key.clone(),
+            (BookmarkKind::PullDefaultPublishing, ONES_CSID),
+        );
+
+        let inner = Arc::new(CountingBookmarks {
+            bookmarks,
+            get_calls: std::sync::atomic::AtomicUsize::new(0),
+        });
+        let cached = CachedBookmarks::new(inner.clone(), repo_id);
+
+        let just_knobs = JustKnobsInMemory::new(
+            hashmap! {"scm/mononoke:bookmarks_cache_ttl_ms".to_string() => KnobVal::Int(100_000)},
+        );
+        with_just_knobs_async(just_knobs, async move {
+            // A bookmark present in the (list-derived) cache should be answered without
+            // calling through to the underlying store's `get`.
+            let found = cached.get(ctx.clone(), &cached_key).await?;
+            assert_eq!(found, Some(ONES_CSID));
+            assert_eq!(
+                inner.get_calls.load(std::sync::atomic::Ordering::SeqCst),
+                0
+            );
+
+            // A bookmark absent from the cache falls back to the underlying store.
+            let missing = cached.get(ctx.clone(), &missing_key).await?;
+            assert_eq!(missing, None);
+            assert_eq!(
+                inner.get_calls.load(std::sync::atomic::Ordering::SeqCst),
+                1
+            );
+
+            Ok(())
+        })
+        .await
+    }
 }
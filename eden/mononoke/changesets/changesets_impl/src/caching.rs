@@ -8,6 +8,7 @@
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::Duration;
 
 use abomonation_derive::Abomonation;
 use anyhow::Error;
@@ -51,12 +52,22 @@ define_stats! {
     gets: timeseries(Rate, Sum),
     get_many_by_prefix: timeseries(Rate, Sum),
     adds: timeseries(Rate, Sum),
+    negative_cache_hits: timeseries(Rate, Sum),
 }
 
+/// How long a changeset id that was not found in the backing store is remembered as missing.
+/// Kept fairly short since a changeset that doesn't exist yet can always be created later (e.g.
+/// by a concurrent push).
+const NEGATIVE_CACHE_TTL: Duration = Duration::from_secs(10);
+
 pub fn get_cache_key(repo_id: RepositoryId, cs_id: &ChangesetId) -> String {
     format!("{}.{}", repo_id.prefix(), cs_id)
 }
 
+fn get_negative_cache_key(repo_id: RepositoryId, cs_id: &ChangesetId) -> String {
+    format!("{}.missing.{}", repo_id.prefix(), cs_id)
+}
+
 #[derive(Clone, Debug, Abomonation, RefCast)]
 #[repr(transparent)]
 pub struct ChangesetEntryWrapper(ChangesetEntry);
@@ -65,6 +76,10 @@ pub struct ChangesetEntryWrapper(ChangesetEntry);
 pub struct CachingChangesets {
     changesets: Arc<dyn Changesets>,
     cachelib: CachelibHandler<ChangesetEntryWrapper>,
+    /// Local-only cache of changeset ids that are known not to exist, so that hot but
+    /// nonexistent lookups (e.g. during graph traversals that probe ahead of what has been
+    /// imported) don't repeatedly fall through to SQL.
+    negative_cache: CachelibHandler<bool>,
     memcache: MemcacheHandler,
     keygen: KeyGen,
     repo_id: RepositoryId,
@@ -89,6 +104,7 @@ impl CachingChangesets {
             repo_id: changesets.repo_id(),
             changesets,
             cachelib: cache_handler_factory.cachelib(),
+            negative_cache: cache_handler_factory.cachelib(),
             memcache: cache_handler_factory.memcache(),
             keygen: get_keygen(),
         }
@@ -105,6 +121,7 @@ impl CachingChangesets {
             repo_id: self.repo_id,
             changesets: self.changesets.clone(),
             cachelib: CachelibHandler::create_mock(),
+            negative_cache: CachelibHandler::create_mock(),
             memcache: self.memcache.clone(),
             keygen: self.keygen.clone(),
         }
@@ -151,9 +168,25 @@ impl Changesets for CachingChangesets {
         cs_id: ChangesetId,
     ) -> Result<Option<ChangesetEntry>, Error> {
         STATS::gets.add_value(1);
+        if self
+            .negative_cache
+            .get_cached(&get_negative_cache_key(self.repo_id, &cs_id))?
+            .is_some()
+        {
+            STATS::negative_cache_hits.add_value(1);
+            return Ok(None);
+        }
         let ctx = (ctx, self);
         let mut map = get_or_fill(&ctx, hashset![cs_id]).await?;
-        Ok(map.remove(&cs_id).map(|entry| entry.0))
+        let entry = map.remove(&cs_id).map(|entry| entry.0);
+        if entry.is_none() {
+            let _ = self.negative_cache.set_cached(
+                &get_negative_cache_key(self.repo_id, &cs_id),
+                &true,
+                Some(NEGATIVE_CACHE_TTL),
+            );
+        }
+        Ok(entry)
     }
 
     async fn get_many(
@@ -162,13 +195,30 @@ impl Changesets for CachingChangesets {
         cs_ids: Vec<ChangesetId>,
     ) -> Result<Vec<ChangesetEntry>, Error> {
         STATS::gets.add_value(1);
+        let mut to_fetch = HashSet::new();
+        for cs_id in cs_ids {
+            if self
+                .negative_cache
+                .get_cached(&get_negative_cache_key(self.repo_id, &cs_id))?
+                .is_some()
+            {
+                STATS::negative_cache_hits.add_value(1);
+            } else {
+                to_fetch.insert(cs_id);
+            }
+        }
         let ctx = (ctx, self);
-        let res = get_or_fill_chunked(&ctx, cs_ids.into_iter().collect(), 1000, 2)
-            .await?
-            .into_values()
-            .map(|val| val.0)
-            .collect();
-        Ok(res)
+        let found = get_or_fill_chunked(&ctx, to_fetch.clone(), 1000, 2).await?;
+        for cs_id in &to_fetch {
+            if !found.contains_key(cs_id) {
+                let _ = self.negative_cache.set_cached(
+                    &get_negative_cache_key(self.repo_id, cs_id),
+                    &true,
+                    Some(NEGATIVE_CACHE_TTL),
+                );
+            }
+        }
+        Ok(found.into_values().map(|val| val.0).collect())
     }
 
     /// Use caching for the full changeset ids and slower path otherwise.
@@ -239,7 +289,7 @@ impl MemcacheEntity for ChangesetEntryWrapper {
         compact_protocol::deserialize(bytes)
             .and_then(ChangesetEntry::from_thrift)
             .map(ChangesetEntryWrapper)
-            .map_err(|_| McErrorKind::Deserialization)
+            .map_err(|e| McErrorKind::Deserialization(e.to_string()))
     }
 }
 
@@ -261,8 +311,13 @@ impl EntityStore<ChangesetEntryWrapper> for CacheRequest<'_> {
         &mapping.memcache
     }
 
-    fn cache_determinator(&self, _: &ChangesetEntryWrapper) -> CacheDisposition {
-        CacheDisposition::Cache(CacheTtl::NoTtl)
+    fn cache_determinator(
+        &self,
+        _key: &str,
+        _: &ChangesetEntryWrapper,
+        _serialized_size: &dyn Fn() -> usize,
+    ) -> Result<CacheDisposition, Error> {
+        Ok(CacheDisposition::Cache(CacheTtl::NoTtl))
     }
 
     caching_ext::impl_singleton_stats!("changesets");
@@ -5,17 +5,22 @@
  * GNU General Public License version 2.
  */
 
+use std::num::NonZeroU32;
+use std::num::NonZeroUsize;
 use std::sync::Arc;
 
 use anyhow::anyhow;
 use anyhow::Context;
 use anyhow::Result;
+use async_limiter::AsyncLimiter;
 use clap::Parser;
 use futures::channel::mpsc;
 use futures::sink::SinkExt;
 use futures::stream;
 use futures::stream::StreamExt;
 use futures::stream::TryStreamExt;
+use governor::Quota;
+use governor::RateLimiter;
 use mononoke_app::MononokeApp;
 use retry::retry_always;
 use slog::info;
@@ -40,6 +45,13 @@ pub struct CommandArgs {
     /// Only set the generation, don't inline small values
     #[clap(long)]
     skip_inline_small_values: bool,
+    /// Number of shards to run the mark phase on concurrently. Default 1 (sequential, as before).
+    #[clap(long, default_value_t = NonZeroUsize::new(1).unwrap())]
+    shard_parallelism: NonZeroUsize,
+    /// Cap on how many keys per second may be pulled off a single shard's key stream and handed
+    /// to the processor. Unset means no per-shard cap, only the global --scheduled-max.
+    #[clap(long)]
+    shard_rate_limit_per_sec: Option<NonZeroU32>,
 }
 
 async fn handle_one_key(
@@ -60,6 +72,55 @@ async fn handle_one_key(
     Ok(())
 }
 
+/// Feed one shard's keys into `key_channel` for marking, honoring `rate_limiter` if set, then
+/// record a checkpoint so a restarted run can skip this shard for this `mark_generation`.
+///
+/// Skips the shard entirely (after an info log) if it's already checkpointed for
+/// `mark_generation` or later, e.g. because a previous run of this same mark phase reached it
+/// before being interrupted.
+async fn mark_shard(
+    shard: usize,
+    sqlblob: Arc<Sqlblob>,
+    logger: Arc<Logger>,
+    mark_generation: u64,
+    rate_limiter: Option<AsyncLimiter>,
+    key_channel: mpsc::Sender<String>,
+) -> Result<()> {
+    if let Some(checkpointed_generation) = sqlblob.get_mark_checkpoint(shard).await? {
+        if checkpointed_generation >= mark_generation {
+            info!(
+                logger,
+                "Skipping shard {}, already marked up to generation {}", shard, checkpointed_generation
+            );
+            return Ok(());
+        }
+    }
+
+    info!(logger, "Starting mark on data keys from shard {}", shard);
+    let keys = sqlblob.get_keys_from_shard(shard);
+    let keys = match rate_limiter {
+        Some(rate_limiter) => {
+            let throttled = keys.then(move |key| {
+                let rate_limiter = rate_limiter.clone();
+                async move {
+                    if key.is_ok() {
+                        let _ = rate_limiter.access().await;
+                    }
+                    key
+                }
+            });
+            throttled.left_stream()
+        }
+        None => keys.right_stream(),
+    };
+
+    keys.forward(key_channel.sink_err_into()).await?;
+
+    sqlblob.set_mark_checkpoint(shard, mark_generation).await?;
+    info!(logger, "Completed mark on data keys from shard {}", shard);
+    Ok(())
+}
+
 async fn handle_initial_generation(store: &Sqlblob, shard: usize, logger: &Logger) -> Result<()> {
     retry_always(
         logger,
@@ -111,6 +172,8 @@ pub async fn run(app: MononokeApp, args: CommandArgs) -> Result<()> {
     let logger = Arc::new(logger);
 
     let inline_small_values = !args.skip_inline_small_values;
+    let shard_parallelism = args.shard_parallelism.get();
+    let shard_rate_limit_per_sec = args.shard_rate_limit_per_sec;
 
     // Hold mark generation constant for run
     let mark_generation = sqlblob.get_mark_generation();
@@ -144,23 +207,44 @@ pub async fn run(app: MononokeApp, args: CommandArgs) -> Result<()> {
         (tx, task)
     };
 
-    // Foreach shard in shard_range
-    for shard in shard_range {
-        info!(logger, "Starting mark on data keys from shard {}", shard);
-        let res = sqlblob
-            .get_keys_from_shard(shard)
-            .forward(key_channel.clone().sink_err_into())
-            .await;
-        // Report processing errors ahead of key errors - that way, we don't lose the error if the channel goes away because of an error
-        if res.is_err() {
-            std::mem::drop(key_channel);
-            processor.await??;
-            return res;
-        }
-    }
-
-    // Drop the spare sender so that the processor task can exit
+    // Run shards concurrently, up to --shard-parallelism at a time, each with its own rate
+    // limiter (rate limiters aren't shared across shards, so this is a per-shard cap, not a
+    // fleet-wide one).
+    let mark_shard_futures: Vec<_> = shard_range
+        .map(|shard| {
+            let sqlblob = Arc::clone(&sqlblob);
+            let logger = Arc::clone(&logger);
+            let key_channel = key_channel.clone();
+            Ok(async move {
+                let rate_limiter = match shard_rate_limit_per_sec {
+                    Some(limit) => {
+                        let limiter = RateLimiter::direct(Quota::per_second(limit));
+                        Some(AsyncLimiter::new(limiter).await)
+                    }
+                    None => None,
+                };
+                mark_shard(
+                    shard,
+                    sqlblob,
+                    logger,
+                    mark_generation,
+                    rate_limiter,
+                    key_channel,
+                )
+                .await
+            })
+        })
+        .collect();
+    let res = stream::iter(mark_shard_futures.into_iter())
+        .try_for_each_concurrent(args.shard_parallelism.get(), |fut| fut)
+        .await;
+
+    // Report processing errors ahead of key errors - that way, we don't lose the error if the channel goes away because of an error
     std::mem::drop(key_channel);
+    if res.is_err() {
+        processor.await??;
+        return res;
+    }
 
     processor.await??;
     info!(logger, "Completed marking generation {}", mark_generation);
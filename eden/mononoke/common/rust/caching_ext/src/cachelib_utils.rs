@@ -10,7 +10,9 @@ use std::hash::Hash;
 use std::time::Duration;
 
 use anyhow::Result;
+use cachelib::get_available_space;
 use cachelib::get_cached;
+use cachelib::remove_cached;
 use cachelib::set_cached;
 use cachelib::Abomonation;
 use cachelib::VolatileLruCachePool;
@@ -18,6 +20,13 @@ use cachelib::VolatileLruCachePool;
 use crate::mock_store::MockStore;
 use crate::CachelibKey;
 
+/// Cachelib is considered under memory pressure once fewer than this many bytes remain
+/// unallocated process-wide. Cachelib doesn't expose per-pool eviction rate or free space in
+/// this tree's bindings, only the process-wide `get_available_space`, so that's the signal used
+/// here; callers that need a pool-specific threshold should additionally gate on
+/// `EntityStore::cache_value_size`.
+const LOW_AVAILABLE_SPACE_BYTES: usize = 64 * 1024 * 1024;
+
 #[derive(Clone)]
 pub enum CachelibHandler<T> {
     Real(VolatileLruCachePool),
@@ -31,6 +40,19 @@ impl<T> From<VolatileLruCachePool> for CachelibHandler<T> {
     }
 }
 
+impl<T> CachelibHandler<T> {
+    /// Best-effort signal for whether cachelib is low on room. Always `false` for the mock and
+    /// no-op handlers, so tests built on them are unaffected by this.
+    pub fn is_under_pressure(&self) -> bool {
+        match self {
+            CachelibHandler::Real(_) => get_available_space()
+                .map(|available| available < LOW_AVAILABLE_SPACE_BYTES)
+                .unwrap_or(false),
+            CachelibHandler::Mock(_) | CachelibHandler::Noop => false,
+        }
+    }
+}
+
 impl<T: Abomonation + Clone + Send + 'static> CachelibHandler<T> {
     pub(crate) fn get_multiple_from_cachelib<Key: Eq + Hash>(
         &self,
@@ -65,13 +87,58 @@ impl<T: Abomonation + Clone + Send + 'static> CachelibHandler<T> {
         match self {
             CachelibHandler::Real(ref cache) => set_cached(cache, key, value, ttl),
             CachelibHandler::Mock(store) => {
-                store.set(key, value.clone());
+                store.set_with_ttl(key, value.clone(), ttl);
                 Ok(true)
             }
             CachelibHandler::Noop => Ok(false),
         }
     }
 
+    /// Like calling [`Self::set_cached`] once per entry, but failures on individual entries are
+    /// swallowed rather than returned, matching how callers that fill in bulk (e.g.
+    /// `fill_multiple_cachelib`) already treat a single failed entry as not worth aborting the
+    /// rest of the batch over.
+    ///
+    /// For the mock store, this also takes its lock once for the whole batch instead of once
+    /// per entry. The real cachelib pool still issues one `set_cached` call per entry below,
+    /// since this tree's `cachelib` binding doesn't expose a batched write primitive - but
+    /// giving fill helpers a single call site here means they'll pick up a true batched path
+    /// for free whenever one lands upstream.
+    pub fn set_cached_multiple<'a>(
+        &self,
+        entries: impl IntoIterator<Item = (&'a str, &'a T, Option<Duration>)>,
+    ) {
+        match self {
+            CachelibHandler::Real(ref cache) => {
+                for (key, value, ttl) in entries {
+                    let _ = set_cached(cache, key, value, ttl);
+                }
+            }
+            CachelibHandler::Mock(store) => {
+                store.set_multiple_with_ttl(
+                    entries
+                        .into_iter()
+                        .map(|(key, value, ttl)| (key.to_owned(), value.clone(), ttl)),
+                );
+            }
+            CachelibHandler::Noop => {}
+        }
+    }
+
+    /// Evict `key` from this cache, so that the next `get_cached` for it is a miss. Used to
+    /// react to out-of-band invalidation (e.g. a tombstone received over an invalidation
+    /// channel) rather than waiting out a TTL.
+    pub fn remove_cached(&self, key: &str) -> Result<()> {
+        match self {
+            CachelibHandler::Real(ref cache) => remove_cached(cache, key).map(|_| ()),
+            CachelibHandler::Mock(store) => {
+                store.remove(key);
+                Ok(())
+            }
+            CachelibHandler::Noop => Ok(()),
+        }
+    }
+
     pub fn create_mock() -> Self {
         CachelibHandler::Mock(MockStore::new())
     }
@@ -163,4 +230,24 @@ mod tests {
             TestResult::passed()
         }
     }
+
+    #[test]
+    fn set_cached_multiple_inserts_every_entry() {
+        let cachelib_handler: CachelibHandler<String> = CachelibHandler::create_mock();
+
+        cachelib_handler.set_cached_multiple(vec![
+            ("key0", &"value0".to_string(), None),
+            ("key1", &"value1".to_string(), None),
+        ]);
+
+        assert_eq!(
+            cachelib_handler.get_cached(&"key0".to_string()).unwrap(),
+            Some("value0".to_string())
+        );
+        assert_eq!(
+            cachelib_handler.get_cached(&"key1".to_string()).unwrap(),
+            Some("value1".to_string())
+        );
+        assert_eq!(cachelib_handler.mock_store().unwrap().stats().sets, 2);
+    }
 }
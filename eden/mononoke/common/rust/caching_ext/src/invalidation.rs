@@ -0,0 +1,128 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! An opt-in channel for pushing cache invalidations (tombstones for a cachelib key) to
+//! everyone sharing a [`CachelibHandler`], so `NoTtl` entries (e.g. mappings, phases) can be
+//! corrected as soon as the underlying data is rewritten, instead of waiting for eviction.
+//!
+//! [`InvalidationChannel::Local`] only fans tombstones out within the current process (via a
+//! `tokio::sync::broadcast` channel); it exists so a store can wire up invalidation and have it
+//! actually do something in tests and single-process deployments. Making tombstones visible
+//! fleet-wide requires publishing them over a real distributed transport - this is the
+//! extension point a store's owner should replace `InvalidationChannel::Local` with once such a
+//! transport is wired in, without needing to change anything downstream of
+//! [`spawn_invalidation_listener`].
+
+use abomonation::Abomonation;
+use tokio::sync::broadcast;
+
+use crate::cachelib_utils::CachelibHandler;
+
+const CHANNEL_CAPACITY: usize = 1000;
+
+/// A channel that stores can opt into to receive tombstones for keys that were invalidated
+/// elsewhere (in this process, or - once a real transport backs `Local` - on another host).
+#[derive(Clone)]
+pub enum InvalidationChannel {
+    Local(broadcast::Sender<String>),
+    Noop,
+}
+
+impl InvalidationChannel {
+    pub fn new_local() -> Self {
+        let (sender, _receiver) = broadcast::channel(CHANNEL_CAPACITY);
+        InvalidationChannel::Local(sender)
+    }
+
+    pub fn new_noop() -> Self {
+        InvalidationChannel::Noop
+    }
+
+    /// Publish a tombstone for `key`. Delivery is best-effort: if there are no subscribers
+    /// (e.g. this store hasn't opted in), the tombstone is silently discarded, and a listener
+    /// that lags too far behind misses the oldest tombstones, since the affected entry will
+    /// still eventually expire or be corrected on next write.
+    pub fn publish(&self, key: &str) {
+        if let InvalidationChannel::Local(sender) = self {
+            let _ = sender.send(key.to_owned());
+        }
+    }
+
+    fn subscribe(&self) -> Option<broadcast::Receiver<String>> {
+        match self {
+            InvalidationChannel::Local(sender) => Some(sender.subscribe()),
+            InvalidationChannel::Noop => None,
+        }
+    }
+}
+
+/// Spawn a task that evicts `cachelib`'s copy of a key as soon as a tombstone for it arrives on
+/// `channel`. A no-op if `channel` is [`InvalidationChannel::Noop`].
+pub fn spawn_invalidation_listener<T>(channel: &InvalidationChannel, cachelib: CachelibHandler<T>)
+where
+    T: Abomonation + Clone + Send + 'static,
+{
+    let mut receiver = match channel.subscribe() {
+        Some(receiver) => receiver,
+        None => return,
+    };
+
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(key) => {
+                    let _ = cachelib.remove_cached(&key);
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => {
+                    // We missed some tombstones. The affected entries will still expire on
+                    // their own TTL (or get corrected on next write); keep listening for new
+                    // tombstones rather than giving up on invalidation entirely.
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn local_channel_evicts_subscribed_cachelib() {
+        let channel = InvalidationChannel::new_local();
+        let cachelib = CachelibHandler::<u64>::create_mock();
+
+        let key = "some-key".to_string();
+        cachelib.set_cached(&key, &42, None).unwrap();
+        assert_eq!(cachelib.get_cached(&key).unwrap(), Some(42));
+
+        spawn_invalidation_listener(&channel, cachelib.clone());
+        channel.publish(&key);
+
+        // Give the listener task a chance to run.
+        for _ in 0..100 {
+            if cachelib.get_cached(&key).unwrap().is_none() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        assert_eq!(cachelib.get_cached(&key).unwrap(), None);
+    }
+
+    #[test]
+    fn noop_channel_has_no_subscribers() {
+        let channel = InvalidationChannel::new_noop();
+        assert!(channel.subscribe().is_none());
+        // Publishing with no subscribers should not panic.
+        channel.publish("some-key");
+    }
+}
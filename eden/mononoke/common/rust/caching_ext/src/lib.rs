@@ -9,31 +9,52 @@
 
 mod cachelib_utils;
 mod factory;
+mod invalidation;
 mod memcache_utils;
 mod mock_store;
 
 use std::borrow::Borrow;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::future::Future;
 use std::hash::Hash;
+use std::hash::Hasher;
+use std::num::NonZeroU32;
+use std::sync::Arc;
+use std::sync::Mutex;
 use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
 
 use abomonation::Abomonation;
 use anyhow::Context as _;
 use anyhow::Error;
+use async_limiter::AsyncLimiter;
 use async_trait::async_trait;
 use bytes::Bytes;
 use cloned::cloned;
+use futures::channel::oneshot;
+use futures::future::BoxFuture;
+use futures::future::Shared;
 use futures::stream;
+use futures::stream::BoxStream;
 use futures::stream::StreamExt;
-use futures::stream::TryStreamExt;
+use futures::FutureExt;
+use futures::Stream;
+use governor::Quota;
+use governor::RateLimiter;
 use itertools::Itertools;
 use memcache::KeyGen;
 use memcache::MEMCACHE_VALUE_MAX_SIZE;
+use once_cell::sync::Lazy;
+use rand::Rng;
 use stats::prelude::*;
+use twox_hash::XxHash;
 
 pub use crate::cachelib_utils::CachelibHandler;
 pub use crate::factory::CacheHandlerFactory;
+pub use crate::invalidation::spawn_invalidation_listener;
+pub use crate::invalidation::InvalidationChannel;
 pub use crate::memcache_utils::MemcacheHandler;
 pub use crate::mock_store::MockStoreStats;
 
@@ -44,6 +65,9 @@ pub mod macro_reexport {
 define_stats_struct! {
     CacheStats("mononoke.cache.{}", label: String),
 
+    memo_hit: timeseries("memo.hit"; Rate, Sum),
+    memo_miss: timeseries("memo.miss"; Rate, Sum),
+
     cachelib_hit: timeseries("cachelib.hit"; Rate, Sum),
     cachelib_miss: timeseries("cachelib.miss"; Rate, Sum),
 
@@ -51,6 +75,12 @@ define_stats_struct! {
     memcache_miss: timeseries("memcache.miss"; Rate, Sum),
     memcache_internal_err: timeseries("memcache.internal_err"; Rate, Sum),
     memcache_deserialize_err: timeseries("memcache.deserialize_err"; Rate, Sum),
+    memcache_poisoned_skip: timeseries("memcache.poisoned_skip"; Rate, Sum),
+    memcache_timeout: timeseries("memcache.timeout"; Rate, Sum),
+    memcache_circuit_breaker_skip: timeseries("memcache.circuit_breaker_skip"; Rate, Sum),
+    memcache_background_write_dropped: timeseries("memcache.background_write_dropped"; Rate, Sum),
+
+    cache_determinator_err: timeseries("cache_determinator.err"; Rate, Sum),
 
     origin_hit: timeseries("origin.hit"; Rate, Sum),
     origin_miss: timeseries("origin.miss"; Rate, Sum),
@@ -74,12 +104,621 @@ pub enum McErrorKind {
     MemcacheInternal,
     /// value returned from memcache was None
     Missing,
-    /// deserialization of memcache data to Rust structures failed
-    Deserialization,
+    /// the read didn't complete within `EntityStore::memcache_timeout`
+    Timeout,
+    /// deserialization of memcache data to Rust structures failed, with a message describing why
+    /// (e.g. a thrift decode error, or a checksum/length mismatch), so operators can tell corrupt
+    /// data apart from schema skew instead of only seeing an aggregate counter tick up
+    Deserialization(String),
 }
 
 const MEMCACHE_CONCURRENCY: usize = 100;
 
+/// Cap on how many `fill_multiple_memcache` background-write tasks (the ones spawned when
+/// `MemcacheHandler::is_async()`) may be running concurrently across the whole process. Without
+/// this, a burst of fills under heavy write traffic can spawn an unbounded number of tasks that
+/// all contend for the same Memcache client at once. Once saturated, a write is dropped - not
+/// queued - and counted via `CacheStats::memcache_background_write_dropped`, on the theory that a
+/// cache fill is always safe to skip: the data's still in the backing store.
+const BACKGROUND_MEMCACHE_WRITE_CONCURRENCY: usize = 1000;
+
+static BACKGROUND_MEMCACHE_WRITE_PERMITS: Lazy<tokio::sync::Semaphore> =
+    Lazy::new(|| tokio::sync::Semaphore::new(BACKGROUND_MEMCACHE_WRITE_CONCURRENCY));
+
+/// Cap on how many results `fill_one_chunk` batches together from a single poll of
+/// `get_from_db_streamed` before writing them to cachelib/Memcache. Keeps a store that streams
+/// back thousands of rows at once from turning "fill incrementally" back into "fill all at once".
+const DB_STREAM_FILL_BATCH: usize = 100;
+
+/// Above this size, an entry is skipped from cachelib admission while the pool is under memory
+/// pressure (see `EntityStore::cache_value_size`), rather than being admitted and potentially
+/// evicting a pool's worth of smaller, hotter entries.
+const PRESSURE_ADMISSION_SIZE_THRESHOLD: usize = 8 * 1024;
+
+/// Above this size, a store that opts into [`EntityStore::memcache_compression`] has its values
+/// zstd-compressed before being written to Memcache. Below it, compression is skipped: zstd has
+/// enough per-call overhead that it isn't worth paying for entries this small, which are also
+/// unlikely to be anywhere near [`MEMCACHE_VALUE_MAX_SIZE`] in the first place.
+const MEMCACHE_COMPRESSION_THRESHOLD: usize = 16 * 1024;
+
+/// Marks an uncompressed Memcache value. Written as the first byte of the value whenever a store
+/// opts into [`EntityStore::memcache_compression`], so a reader always knows whether to
+/// zstd-decompress the rest.
+const MEMCACHE_COMPRESSION_FLAG_RAW: u8 = 0;
+/// Marks a zstd-compressed Memcache value. See [`MEMCACHE_COMPRESSION_FLAG_RAW`].
+const MEMCACHE_COMPRESSION_FLAG_ZSTD: u8 = 1;
+
+/// Prepend the compression flag byte to `bytes`, zstd-compressing it first if `compress` is set
+/// and it's larger than [`MEMCACHE_COMPRESSION_THRESHOLD`]. Falls back to storing it uncompressed
+/// if compression fails, rather than dropping the value entirely.
+///
+/// `compress` is `false` for a store that has opted into [`EntityStore::memcache_chunking`] but
+/// not [`EntityStore::memcache_compression`]: such a store's values still need the flag byte, so
+/// [`resolve_chunked_memcache_value`] can tell an ordinary value apart from a [`ChunkIndex`], but
+/// shouldn't have zstd compression silently turned on as a side effect of chunking.
+fn compress_for_memcache(bytes: Bytes, compress: bool) -> Bytes {
+    if compress && bytes.len() > MEMCACHE_COMPRESSION_THRESHOLD {
+        if let Ok(compressed) = zstd::encode_all(bytes.as_ref(), 0) {
+            let mut out = Vec::with_capacity(compressed.len() + 1);
+            out.push(MEMCACHE_COMPRESSION_FLAG_ZSTD);
+            out.extend(compressed);
+            return Bytes::from(out);
+        }
+    }
+
+    let mut out = Vec::with_capacity(bytes.len() + 1);
+    out.push(MEMCACHE_COMPRESSION_FLAG_RAW);
+    out.extend_from_slice(&bytes);
+    Bytes::from(out)
+}
+
+/// Inverse of [`compress_for_memcache`]: strip the leading flag byte, zstd-decompressing the rest
+/// if it's flagged as compressed.
+fn decompress_from_memcache(bytes: Bytes) -> McResult<Bytes> {
+    let flag = *bytes
+        .first()
+        .ok_or_else(|| McErrorKind::Deserialization("value was empty".to_string()))?;
+    let payload = bytes.slice(1..);
+    match flag {
+        MEMCACHE_COMPRESSION_FLAG_RAW => Ok(payload),
+        MEMCACHE_COMPRESSION_FLAG_ZSTD => zstd::decode_all(payload.as_ref())
+            .map(Bytes::from)
+            .map_err(|e| McErrorKind::Deserialization(format!("zstd decode failed: {}", e))),
+        _ => Err(McErrorKind::Deserialization(format!(
+            "unrecognized compression flag byte {}",
+            flag
+        ))),
+    }
+}
+
+/// A value written by a store that opts into [`EntityStore::memcache_chunking`] is split across
+/// this many bytes per part key, once it (after any [`compress_for_memcache`]) would otherwise
+/// land at or above `MEMCACHE_VALUE_MAX_SIZE`. Left with some headroom below the real limit so a
+/// part carrying a few extra bytes of overhead never itself needs re-splitting.
+const MEMCACHE_CHUNK_SIZE: usize = MEMCACHE_VALUE_MAX_SIZE - 4 * 1024;
+
+/// Marks the value stored at a chunked entry's own Memcache key as an index rather than data: the
+/// rest of the value is a [`ChunkIndex`], and the actual payload lives under `N` separate part
+/// keys derived from [`chunk_part_key`]. Shares the same leading-byte slot as
+/// [`MEMCACHE_COMPRESSION_FLAG_RAW`]/[`MEMCACHE_COMPRESSION_FLAG_ZSTD`], which is safe because a
+/// store only ever writes this flag once it's opted into [`EntityStore::memcache_chunking`], at
+/// which point every value it writes goes through the same flag-byte encoding.
+const MEMCACHE_CHUNK_INDEX_FLAG: u8 = 2;
+
+/// The fixed-size metadata [`MEMCACHE_CHUNK_INDEX_FLAG`] points readers at: how many part keys to
+/// fetch, how long the reassembled value should be, and a checksum to catch a part having gone
+/// missing or having been overwritten by a differently-sized value before all parts could be
+/// read.
+struct ChunkIndex {
+    num_chunks: usize,
+    total_len: usize,
+    checksum: u64,
+}
+
+impl ChunkIndex {
+    const ENCODED_LEN: usize = 1 + 8 + 8 + 8;
+
+    fn encode(&self) -> Bytes {
+        let mut out = Vec::with_capacity(Self::ENCODED_LEN);
+        out.push(MEMCACHE_CHUNK_INDEX_FLAG);
+        out.extend_from_slice(&(self.num_chunks as u64).to_be_bytes());
+        out.extend_from_slice(&(self.total_len as u64).to_be_bytes());
+        out.extend_from_slice(&self.checksum.to_be_bytes());
+        Bytes::from(out)
+    }
+
+    /// `bytes` must not include the leading [`MEMCACHE_CHUNK_INDEX_FLAG`] byte.
+    fn decode(bytes: &[u8]) -> McResult<Self> {
+        if bytes.len() != Self::ENCODED_LEN - 1 {
+            return Err(McErrorKind::Deserialization(format!(
+                "chunk index was {} bytes, expected {}",
+                bytes.len(),
+                Self::ENCODED_LEN - 1
+            )));
+        }
+        let num_chunks = u64::from_be_bytes(bytes[0..8].try_into().unwrap()) as usize;
+        let total_len = u64::from_be_bytes(bytes[8..16].try_into().unwrap()) as usize;
+        let checksum = u64::from_be_bytes(bytes[16..24].try_into().unwrap());
+        Ok(Self {
+            num_chunks,
+            total_len,
+            checksum,
+        })
+    }
+}
+
+fn checksum_for_chunking(bytes: &[u8]) -> u64 {
+    let mut hasher = XxHash::with_seed(0);
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+/// The Memcache key a chunked value's `i`th part is stored under.
+fn chunk_part_key(memcache_key: &str, i: usize) -> String {
+    format!("{}.chunk.{}", memcache_key, i)
+}
+
+/// How long [`update_cache`] holds its per-key Memcache lock for while it compares the current
+/// value against the caller's `expected` and, if they match, writes the new one. That's a couple
+/// of Memcache round trips, not a backing-store fetch, so unlike [`MemcacheLease::lease_ttl`] this
+/// doesn't need to be configurable per store - it just needs to comfortably outlast them.
+const UPDATE_CACHE_LOCK_TTL: Duration = Duration::from_secs(5);
+
+/// Time source consulted everywhere this module would otherwise call `SystemTime::now()` - the
+/// Memcache refresh-ahead timestamp, and the poisoned-key and Memcache circuit breaker cooldowns.
+/// A store opts into a controllable clock by overriding [`EntityStore::clock`]; the default,
+/// [`SystemClock`], is the real wall clock.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+/// The real wall clock. [`EntityStore::clock`]'s default.
+#[derive(Copy, Clone, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A [`Clock`] whose time is set explicitly rather than tracking the real wall clock, so tests can
+/// advance past a TTL or cooldown deterministically instead of sleeping for real - e.g. to observe
+/// a poisoned Memcache key's [`POISONED_KEY_TTL`] or the circuit breaker's
+/// [`CIRCUIT_BREAKER_COOLDOWN`] actually expire.
+pub struct MockClock(Mutex<SystemTime>);
+
+impl MockClock {
+    pub fn new(now: SystemTime) -> Self {
+        Self(Mutex::new(now))
+    }
+
+    pub fn set(&self, now: SystemTime) {
+        *self.0.lock().expect("lock poisoned") = now;
+    }
+
+    pub fn advance(&self, by: Duration) {
+        let mut now = self.0.lock().expect("lock poisoned");
+        *now += by;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> SystemTime {
+        *self.0.lock().expect("lock poisoned")
+    }
+}
+
+/// Apply [`EntityStore::memcache_ttl_jitter_pct`] to `ttl`, scaling a [`CacheTtl::Ttl`]'s
+/// duration by a random factor in `[1 - jitter_pct, 1 + jitter_pct]`. `jitter_pct` <= 0 (the
+/// default) is a no-op, as is `CacheTtl::NoTtl`.
+fn jittered_ttl(ttl: CacheTtl, jitter_pct: f64) -> CacheTtl {
+    let ttl = match ttl {
+        CacheTtl::Ttl(ttl) => ttl,
+        CacheTtl::NoTtl => return CacheTtl::NoTtl,
+    };
+    if jitter_pct <= 0.0 {
+        return CacheTtl::Ttl(ttl);
+    }
+    let factor = rand::thread_rng().gen_range(1.0 - jitter_pct..=1.0 + jitter_pct);
+    let jittered = ttl.mul_f64(factor.max(0.0));
+    CacheTtl::Ttl(jittered)
+}
+
+/// Prepend `now` (seconds since the Unix epoch, big-endian) to `bytes`, so a later reader can
+/// recover how long ago this value was written via [`split_refresh_ahead_timestamp`]. Used by
+/// [`EntityStore::memcache_refresh_ahead`]; `now` comes from [`EntityStore::clock`].
+fn with_refresh_ahead_timestamp(bytes: Bytes, now: SystemTime) -> Bytes {
+    let now = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let mut out = Vec::with_capacity(8 + bytes.len());
+    out.extend_from_slice(&now.to_be_bytes());
+    out.extend_from_slice(&bytes);
+    Bytes::from(out)
+}
+
+/// Inverse of [`with_refresh_ahead_timestamp`]: strips the leading 8-byte write time off `bytes`
+/// and returns how long ago it was written (relative to `now`, from [`EntityStore::clock`]),
+/// alongside the remaining bytes. Treats `bytes` too short to hold a timestamp as written just
+/// now - that just means it predates `memcache_refresh_ahead` being turned on for this store.
+fn split_refresh_ahead_timestamp(bytes: Bytes, now: SystemTime) -> (Duration, Bytes) {
+    if bytes.len() < 8 {
+        return (Duration::ZERO, bytes);
+    }
+    let mut written_at = [0u8; 8];
+    written_at.copy_from_slice(&bytes[..8]);
+    let written_at = u64::from_be_bytes(written_at);
+    let now = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    (Duration::from_secs(now.saturating_sub(written_at)), bytes.slice(8..))
+}
+
+/// Split `bytes` (already flag-byte-encoded by [`compress_for_memcache`]) into
+/// [`MEMCACHE_CHUNK_SIZE`]-sized parts, and write those parts plus a [`ChunkIndex`] at
+/// `memcache_key` itself. Best-effort, like every other Memcache write in this module: a failed
+/// part write just means that part (and so the whole entry) won't be reassembled on the read
+/// side, which is treated as a miss rather than an error.
+async fn write_chunked(
+    memcache: MemcacheHandler,
+    memcache_key: MemcacheKey,
+    bytes: Bytes,
+    ttl: CacheTtl,
+) {
+    let num_chunks = (bytes.len() + MEMCACHE_CHUNK_SIZE - 1) / MEMCACHE_CHUNK_SIZE;
+    let index = ChunkIndex {
+        num_chunks,
+        total_len: bytes.len(),
+        checksum: checksum_for_chunking(bytes.as_ref()),
+    }
+    .encode();
+
+    let mut part_entries: Vec<(String, Bytes)> = (0..num_chunks)
+        .map(|i| {
+            let start = i * MEMCACHE_CHUNK_SIZE;
+            let end = std::cmp::min(start + MEMCACHE_CHUNK_SIZE, bytes.len());
+            (chunk_part_key(&memcache_key.0, i), bytes.slice(start..end))
+        })
+        .collect();
+    part_entries.push((memcache_key.0, index));
+
+    let writes = part_entries.into_iter().map(|(key, value)| {
+        cloned!(memcache);
+        async move {
+            let _ = match ttl {
+                CacheTtl::NoTtl => memcache.set(key, value).await,
+                CacheTtl::Ttl(ttl) => memcache.set_with_ttl(key, value, ttl).await,
+            };
+        }
+    });
+
+    futures::future::join_all(writes).await;
+}
+
+/// If `bytes` (as read from `memcache_key` itself) is a [`ChunkIndex`], fetch and reassemble its
+/// parts. Otherwise, `bytes` already is the whole value, and is returned unchanged.
+///
+/// A missing, truncated, or checksum-mismatched part is reported as [`McErrorKind::Missing`]
+/// rather than [`McErrorKind::Deserialization`]: it's the expected outcome of a part having
+/// expired independently of its index (Memcache evicts keys individually) or of a concurrent
+/// write replacing some but not yet all of an entry's parts, not a sign of corrupt data.
+async fn resolve_chunked_memcache_value(
+    memcache: &MemcacheHandler,
+    memcache_key: &str,
+    bytes: Bytes,
+) -> McResult<Bytes> {
+    if bytes.first() != Some(&MEMCACHE_CHUNK_INDEX_FLAG) {
+        return Ok(bytes);
+    }
+    let index = ChunkIndex::decode(bytes.slice(1..).as_ref())?;
+
+    let part_futs = (0..index.num_chunks).map(|i| {
+        let part_key = chunk_part_key(memcache_key, i);
+        async move { memcache.get(part_key).await }
+    });
+
+    let mut reassembled = Vec::with_capacity(index.total_len);
+    for part in futures::future::join_all(part_futs).await {
+        match part {
+            Ok(Some(part)) => reassembled.extend_from_slice(&part),
+            _ => return Err(McErrorKind::Missing),
+        }
+    }
+
+    if reassembled.len() != index.total_len || checksum_for_chunking(&reassembled) != index.checksum {
+        return Err(McErrorKind::Missing);
+    }
+
+    Ok(Bytes::from(reassembled))
+}
+
+/// Memcache rejects keys longer than this many bytes. Stores that build their cache key from
+/// long paths or multi-part identifiers can exceed it, so a key longer than this is truncated and
+/// hashed down to this length by [`memcache_key_for`] instead of being sent to Memcache as-is and
+/// failing the set.
+const MEMCACHE_KEY_MAX_LEN: usize = 250;
+
+/// How many hashed-key -> original-key mappings to remember for [`debug_unhash_memcache_key`].
+/// This is just a debugging aid, not a correctness requirement, so it's fine for every recorded
+/// mapping to be dropped at once when this many have been recorded.
+const HASHED_MEMCACHE_KEYS_CAPACITY: usize = 10_000;
+
+/// Recently hashed-down Memcache keys, so the admin tooling can recover the original, readable
+/// key a given hash came from (e.g. when investigating a specific key seen in a Memcache dump).
+/// This is process-local and best-effort: it only remembers keys hashed by this process since it
+/// started, and at most [`HASHED_MEMCACHE_KEYS_CAPACITY`] of them: once that many are recorded,
+/// all of them are forgotten and recording starts over.
+static HASHED_MEMCACHE_KEYS: Lazy<Mutex<HashMap<String, String>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn record_hashed_memcache_key(hashed: String, original: String) {
+    let mut keys = HASHED_MEMCACHE_KEYS
+        .lock()
+        .expect("HASHED_MEMCACHE_KEYS lock poisoned");
+    if keys.len() >= HASHED_MEMCACHE_KEYS_CAPACITY {
+        keys.clear();
+    }
+    keys.insert(hashed, original);
+}
+
+/// Recover the original, unhashed Memcache key that [`memcache_key_for`] hashed down to `hashed`,
+/// if this process still remembers it. Intended for admin tooling that needs to turn an opaque
+/// hashed key back into the human-readable key it was derived from.
+pub fn debug_unhash_memcache_key(hashed: &str) -> Option<String> {
+    HASHED_MEMCACHE_KEYS
+        .lock()
+        .expect("HASHED_MEMCACHE_KEYS lock poisoned")
+        .get(hashed)
+        .cloned()
+}
+
+/// Consecutive deserialization failures a Memcache key must rack up before it's treated as
+/// poisoned. A single failure could just be a racing write landing mid-read, so this waits for a
+/// second one before assuming the stored value itself is bad.
+const POISONED_KEY_FAILURE_THRESHOLD: u32 = 2;
+
+/// How long a poisoned key is treated as DB-only once it crosses
+/// [`POISONED_KEY_FAILURE_THRESHOLD`], so a key whose value gets rewritten with something
+/// deserializable doesn't stay DB-only forever.
+const POISONED_KEY_TTL: Duration = Duration::from_secs(60);
+
+/// How many distinct Memcache keys to track consecutive-failure counts and poisoned status for.
+/// Best-effort, like [`HASHED_MEMCACHE_KEYS_CAPACITY`]: once full, every tracked entry (poisoned
+/// status included) is dropped at once, rather than this growing unbounded under a wide key space.
+const POISONED_MEMCACHE_KEYS_CAPACITY: usize = 10_000;
+
+/// A Memcache key that has recently failed to deserialize at least once. Once
+/// `consecutive_failures` reaches [`POISONED_KEY_FAILURE_THRESHOLD`], `poisoned_until` is set and
+/// the key is treated as DB-only (and a best-effort delete is issued) until that deadline passes.
+#[derive(Clone, Copy)]
+struct PoisonedKeyState {
+    consecutive_failures: u32,
+    poisoned_until: Option<SystemTime>,
+}
+
+/// Process-local tracking of Memcache keys whose stored value has repeatedly failed to
+/// deserialize. See [`PoisonedKeyState`].
+static POISONED_MEMCACHE_KEYS: Lazy<Mutex<HashMap<String, PoisonedKeyState>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// True if `key` is currently poisoned, i.e. should be treated as DB-only rather than fetched
+/// from Memcache. Lazily clears the entry once `poisoned_until` has passed. `now` comes from
+/// [`EntityStore::clock`].
+fn is_poisoned_memcache_key(key: &str, now: SystemTime) -> bool {
+    let mut keys = POISONED_MEMCACHE_KEYS
+        .lock()
+        .expect("POISONED_MEMCACHE_KEYS lock poisoned");
+    match keys.get(key).and_then(|state| state.poisoned_until) {
+        Some(until) if until > now => true,
+        Some(_) => {
+            keys.remove(key);
+            false
+        }
+        None => false,
+    }
+}
+
+/// Record a deserialization failure for `key`. Returns `true` the first time this call pushes
+/// the key over [`POISONED_KEY_FAILURE_THRESHOLD`], so the caller knows to also issue a delete.
+/// `now` comes from [`EntityStore::clock`].
+fn record_memcache_deserialization_failure(key: &str, now: SystemTime) -> bool {
+    let mut keys = POISONED_MEMCACHE_KEYS
+        .lock()
+        .expect("POISONED_MEMCACHE_KEYS lock poisoned");
+    if keys.len() >= POISONED_MEMCACHE_KEYS_CAPACITY && !keys.contains_key(key) {
+        keys.clear();
+    }
+
+    let state = keys.entry(key.to_owned()).or_insert(PoisonedKeyState {
+        consecutive_failures: 0,
+        poisoned_until: None,
+    });
+    state.consecutive_failures += 1;
+
+    if state.poisoned_until.is_none() && state.consecutive_failures >= POISONED_KEY_FAILURE_THRESHOLD
+    {
+        state.poisoned_until = Some(now + POISONED_KEY_TTL);
+        true
+    } else {
+        false
+    }
+}
+
+/// Clear any tracked failure count for `key`, since it just deserialized successfully.
+fn record_memcache_deserialization_success(key: &str) {
+    POISONED_MEMCACHE_KEYS
+        .lock()
+        .expect("POISONED_MEMCACHE_KEYS lock poisoned")
+        .remove(key);
+}
+
+/// Consecutive `McErrorKind::MemcacheInternal` failures a store must rack up before its
+/// Memcache access is short-circuited. Unlike [`POISONED_KEY_FAILURE_THRESHOLD`] (which tracks
+/// one bad key), this tracks the Memcache tier itself being unreachable or erroring for a whole
+/// store, so it tolerates a few more isolated blips before tripping.
+const CIRCUIT_BREAKER_FAILURE_THRESHOLD: u32 = 5;
+
+/// How long a store's Memcache reads and writes are short-circuited for once
+/// [`CIRCUIT_BREAKER_FAILURE_THRESHOLD`] is crossed, before the next read is let through again
+/// to probe whether Memcache has recovered.
+const CIRCUIT_BREAKER_COOLDOWN: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Copy, Default)]
+struct CircuitBreakerState {
+    consecutive_failures: u32,
+    open_until: Option<SystemTime>,
+}
+
+/// Process-local circuit breaker state, one entry per store. Stores are identified by the
+/// address of their `CacheStats` - one per store type for a store using `impl_singleton_stats!`,
+/// or one per instance for a store that holds its own (e.g. bonsai_git_mapping's per-repo
+/// stats) - so a degraded Memcache tier for one store doesn't also
+/// short-circuit access for every other store sharing the process.
+static MEMCACHE_CIRCUIT_BREAKERS: Lazy<Mutex<HashMap<usize, CircuitBreakerState>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn circuit_breaker_key(stats: &CacheStats) -> usize {
+    stats as *const CacheStats as usize
+}
+
+/// True if `stats`'s store is currently short-circuiting Memcache access, i.e. reads and writes
+/// should be skipped in favor of cachelib/DB. Lazily clears the breaker once `open_until` has
+/// passed, so the next call is let through to probe for recovery. `now` comes from
+/// [`EntityStore::clock`].
+fn memcache_circuit_breaker_is_open(stats: &CacheStats, now: SystemTime) -> bool {
+    let mut breakers = MEMCACHE_CIRCUIT_BREAKERS
+        .lock()
+        .expect("MEMCACHE_CIRCUIT_BREAKERS lock poisoned");
+    let key = circuit_breaker_key(stats);
+    match breakers.get(&key).and_then(|state| state.open_until) {
+        Some(until) if until > now => true,
+        Some(_) => {
+            breakers.remove(&key);
+            false
+        }
+        None => false,
+    }
+}
+
+/// Record a Memcache-internal failure for `stats`'s store, opening the circuit breaker once
+/// [`CIRCUIT_BREAKER_FAILURE_THRESHOLD`] consecutive failures have been seen. `now` comes from
+/// [`EntityStore::clock`].
+fn record_memcache_circuit_breaker_failure(stats: &CacheStats, now: SystemTime) {
+    let mut breakers = MEMCACHE_CIRCUIT_BREAKERS
+        .lock()
+        .expect("MEMCACHE_CIRCUIT_BREAKERS lock poisoned");
+    let state = breakers.entry(circuit_breaker_key(stats)).or_default();
+    state.consecutive_failures += 1;
+    if state.open_until.is_none()
+        && state.consecutive_failures >= CIRCUIT_BREAKER_FAILURE_THRESHOLD
+    {
+        state.open_until = Some(now + CIRCUIT_BREAKER_COOLDOWN);
+    }
+}
+
+/// Clear any tracked failure count for `stats`'s store, since it just had a successful Memcache
+/// access.
+fn record_memcache_circuit_breaker_success(stats: &CacheStats) {
+    MEMCACHE_CIRCUIT_BREAKERS
+        .lock()
+        .expect("MEMCACHE_CIRCUIT_BREAKERS lock poisoned")
+        .remove(&circuit_breaker_key(stats));
+}
+
+/// Build the cachelib key for `key`, folding in [`EntityStore::cache_version`] if the store has
+/// bumped it above its default of `0`. Since [`memcache_key_for`] derives the Memcache key from
+/// this cachelib key, a non-zero version is folded into both keys from this one call.
+fn cachelib_key_for<K, V>(store: &impl KeyedEntityStore<K, V>, key: &K) -> CachelibKey {
+    let key = store.get_cache_key(key);
+    match store.cache_version() {
+        0 => CachelibKey(key),
+        version => CachelibKey(format!("{}.v{}", key, version)),
+    }
+}
+
+/// Builds the full Memcache key for `cachelib_key` via `store.keygen()`, hashing it down to
+/// [`MEMCACHE_KEY_MAX_LEN`] if it would otherwise be too long and get rejected by Memcache. The
+/// hashed form keeps as much of the original key's leading, readable prefix as fits alongside the
+/// hash suffix, so someone scanning a Memcache dump can still tell which store and rough key a
+/// truncated entry came from without needing [`debug_unhash_memcache_key`] (which only remembers
+/// keys hashed by this process since it started). The unhashed key is still recorded there too,
+/// for exact recovery.
+fn memcache_key_for<V>(store: &impl EntityStore<V>, cachelib_key: &CachelibKey) -> MemcacheKey {
+    let key = store.keygen().key(&cachelib_key.0);
+    if key.len() <= MEMCACHE_KEY_MAX_LEN {
+        return MemcacheKey(key);
+    }
+    let mut hasher = XxHash::with_seed(0);
+    hasher.write(key.as_bytes());
+    let suffix = format!(".hashed:{:016x}", hasher.finish());
+
+    let mut prefix_len = MEMCACHE_KEY_MAX_LEN.saturating_sub(suffix.len());
+    while prefix_len > 0 && !key.is_char_boundary(prefix_len) {
+        prefix_len -= 1;
+    }
+
+    let hashed = format!("{}{}", &key[..prefix_len], suffix);
+    record_hashed_memcache_key(hashed.clone(), key);
+    MemcacheKey(hashed)
+}
+
+/// Per-layer result of [`admission`]: whether (and with what TTL) `v` should be written to each
+/// cache layer.
+struct Admission {
+    cachelib: Option<CacheTtl>,
+    memcache: Option<CacheTtl>,
+}
+
+/// Applies both `cache_determinator` and pressure-aware admission to decide whether, and to
+/// which layer(s), `v` should be written.
+fn admission<V: MemcacheEntity>(store: &impl EntityStore<V>, key: &str, v: &V) -> Admission {
+    let disposition = store
+        .cache_determinator(key, v, &|| v.serialize().len())
+        .unwrap_or_else(|_| {
+            store.stats().cache_determinator_err.add_value(1);
+            record_prometheus_stat("mononoke_cache_cache_determinator_err_total", 1);
+            CacheDisposition::Ignore
+        });
+
+    let (cachelib, memcache) = match disposition {
+        CacheDisposition::Cache(ttl) => (Some(ttl), Some(ttl)),
+        CacheDisposition::CacheWithTtls { cachelib, memcache } => (Some(cachelib), Some(memcache)),
+        CacheDisposition::CachelibOnly(ttl) => (Some(ttl), None),
+        CacheDisposition::MemcacheOnly(ttl) => (None, Some(ttl)),
+        CacheDisposition::Ignore => (None, None),
+    };
+
+    let under_pressure = store.cache_value_size(v) > PRESSURE_ADMISSION_SIZE_THRESHOLD
+        && store.cachelib().is_under_pressure();
+
+    Admission {
+        cachelib: if under_pressure { None } else { cachelib },
+        memcache,
+    }
+}
+
+/// This crate's cache hit/miss/error counters, aggregated across all cache stores in the
+/// process, rendered in the Prometheus text exposition format. fbcode builds get these same
+/// counters (broken down per-store) through `stats::prelude`'s ODS integration instead; this is
+/// only populated outside fbcode, where that integration compiles to no-ops. Per-store
+/// breakdown isn't available here, since a store's `CacheStats` label isn't introspectable from
+/// outside the `stats` crate.
+#[cfg(not(fbcode_build))]
+static PROMETHEUS_STATS: Lazy<stats_exporter::PrometheusStatsRegistry> =
+    Lazy::new(stats_exporter::PrometheusStatsRegistry::new);
+
+/// Render this crate's cache stats for a non-fbcode metrics endpoint to serve. See
+/// [`PROMETHEUS_STATS`].
+#[cfg(not(fbcode_build))]
+pub fn render_prometheus_stats() -> String {
+    PROMETHEUS_STATS.render()
+}
+
+#[cfg(fbcode_build)]
+fn record_prometheus_stat(_name: &'static str, _delta: i64) {}
+
+#[cfg(not(fbcode_build))]
+fn record_prometheus_stat(name: &'static str, delta: i64) {
+    PROMETHEUS_STATS.counter(name).add(delta);
+}
+
 pub type McResult<T> = Result<T, McErrorKind>;
 
 struct CachelibKey(String);
@@ -94,15 +733,48 @@ pub enum CacheTtl {
     Ttl(Duration),
 }
 
-/// Whether or not to cache an item
+/// Whether or not to cache an item, and in which layer(s)
 #[derive(Copy, Clone)]
 pub enum CacheDisposition {
-    /// Cache this item with the given TTL
+    /// Cache this item in both cachelib and Memcache with the given TTL
     Cache(CacheTtl),
+    /// Cache this item in both cachelib and Memcache, like [`Self::Cache`], but with an
+    /// independent TTL for each layer. Useful for data whose local staleness tolerance is much
+    /// lower than its remote one - e.g. phases, which wants a short cachelib TTL so a host
+    /// notices a local draft-to-public transition quickly, but can afford a much longer Memcache
+    /// TTL since every host sees the same write.
+    CacheWithTtls {
+        cachelib: CacheTtl,
+        memcache: CacheTtl,
+    },
+    /// Cache this item in cachelib only. Useful for items that are too large, or too cheap to
+    /// recompute locally, to be worth replicating to every host's Memcache.
+    CachelibOnly(CacheTtl),
+    /// Cache this item in Memcache only. Useful for items that must stay consistent across
+    /// hosts, or that churn too often to be worth holding in a single host's local cache.
+    MemcacheOnly(CacheTtl),
     /// Do not cache this item; re-fetch from backing store if it's requested again
     Ignore,
 }
 
+/// Controls whether [`get_or_fill_chunked_with_origin`] and friends touch the cache at all for a
+/// given store, on top of (not instead of) [`EntityStore::fill_only`] and `cache_determinator`.
+/// See [`EntityStore::cache_mode`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Default)]
+pub enum CacheMode {
+    /// Read from cachelib/Memcache as usual, and fill them after a backing store fetch as usual.
+    #[default]
+    Normal,
+    /// Skip cachelib/Memcache reads, and don't fill them after a backing store fetch either - a
+    /// pure passthrough to `get_from_db`. There's no dedicated "write-only" variant here since
+    /// [`EntityStore::fill_only`] already covers skip-reads-but-still-fill.
+    Bypass,
+    /// Read from cachelib/Memcache as usual, but never write back after a backing store fetch.
+    /// Useful for a backfill/migration job that shouldn't leave its own reads' results behind in
+    /// the cache for everyone else.
+    ReadOnly,
+}
+
 /// Implement this for a data item that can be cached. You will also need
 /// #[derive(Abomonation)] on the data item.
 pub trait MemcacheEntity: Sized {
@@ -114,6 +786,34 @@ pub trait MemcacheEntity: Sized {
     fn deserialize(bytes: Bytes) -> McResult<Self>;
 }
 
+/// Implement this instead of [`MemcacheEntity`] directly when the real implementation would just
+/// be a Thrift round-trip: `compact_protocol::serialize`/`deserialize` plus a `From`/`TryInto`
+/// conversion to and from a generated Thrift struct. That pattern is the same boilerplate in every
+/// caching module that backs onto a Thrift-encoded row (mapping a `from_thrift`/`into_thrift`
+/// error to [`McErrorKind::Deserialization`]), so the blanket impl below does it once.
+///
+/// `caching_ext` doesn't depend on `fbthrift` itself, so the actual `compact_protocol` calls still
+/// live in the implementing crate - this only factors out the "turn a conversion failure into a
+/// `MemcacheEntity` deserialization failure" wiring.
+pub trait ThriftMemcacheEntity: Sized {
+    /// Encode `self` to bytes, typically `compact_protocol::serialize(&self.clone().into_thrift())`.
+    fn into_bytes(&self) -> Bytes;
+
+    /// Decode bytes back into `Self`, typically `compact_protocol::deserialize` followed by a
+    /// `from_thrift` conversion. Any error is treated as a deserialization failure.
+    fn from_bytes(bytes: Bytes) -> Result<Self, Error>;
+}
+
+impl<T: ThriftMemcacheEntity> MemcacheEntity for T {
+    fn serialize(&self) -> Bytes {
+        self.into_bytes()
+    }
+
+    fn deserialize(bytes: Bytes) -> McResult<Self> {
+        Self::from_bytes(bytes).map_err(|e| McErrorKind::Deserialization(e.to_string()))
+    }
+}
+
 /// Implement this trait to indicate that you can cache values retrived through you
 pub trait EntityStore<V> {
     /// Get the cachelib handler. This can be created with `.into()` on a `VolatileLruCachePool`
@@ -127,7 +827,215 @@ pub trait EntityStore<V> {
     fn memcache(&self) -> &MemcacheHandler;
 
     /// Given a value `v`, decide whether or not to cache it.
-    fn cache_determinator(&self, v: &V) -> CacheDisposition;
+    ///
+    /// `key` is the cachelib key the value would be stored under. It's a `&str` rather than the
+    /// store's generic key type `K`, because only the cachelib-key string is uniformly available
+    /// at every call site that needs a disposition (some fill paths have already reduced the
+    /// typed key down to its cache-key string by the time they get here). It lets a store base
+    /// its TTL on properties it can recover from the key itself (e.g. an embedded timestamp or
+    /// generation number), without being able to inspect the original typed key.
+    ///
+    /// Fallible so a store can base the decision on something that can itself fail to read (e.g.
+    /// a config knob gated per-repo), without having to silently paper over that failure as some
+    /// fixed disposition itself. An `Err` is treated the same as `Ok(CacheDisposition::Ignore)` by
+    /// every caller here - refusing to cache a value is always safe, just slower - and is counted
+    /// via `CacheStats::cache_determinator_err`. Still synchronous, like the rest of
+    /// `EntityStore`: this crate doesn't want every admission decision to require an `.await`, so
+    /// a store whose decision genuinely depends on async state should resolve that state ahead of
+    /// time (e.g. cache it on the store itself) rather than block here.
+    ///
+    /// `serialized_size` lazily computes `v`'s [`MemcacheEntity::serialize`]d length - the same
+    /// length `fill_multiple_memcache` later checks against `MEMCACHE_VALUE_MAX_SIZE` before
+    /// silently dropping an oversized value rather than writing it. A store that wants to make
+    /// that call itself (e.g. returning `CacheDisposition::CachelibOnly` once a value is too big
+    /// for Memcache, instead of finding out from a drop deep in the fill path) can call it here;
+    /// it's a callback rather than a plain `usize` so that serializing `v` isn't wasted work for
+    /// the common case of a store that never looks at size. Nothing memoizes the result, so a
+    /// store that needs it more than once should hold onto it rather than calling this again.
+    fn cache_determinator(
+        &self,
+        key: &str,
+        v: &V,
+        serialized_size: &dyn Fn() -> usize,
+    ) -> Result<CacheDisposition, Error>;
+
+    /// Approximate size of `v` in bytes, used for pressure-aware admission: an entry larger than
+    /// [`PRESSURE_ADMISSION_SIZE_THRESHOLD`] is skipped rather than admitted to cachelib while
+    /// the pool is under memory pressure, even if `cache_determinator` would otherwise cache it.
+    ///
+    /// Defaults to 0, i.e. never skipped under pressure. Stores whose entries are small and
+    /// uniform (e.g. phases) can rely on the default; stores caching larger, more variably-sized
+    /// blobs should override this so a burst of large fills can't push hot small entries out of
+    /// the pool.
+    fn cache_value_size(&self, _v: &V) -> usize {
+        0
+    }
+
+    /// How many individual Memcache gets to have in flight at once while filling a miss from
+    /// Memcache. Memcache's client in this tree has no multi-key get, so this is the only lever
+    /// available for cutting down how long a large miss (e.g. 1000+ keys) takes: a store whose
+    /// keys are cheap to fetch and numerous can raise it past the default to get more of them in
+    /// flight at once.
+    fn memcache_concurrency(&self) -> usize {
+        MEMCACHE_CONCURRENCY
+    }
+
+    /// Opt into zstd-compressing Memcache values once they're larger than
+    /// [`MEMCACHE_COMPRESSION_THRESHOLD`], prefixed with a flag byte recording whether
+    /// compression was actually applied. Lets a store whose entries sit just above
+    /// `MEMCACHE_VALUE_MAX_SIZE` still get cached instead of being silently skipped by
+    /// `fill_multiple_memcache`.
+    ///
+    /// Defaults to `false`, i.e. values are stored exactly as `MemcacheEntity::serialize`
+    /// produces them, preserving the existing wire format for stores that don't opt in. Changing
+    /// this for a store that already has entries in Memcache needs a Memcache sitever bump (see
+    /// `KeyGen`), since an old binary would otherwise try to deserialize a flag byte it doesn't
+    /// know to strip, or a new binary would try to strip a flag byte that was never written.
+    fn memcache_compression(&self) -> bool {
+        false
+    }
+
+    /// Opt into splitting a Memcache value across multiple keys (see [`write_chunked`]) once it's
+    /// still at or above `MEMCACHE_VALUE_MAX_SIZE` after any [`EntityStore::memcache_compression`],
+    /// instead of `fill_multiple_memcache` silently skipping it. Lets entries that are genuinely
+    /// larger than a single Memcache value (e.g. a large manifest) still be served from Memcache
+    /// rather than always falling back to the backing store.
+    ///
+    /// Defaults to `false`, preserving the existing wire format and behaviour (oversized entries
+    /// are dropped) for stores that don't opt in. Like `memcache_compression`, turning this on for
+    /// a store with existing entries needs a Memcache sitever bump, since it changes what the
+    /// first byte of a value means.
+    fn memcache_chunking(&self) -> bool {
+        false
+    }
+
+    /// Randomly vary each Memcache TTL this store writes by up to this fraction, e.g. `0.1` for
+    /// ±10%. Spreads out the expiry of entries written in a burst (a backfill, a cache warmup
+    /// after a deploy) so they don't all expire in the same second and send a synchronized wave
+    /// of requests to the backing store.
+    ///
+    /// Defaults to `0.0`, i.e. no jitter, TTLs are written exactly as given. Only affects
+    /// [`CacheTtl::Ttl`]; `CacheTtl::NoTtl` entries have nothing to jitter.
+    fn memcache_ttl_jitter_pct(&self) -> f64 {
+        0.0
+    }
+
+    /// Opt into reporting Memcache hits older than this as stale via
+    /// [`get_or_fill_chunked_with_origin`]'s `OriginFillResult::stale`, so a caller can serve the
+    /// (still valid) cached value with no added read latency while kicking off its own background
+    /// `get_from_db` to refill the cache ahead of the entry's actual expiry.
+    ///
+    /// This only covers Memcache hits, not cachelib ones: cachelib stores `V` via `Abomonation`,
+    /// which serializes the type's exact in-memory layout rather than a format this crate
+    /// controls, so there's nowhere to tag a write time without wrapping every store's cached
+    /// type - a wire-format migration of its own, not something to fold into this flag. Memcache
+    /// values are already passed through this crate as opaque bytes we control, the same way
+    /// `memcache_compression`'s flag byte is.
+    ///
+    /// caching_ext only detects staleness; it doesn't enqueue the refetch itself, since it holds
+    /// only a borrowed `&impl KeyedEntityStore` here and has no `'static`, owned handle on the
+    /// store it could safely move into a spawned task.
+    ///
+    /// Defaults to `None`, i.e. no staleness tracking. Not currently supported together with
+    /// `memcache_chunking`: a chunked value's freshness isn't tracked, so a store with both set
+    /// never reports its chunked entries as stale. Like `memcache_compression`, turning this on
+    /// for a store with existing entries needs a Memcache sitever bump, since it changes what the
+    /// first bytes of a value mean.
+    fn memcache_refresh_ahead(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Time source for Memcache refresh-ahead tagging and the poisoned-key/circuit-breaker
+    /// cooldowns. Defaults to [`SystemClock`], the real wall clock; a test can override this with
+    /// a [`MockClock`] to advance past a TTL deterministically instead of sleeping.
+    fn clock(&self) -> &dyn Clock {
+        &SystemClock
+    }
+
+    /// Cap on how long a single key's Memcache read is allowed to take before it's treated as a
+    /// miss and the fetch falls through to `get_from_db` instead. Without this, one slow or
+    /// unreachable Memcache host adds its full latency to every cold read behind it, even though
+    /// the backing store would likely have answered sooner.
+    ///
+    /// Defaults to `None`, i.e. no timeout - a Memcache read is awaited for as long as the
+    /// underlying client takes. A store fronting a fast backing store, where a slow Memcache read
+    /// is rarely worth waiting out, should set this; one where Memcache is reliably much faster
+    /// than the backing store shouldn't bother.
+    fn memcache_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Folded into every cachelib and Memcache key this store generates (see `cachelib_key_for`).
+    /// Bumping it at runtime (e.g. via a tunable) instantly invalidates every entry this store
+    /// has cached under the old version, since they're no longer reachable under any key a
+    /// lookup would generate - without a redeploy to ship a new `KeyGen` codever/sitever
+    /// constant, which is otherwise the only way to do a mass invalidation like this.
+    ///
+    /// Defaults to `0`, which doesn't change the generated key at all, preserving existing cache
+    /// entries for stores that don't opt in.
+    fn cache_version(&self) -> u32 {
+        0
+    }
+
+    /// Opt into "FillOnly" mode: every read goes straight to the backing store, skipping
+    /// cachelib and Memcache entirely, while still writing back through the normal
+    /// `cache_determinator`-driven admission path once the value comes back. Meant for
+    /// backfill/migration jobs that must see source-of-truth data - a cache hit could be serving
+    /// something the job is specifically there to fix or supersede - but that still want to warm
+    /// the cache for the readers that come after them, instead of leaving it cold or stale.
+    ///
+    /// This isn't a [`CacheDisposition`] variant, even though it's conceptually the read-side
+    /// counterpart to one: `cache_determinator` only runs on a value already fetched from either
+    /// cache or the backing store, so it has no way to influence whether that initial read checks
+    /// the cache at all.
+    ///
+    /// Defaults to `false`, i.e. reads check cachelib and Memcache as usual.
+    fn fill_only(&self) -> bool {
+        false
+    }
+
+    /// Opt into bypassing or read-only-ing the cache for this store - see [`CacheMode`]. Meant
+    /// for admin tools and backfillers that need to run against the cache differently than
+    /// ordinary traffic, e.g. bypassing it entirely to avoid polluting it with one-off admin
+    /// reads, without having to fake it out by swapping in a different cachelib/Memcache handler.
+    ///
+    /// This takes the same shape as `fill_only` rather than threading a `CoreContext`/session
+    /// through `get_or_fill`: this crate has no dependency on (and shouldn't gain one on) the
+    /// session/context types, since it sits below most of the rest of Mononoke. A store built on
+    /// top of a `CoreContext` should read whatever session state it cares about (e.g. a
+    /// `SessionClass`) and map it to a `CacheMode` here.
+    ///
+    /// Defaults to [`CacheMode::Normal`], i.e. no change to existing behavior.
+    fn cache_mode(&self) -> CacheMode {
+        CacheMode::Normal
+    }
+
+    /// Opt into deleting (rather than overwriting) this store's cachelib/Memcache entry for a key
+    /// in [`fill_cache`], whenever that key's owning store just performed a mutation (e.g. an
+    /// `add()`). Lets a store like `bonsai_hg_mapping` or `phases` keep its remote Memcache
+    /// coherent after a write without hand-rolling a `store.memcache().del(...)` call of its own
+    /// at every mutation site - `fill_cache` already knows the cachelib/Memcache keys for what it
+    /// was just handed, this just tells it to delete them instead of writing the new value.
+    ///
+    /// A straight delete, rather than writing the mutated value through as
+    /// `cache_determinator` would otherwise admit it, is deliberately the only option here: a
+    /// store whose backing write isn't guaranteed visible to every other host the instant this
+    /// call returns (e.g. an async-replicated DB) would otherwise risk another host's Memcache
+    /// caching a value that its own DB read can't back up yet. Deleting just means the next
+    /// reader anywhere pays for a cache miss instead.
+    ///
+    /// Defaults to `false`, i.e. `fill_cache` writes the value through as usual.
+    fn invalidate_on_write(&self) -> bool {
+        false
+    }
+
+    /// Called whenever a value read back from Memcache fails `MemcacheEntity::deserialize`, with
+    /// the message it failed with. `caching_ext` itself only has `CacheStats` counters available
+    /// (see `CacheStats::memcache_deserialize_err`) to record that this happened at all; a store
+    /// with access to richer logging (e.g. scuba, via its own `CoreContext`) can override this to
+    /// also record *why*, which is what tells corrupt data apart from schema skew. Defaults to a
+    /// no-op.
+    fn on_deserialize_error(&self, _key: &str, _message: &str) {}
 
     /// Finds the cache stats for this handler
     ///
@@ -135,24 +1043,228 @@ pub trait EntityStore<V> {
     fn stats(&self) -> &CacheStats;
 }
 
+/// Result broadcast to single-flight joiners: either the fetched map (shared via `Arc` since
+/// every joiner only needs to read its own key out of it) or the leader's error, re-wrapped in
+/// `Arc` since `Error` isn't `Clone`.
+type SingleFlightValue<K, V> = Result<Arc<HashMap<K, V>>, Arc<Error>>;
+type SingleFlightFuture<K, V> = Shared<oneshot::Receiver<SingleFlightValue<K, V>>>;
+
+/// Per-Memcache-key request coalescing for [`KeyedEntityStore::get_from_db`]. While one caller's
+/// fetch for a given key is outstanding, other callers asking for the same key join it instead of
+/// issuing a redundant fetch of their own, the same way cacheblob's `InProcessLease` coalesces
+/// concurrent work on a lease key.
+///
+/// A store opts in by holding one of these alongside its other cache handles (e.g. its
+/// `CachelibHandler`/`MemcacheHandler`/`KeyGen`) and returning it from
+/// [`KeyedEntityStore::single_flight`].
+pub struct SingleFlight<K, V> {
+    inflight: Mutex<HashMap<String, SingleFlightFuture<K, V>>>,
+}
+
+impl<K, V> SingleFlight<K, V> {
+    pub fn new() -> Self {
+        Self {
+            inflight: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K, V> Default for SingleFlight<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Cross-host coordination for [`KeyedEntityStore::get_from_db`], built on Memcache's
+/// lease-by-add semantics: acquiring the lease is an atomic add, which only one of several hosts
+/// racing to refill the same hot key can win. The losers poll Memcache for the winner's result
+/// for up to `max_wait` instead of immediately falling through to the backing store themselves,
+/// so a hot key expiring produces one store fetch across the fleet rather than one per host that
+/// happened to be serving a request at that moment.
+///
+/// Unlike [`SingleFlight`], which only coalesces calls within this process, this coordinates
+/// across every host sharing a Memcache instance. It never blocks indefinitely: if the lease
+/// holder doesn't finish within `max_wait` (e.g. it died, or is just slow), waiters fall through
+/// to fetching from the backing store themselves rather than risk reporting a false miss.
+///
+/// A store opts in by holding one of these and returning it from
+/// [`KeyedEntityStore::memcache_lease`].
+#[derive(Copy, Clone)]
+pub struct MemcacheLease {
+    lease_ttl: Duration,
+    max_wait: Duration,
+    poll_interval: Duration,
+}
+
+impl MemcacheLease {
+    pub fn new(lease_ttl: Duration, max_wait: Duration) -> Self {
+        Self {
+            lease_ttl,
+            max_wait,
+            poll_interval: Duration::from_millis(20),
+        }
+    }
+
+    fn lease_key(memcache_key: &str) -> String {
+        format!("{}.lease", memcache_key)
+    }
+
+    /// Try to win the lease for `memcache_key`. A caller that wins must call [`Self::release`]
+    /// once it's done fetching, so other hosts don't have to wait out the full `lease_ttl`; a
+    /// caller that loses must not, since that would delete the winner's lease.
+    async fn try_acquire(&self, memcache: &MemcacheHandler, memcache_key: &str) -> bool {
+        memcache
+            .add_with_ttl(Self::lease_key(memcache_key), Bytes::new(), self.lease_ttl)
+            .await
+            // An error talking to Memcache shouldn't block the fetch - treat it as a win, the
+            // same way every other best-effort Memcache operation in this module does.
+            .unwrap_or(true)
+    }
+
+    async fn release(&self, memcache: &MemcacheHandler, memcache_key: &str) {
+        let _ = memcache.del(Self::lease_key(memcache_key)).await;
+    }
+
+    /// Wait for the lease holder to either publish the value to Memcache or for `max_wait` to
+    /// elapse, whichever comes first.
+    async fn wait_for_value(&self, memcache: &MemcacheHandler, memcache_key: &str) -> Option<Bytes> {
+        let deadline = tokio::time::Instant::now() + self.max_wait;
+        loop {
+            if let Ok(Some(value)) = memcache.get(memcache_key.to_owned()).await {
+                return Some(value);
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return None;
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+}
+
+/// Per-caller in-memory memoization, consulted before cachelib. A store opts in by holding one
+/// of these - typically scoped to a single request or `CoreContext` - and returning it from
+/// [`KeyedEntityStore::request_memo`], so repeated lookups of the same key within that scope
+/// (common e.g. when walking a manifest) cost a `HashMap` probe instead of a cachelib round trip
+/// and clone. caching_ext has no request concept of its own, so the store is responsible for
+/// creating a fresh `RequestMemo` per request and dropping it at the end.
+pub struct RequestMemo<K, V> {
+    cache: Mutex<HashMap<K, V>>,
+}
+
+impl<K, V> RequestMemo<K, V> {
+    pub fn new() -> Self {
+        Self {
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K, V> Default for RequestMemo<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> RequestMemo<K, V> {
+    /// Split `keys` into what's already memoized and what still needs to be fetched.
+    fn partition(&self, keys: HashSet<K>) -> (HashMap<K, V>, HashSet<K>) {
+        let cache = self.cache.lock().expect("lock poisoned");
+        let mut found = HashMap::with_capacity(keys.len());
+        let mut remaining = HashSet::new();
+        for key in keys {
+            match cache.get(&key) {
+                Some(v) => {
+                    found.insert(key, v.clone());
+                }
+                None => {
+                    remaining.insert(key);
+                }
+            }
+        }
+        (found, remaining)
+    }
+
+    fn insert_many<'a>(&self, values: impl IntoIterator<Item = (&'a K, &'a V)>)
+    where
+        K: 'a,
+        V: 'a,
+    {
+        let mut cache = self.cache.lock().expect("lock poisoned");
+        for (k, v) in values {
+            cache.insert(k.clone(), v.clone());
+        }
+    }
+}
+
 /// Implement this to make it possible to fetch keys via the cache
 #[async_trait]
 pub trait KeyedEntityStore<K, V>: EntityStore<V> {
     /// Given an item key, return the cachelib key to use.
     fn get_cache_key(&self, key: &K) -> String;
 
+    /// Opt into a per-caller memo map consulted before cachelib. See [`RequestMemo`]. Defaults
+    /// to no memoization.
+    fn request_memo(&self) -> Option<&RequestMemo<K, V>> {
+        None
+    }
+
     /// Given a set of keys to fetch from backing store, return a map from keys to fetched values
     ///
     /// If a key has no value in the backing store, omit it from the result map. Only use an
     /// Error for a failure to fetch, not absence
     async fn get_from_db(&self, keys: HashSet<K>) -> Result<HashMap<K, V>, Error>;
 
+    /// Like [`Self::get_from_db`], but for a store whose misses can be large enough that
+    /// `fill_one_chunk` shouldn't have to wait for the whole scan before it can start warming
+    /// cachelib/Memcache with the keys that have already come back. Defaults to wrapping
+    /// `get_from_db`'s all-at-once result in a single-item stream, so a store that doesn't
+    /// override this keeps its existing behavior exactly.
+    ///
+    /// Note this only makes the cache-filling side of a miss incremental; `get_or_fill` and
+    /// friends still wait for every key in a chunk to resolve before returning to the caller.
+    /// Streaming partial results back to the caller as they arrive would need a new
+    /// streaming-result entry point alongside `get_or_fill_chunked`, which is out of scope here.
+    fn get_from_db_streamed(&self, keys: HashSet<K>) -> BoxStream<'_, Result<(K, V), Error>>
+    where
+        K: Send,
+        V: Send,
+    {
+        self.get_from_db(keys)
+            .map(|res| match res {
+                Ok(data) => stream::iter(data.into_iter().map(Ok)).boxed(),
+                Err(e) => stream::once(async move { Err(e) }).boxed(),
+            })
+            .flatten_stream()
+            .boxed()
+    }
+
     fn on_memcache_hits<'a>(&self, _values: impl IntoIterator<Item = (&'a K, &'a V)>)
     where
         K: 'a,
         V: 'a,
     {
     }
+
+    /// Called after each layer is queried by [`get_or_fill_chunked_with_origin`] and friends,
+    /// with how many of the requested keys were served from `origin` this call. Defaults to a
+    /// no-op; the counts are already visible in aggregate via `EntityStore::stats`, so this only
+    /// needs overriding by a store whose caller wants them broken out per request (e.g. into
+    /// `CoreContext` perf counters) - caching_ext has no dependency on `context` of its own.
+    fn on_fetch_origin(&self, _origin: FetchOrigin, _count: usize) {}
+
+    /// Opt into per-key coalescing of concurrent `get_from_db` calls that would otherwise fetch
+    /// the same key. Defaults to no coalescing.
+    fn single_flight(&self) -> Option<&SingleFlight<K, V>> {
+        None
+    }
+
+    /// Opt into cross-host lease coordination of `get_from_db` calls for the same Memcache key,
+    /// so that when a hot key expires, only one host fetches from the backing store at a time
+    /// and the rest briefly wait on its result instead of also going to the backing store.
+    /// Defaults to no coordination. See [`MemcacheLease`].
+    fn memcache_lease(&self) -> Option<&MemcacheLease> {
+        None
+    }
 }
 
 /// Utility function to fetch all keys in a single chunk without parallelism
@@ -161,7 +1273,7 @@ pub async fn get_or_fill<K, V>(
     keys: HashSet<K>,
 ) -> Result<HashMap<K, V>, Error>
 where
-    K: Hash + Eq + Clone,
+    K: Hash + Eq + Clone + Send,
     // TODO: We should relax the bounds on cachelib's set_cached. We don't need all of this:
     V: Abomonation + MemcacheEntity + Send + Clone + 'static,
 {
@@ -180,6 +1292,12 @@ where
 /// and parallel fetching. Keys to fetch from the backing store
 /// will be split into `fetch_chunk` size groups, and at most `parallel_chunks`
 /// groups will be in flight at once.
+///
+/// This is an all-or-nothing wrapper around [`get_or_fill_chunked_partial`]: if any chunk
+/// fails to fetch from the backing store, the whole call fails, even though other chunks -
+/// and everything already served from cachelib or memcache - succeeded. Callers that would
+/// rather keep the keys they did resolve and inspect the rest should call
+/// `get_or_fill_chunked_partial` directly.
 pub async fn get_or_fill_chunked<K, V>(
     store: &impl KeyedEntityStore<K, V>,
     keys: HashSet<K>,
@@ -187,74 +1305,434 @@ pub async fn get_or_fill_chunked<K, V>(
     parallel_chunks: usize,
 ) -> Result<HashMap<K, V>, Error>
 where
-    K: Hash + Eq + Clone,
+    K: Hash + Eq + Clone + Send,
     // TODO: We should relax the bounds on cachelib's set_cached. We don't need all of this:
     V: Abomonation + MemcacheEntity + Send + Clone + 'static,
 {
-    let mut ret = HashMap::<K, V>::with_capacity(keys.len());
+    let partial = get_or_fill_chunked_partial(store, keys, fetch_chunk, parallel_chunks).await?;
+    if let Some(error) = partial.errors.into_iter().next() {
+        return Err(error);
+    }
+    Ok(partial.found)
+}
 
-    let stats = store.stats();
+/// The result of [`get_or_fill_chunked_partial`]: the keys that were resolved, the keys that
+/// were looked up successfully but have no value anywhere (cache or backing store), and the
+/// errors encountered fetching the rest from the backing store.
+///
+/// A key that was in a chunk whose backing store fetch failed ends up in neither `found` nor
+/// `missing` - it's simply absent from both, with the failure recorded in `errors` instead.
+/// `fill_one_chunk` fetches a whole chunk as one backing-store call, so a failure can't be
+/// attributed to a single key within it.
+pub struct PartialFillResult<K, V> {
+    pub found: HashMap<K, V>,
+    pub missing: HashSet<K>,
+    pub errors: Vec<Error>,
+}
 
-    let cachelib_keys: Vec<_> = keys
-        .into_iter()
-        .map(|key| {
-            let cachelib_key = CachelibKey(store.get_cache_key(&key));
-            (key, cachelib_key)
-        })
-        .collect();
+/// Like [`get_or_fill_chunked`], but tolerates backing store failures: keys that were served
+/// from cachelib, memcache, or a backing store chunk that succeeded are returned in `found`
+/// even if another chunk's fetch failed. Use this when serving the data you do have is better
+/// than failing the whole request over a storage blip affecting only some keys.
+pub async fn get_or_fill_chunked_partial<K, V>(
+    store: &impl KeyedEntityStore<K, V>,
+    keys: HashSet<K>,
+    fetch_chunk: usize,
+    parallel_chunks: usize,
+) -> Result<PartialFillResult<K, V>, Error>
+where
+    K: Hash + Eq + Clone + Send,
+    // TODO: We should relax the bounds on cachelib's set_cached. We don't need all of this:
+    V: Abomonation + MemcacheEntity + Send + Clone + 'static,
+{
+    let with_origin = get_or_fill_chunked_with_origin(store, keys, fetch_chunk, parallel_chunks).await?;
+    Ok(PartialFillResult {
+        found: with_origin
+            .found
+            .into_iter()
+            .map(|(k, (v, _origin))| (k, v))
+            .collect(),
+        missing: with_origin.missing,
+        errors: with_origin.errors,
+    })
+}
+
+/// The handles and cache policy a [`get_or_fill_with`] call needs, bundled up so a one-off
+/// cached lookup doesn't have to define its own `KeyedEntityStore` impl just to provide them.
+///
+/// Only covers what `EntityStore` has no default for - a call site that also needs to tune e.g.
+/// `memcache_compression` or `cache_version` has outgrown `get_or_fill_with` and should define
+/// its own store instead.
+pub struct StoreParts<V> {
+    pub cachelib: CachelibHandler<V>,
+    pub keygen: KeyGen,
+    pub memcache: MemcacheHandler,
+    pub cache_disposition: CacheDisposition,
+}
 
-    let (fetched_from_cachelib, to_fetch_from_memcache) = store
-        .cachelib()
-        .get_multiple_from_cachelib::<K>(cachelib_keys)
-        .with_context(|| "Error reading from cachelib")?;
+/// Like [`get_or_fill`], but for call sites that would otherwise have to define a throwaway
+/// `KeyedEntityStore` impl just to satisfy the trait for a single lookup. `parts` bundles the
+/// handles and cache policy that impl would otherwise have provided (see [`StoreParts`]), and
+/// `get_from_db` stands in for `KeyedEntityStore::get_from_db`.
+///
+/// `K`'s cache key is simply `key.to_string()` - a lookup whose keys need a different mapping
+/// (or that needs any of the knobs `StoreParts` doesn't cover) should define its own store and
+/// call `get_or_fill` directly.
+pub async fn get_or_fill_with<K, V, F, Fut>(
+    parts: &StoreParts<V>,
+    keys: HashSet<K>,
+    get_from_db: F,
+) -> Result<HashMap<K, V>, Error>
+where
+    K: Hash + Eq + Clone + Send + ToString,
+    // TODO: We should relax the bounds on cachelib's set_cached. We don't need all of this:
+    V: Abomonation + MemcacheEntity + Send + Clone + 'static,
+    F: Fn(HashSet<K>) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<HashMap<K, V>, Error>> + Send,
+{
+    struct ClosureStore<'a, V, F> {
+        parts: &'a StoreParts<V>,
+        get_from_db: F,
+    }
 
-    stats
-        .cachelib_hit
-        .add_value(fetched_from_cachelib.len() as i64);
-    stats
-        .cachelib_miss
-        .add_value(to_fetch_from_memcache.len() as i64);
+    impl<'a, V, F> EntityStore<V> for ClosureStore<'a, V, F> {
+        fn cachelib(&self) -> &CachelibHandler<V> {
+            &self.parts.cachelib
+        }
 
-    ret.extend(fetched_from_cachelib);
+        fn keygen(&self) -> &KeyGen {
+            &self.parts.keygen
+        }
 
-    let to_fetch_from_memcache: Vec<(K, CachelibKey, MemcacheKey)> = to_fetch_from_memcache
-        .into_iter()
-        .map(|(key, cachelib_key)| {
-            let memcache_key = MemcacheKey(store.keygen().key(&cachelib_key.0));
-            (key, cachelib_key, memcache_key)
-        })
-        .collect();
+        fn memcache(&self) -> &MemcacheHandler {
+            &self.parts.memcache
+        }
+
+        fn cache_determinator(
+            &self,
+            _key: &str,
+            _v: &V,
+            _serialized_size: &dyn Fn() -> usize,
+        ) -> Result<CacheDisposition, Error> {
+            Ok(self.parts.cache_disposition)
+        }
+
+        impl_singleton_stats!("closure_store");
+    }
+
+    #[async_trait]
+    impl<'a, K, V, F, Fut> KeyedEntityStore<K, V> for ClosureStore<'a, V, F>
+    where
+        K: Hash + Eq + Clone + Send + ToString,
+        V: Send,
+        F: Fn(HashSet<K>) -> Fut + Send + Sync,
+        Fut: Future<Output = Result<HashMap<K, V>, Error>> + Send,
+    {
+        fn get_cache_key(&self, key: &K) -> String {
+            key.to_string()
+        }
+
+        async fn get_from_db(&self, keys: HashSet<K>) -> Result<HashMap<K, V>, Error> {
+            (self.get_from_db)(keys).await
+        }
+    }
+
+    let store = ClosureStore { parts, get_from_db };
+    get_or_fill(&store, keys).await
+}
+
+/// Number of keys fetched per `get_from_db` call while warming the cache with [`warm_cache`].
+const WARM_CACHE_CHUNK_SIZE: usize = 1000;
+
+/// Prefill the cache for every key in `key_stream`, for use on startup or after a Memcache
+/// sitever bump, where every consumer's first request would otherwise miss the cache at once
+/// and stampede the backing store together.
+///
+/// Keys are pulled off `key_stream` [`WARM_CACHE_CHUNK_SIZE`] at a time and fetched via
+/// [`get_or_fill_chunked_partial`], rate limited to at most `qps_limit` chunk fetches per
+/// second so warmup competes gently with live traffic for the backing store instead of
+/// saturating it. `on_progress` is called after each chunk with the cumulative number of keys
+/// warmed so far, so a caller can log or report progress however it likes - caching_ext has no
+/// logging dependency of its own.
+///
+/// A chunk whose backing store fetch fails doesn't abort the rest of the warmup (see
+/// [`get_or_fill_chunked_partial`]); its error is collected and warming continues with the
+/// next chunk. All collected errors are returned once `key_stream` is exhausted.
+pub async fn warm_cache<K, V>(
+    store: &impl KeyedEntityStore<K, V>,
+    key_stream: impl Stream<Item = K> + Send,
+    qps_limit: u32,
+    on_progress: &dyn Fn(usize),
+) -> Result<Vec<Error>, Error>
+where
+    K: Hash + Eq + Clone + Send,
+    // TODO: We should relax the bounds on cachelib's set_cached. We don't need all of this:
+    V: Abomonation + MemcacheEntity + Send + Clone + 'static,
+{
+    let limiter = AsyncLimiter::new(RateLimiter::direct(Quota::per_second(
+        NonZeroU32::new(qps_limit).unwrap_or(NonZeroU32::new(1).expect("1 is non-zero")),
+    )))
+    .await;
+
+    let mut chunks = key_stream.chunks(WARM_CACHE_CHUNK_SIZE);
+    let mut warmed = 0;
+    let mut errors = Vec::new();
+
+    while let Some(chunk) = chunks.next().await {
+        limiter.access().await?;
+
+        let keys: HashSet<K> = chunk.into_iter().collect();
+        let chunk_len = keys.len();
+        let partial = get_or_fill_chunked_partial(store, keys, WARM_CACHE_CHUNK_SIZE, 1).await?;
+        errors.extend(partial.errors);
+
+        warmed += chunk_len;
+        on_progress(warmed);
+    }
+
+    Ok(errors)
+}
 
-    let to_fetch_from_store = {
-        let (fetched_from_memcache, to_fetch_from_store) =
-            get_multiple_from_memcache(store.memcache(), to_fetch_from_memcache, stats).await;
+/// Which layer a value came from when it was fetched by
+/// [`get_or_fill_chunked_with_origin`]: in order, the store's [`RequestMemo`], cachelib,
+/// memcache, or the backing store behind `get_from_db`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FetchOrigin {
+    Memo,
+    Cachelib,
+    Memcache,
+    Db,
+}
+
+/// Like [`PartialFillResult`], but `found` also records which layer served each key, so
+/// callers can log cache effectiveness (e.g. into scuba) instead of only seeing the aggregate
+/// counts in `EntityStore::stats`.
+///
+/// `stale` holds the keys that were served from Memcache but are older than
+/// [`EntityStore::memcache_refresh_ahead`] - they're present in `found` as usual, but a caller
+/// that cares about keeping hot keys warm should enqueue its own `get_from_db` refetch for them.
+pub struct OriginFillResult<K, V> {
+    pub found: HashMap<K, (V, FetchOrigin)>,
+    pub missing: HashSet<K>,
+    pub errors: Vec<Error>,
+    pub stale: HashSet<K>,
+}
+
+/// Like [`get_or_fill_chunked_partial`], but tags every resolved value with the
+/// [`FetchOrigin`] it was served from.
+pub async fn get_or_fill_chunked_with_origin<K, V>(
+    store: &impl KeyedEntityStore<K, V>,
+    keys: HashSet<K>,
+    fetch_chunk: usize,
+    parallel_chunks: usize,
+) -> Result<OriginFillResult<K, V>, Error>
+where
+    K: Hash + Eq + Clone + Send,
+    // TODO: We should relax the bounds on cachelib's set_cached. We don't need all of this:
+    V: Abomonation + MemcacheEntity + Send + Clone + 'static,
+{
+    let original_keys = keys.clone();
+    let mut ret = HashMap::<K, (V, FetchOrigin)>::with_capacity(keys.len());
+
+    let stats = store.stats();
+    let mut stale = HashSet::new();
+
+    let skip_fill = matches!(store.cache_mode(), CacheMode::Bypass | CacheMode::ReadOnly);
+    let skip_memo = store.fill_only() || store.cache_mode() == CacheMode::Bypass;
+
+    let keys = match (skip_memo, store.request_memo()) {
+        (false, Some(memo)) => {
+            let (memoized, remaining) = memo.partition(keys);
+            stats.memo_hit.add_value(memoized.len() as i64);
+            stats.memo_miss.add_value(remaining.len() as i64);
+            store.on_fetch_origin(FetchOrigin::Memo, memoized.len());
+            ret.extend(memoized.into_iter().map(|(k, v)| (k, (v, FetchOrigin::Memo))));
+            remaining
+        }
+        _ => keys,
+    };
+
+    let to_fetch_from_store: Vec<(K, CachelibKey, MemcacheKey)> = if store.fill_only()
+        || store.cache_mode() == CacheMode::Bypass
+    {
+        // FillOnly (or CacheMode::Bypass): go straight to the backing store without checking
+        // cachelib or Memcache, so a backfill/migration job sees source-of-truth data even if a
+        // stale value happens to be cached. Unlike plain FillOnly, Bypass also skips the
+        // write-back below, via `skip_fill`, so it leaves the cache untouched either way.
+        keys.into_iter()
+            .map(|key| {
+                let cachelib_key = cachelib_key_for(store, &key);
+                let memcache_key = memcache_key_for(store, &cachelib_key);
+                (key, cachelib_key, memcache_key)
+            })
+            .collect()
+    } else {
+        let cachelib_keys: Vec<_> = keys
+            .into_iter()
+            .map(|key| {
+                let cachelib_key = cachelib_key_for(store, &key);
+                (key, cachelib_key)
+            })
+            .collect();
+
+        let (fetched_from_cachelib, to_fetch_from_memcache) = store
+            .cachelib()
+            .get_multiple_from_cachelib::<K>(cachelib_keys)
+            .with_context(|| "Error reading from cachelib")?;
+
+        stats
+            .cachelib_hit
+            .add_value(fetched_from_cachelib.len() as i64);
+        record_prometheus_stat("mononoke_cache_cachelib_hit_total", fetched_from_cachelib.len() as i64);
+        store.on_fetch_origin(FetchOrigin::Cachelib, fetched_from_cachelib.len());
+        stats
+            .cachelib_miss
+            .add_value(to_fetch_from_memcache.len() as i64);
+        record_prometheus_stat(
+            "mononoke_cache_cachelib_miss_total",
+            to_fetch_from_memcache.len() as i64,
+        );
+
+        ret.extend(
+            fetched_from_cachelib
+                .into_iter()
+                .map(|(k, v)| (k, (v, FetchOrigin::Cachelib))),
+        );
+
+        let to_fetch_from_memcache: Vec<(K, CachelibKey, MemcacheKey)> = to_fetch_from_memcache
+            .into_iter()
+            .map(|(key, cachelib_key)| {
+                let memcache_key = memcache_key_for(store, &cachelib_key);
+                (key, cachelib_key, memcache_key)
+            })
+            .collect();
+
+        let (fetched_from_memcache, to_fetch_from_store) = get_multiple_from_memcache(
+            store.memcache(),
+            to_fetch_from_memcache,
+            stats,
+            store.memcache_concurrency(),
+            store.memcache_compression(),
+            store.memcache_chunking(),
+            store.memcache_refresh_ahead(),
+            store.memcache_timeout(),
+            store.clock(),
+            &|key, message| store.on_deserialize_error(key, message),
+        )
+        .await;
 
         stats
             .memcache_hit
             .add_value(fetched_from_memcache.len() as i64);
+        record_prometheus_stat("mononoke_cache_memcache_hit_total", fetched_from_memcache.len() as i64);
+        store.on_fetch_origin(FetchOrigin::Memcache, fetched_from_memcache.len());
         stats
             .memcache_miss
             .add_value(to_fetch_from_store.len() as i64);
+        record_prometheus_stat(
+            "mononoke_cache_memcache_miss_total",
+            to_fetch_from_store.len() as i64,
+        );
 
-        store.on_memcache_hits(fetched_from_memcache.iter().map(|(k, (v, _))| (k, v)));
+        store.on_memcache_hits(fetched_from_memcache.iter().map(|(k, (v, _, _))| (k, v)));
 
         fill_multiple_cachelib(
             store.cachelib(),
             fetched_from_memcache
                 .values()
-                .filter_map(|(v, k)| match store.cache_determinator(v) {
-                    CacheDisposition::Cache(ttl) => Some((k, ttl, v)),
-                    _ => None,
-                }),
+                .filter_map(|(v, k, _)| admission(store, &k.0, v).cachelib.map(|ttl| (k, ttl, v))),
         );
 
-        ret.extend(fetched_from_memcache.into_iter().map(|(k, (v, _))| (k, v)));
+        stale.extend(
+            fetched_from_memcache
+                .iter()
+                .filter(|(_, (_, _, is_stale))| *is_stale)
+                .map(|(k, _)| k.clone()),
+        );
+
+        ret.extend(
+            fetched_from_memcache
+                .into_iter()
+                .map(|(k, (v, _, _))| (k, (v, FetchOrigin::Memcache))),
+        );
 
         to_fetch_from_store
     };
 
+    let mut errors = Vec::new();
+
     if !to_fetch_from_store.is_empty() {
-        let to_fetch_from_store: Vec<_> = to_fetch_from_store
+        let single_flight = store.single_flight();
+
+        // Split off keys that another in-flight fetch is already covering: they join that
+        // fetch's result instead of being fetched again below.
+        let (needs_fetch, joined) = match single_flight {
+            Some(single_flight) => {
+                let inflight = single_flight.inflight.lock().expect("lock poisoned");
+                let mut needs_fetch = Vec::with_capacity(to_fetch_from_store.len());
+                let mut joined = Vec::new();
+                for entry in to_fetch_from_store {
+                    match inflight.get(&entry.2.0) {
+                        Some(fut) => joined.push((entry.0, fut.clone())),
+                        None => needs_fetch.push(entry),
+                    }
+                }
+                (needs_fetch, joined)
+            }
+            None => (to_fetch_from_store, Vec::new()),
+        };
+
+        // Race for the Memcache lease on every key this host still needs to fetch. Losers poll
+        // Memcache for the winner's result instead of also going to the backing store.
+        let mut acquired_lease_keys = Vec::new();
+        let needs_fetch = match store.memcache_lease() {
+            Some(lease) => {
+                let mut still_needs_fetch = Vec::with_capacity(needs_fetch.len());
+                for (key, cachelib_key, memcache_key) in needs_fetch {
+                    if lease.try_acquire(store.memcache(), &memcache_key.0).await {
+                        acquired_lease_keys.push(memcache_key.0.clone());
+                        still_needs_fetch.push((key, cachelib_key, memcache_key));
+                        continue;
+                    }
+                    match lease.wait_for_value(store.memcache(), &memcache_key.0).await {
+                        Some(bytes) => match V::deserialize(bytes) {
+                            Ok(v) => {
+                                ret.insert(key, (v, FetchOrigin::Memcache));
+                            }
+                            Err(McErrorKind::Deserialization(message)) => {
+                                // Same poisoned-key accounting as the main Memcache fetch path in
+                                // `get_multiple_from_memcache`: a value that doesn't deserialize is
+                                // just as likely to be garbage here, as it's the same key, just
+                                // observed via the lease-wait path instead of a direct `get`.
+                                stats.memcache_deserialize_err.add_value(1);
+                                record_prometheus_stat(
+                                    "mononoke_cache_memcache_deserialize_err_total",
+                                    1,
+                                );
+                                store.on_deserialize_error(&memcache_key.0, &message);
+                                if record_memcache_deserialization_failure(
+                                    &memcache_key.0,
+                                    store.clock().now(),
+                                ) {
+                                    let memcache = store.memcache().clone();
+                                    let poisoned_key = memcache_key.0.clone();
+                                    tokio::task::spawn(async move {
+                                        let _ = memcache.del(poisoned_key).await;
+                                    });
+                                }
+                                still_needs_fetch.push((key, cachelib_key, memcache_key));
+                            }
+                            Err(_) => still_needs_fetch.push((key, cachelib_key, memcache_key)),
+                        },
+                        None => still_needs_fetch.push((key, cachelib_key, memcache_key)),
+                    }
+                }
+                still_needs_fetch
+            }
+            None => needs_fetch,
+        };
+
+        let mut chunk_futures: Vec<BoxFuture<'_, Result<HashMap<K, V>, Error>>> = needs_fetch
             .into_iter()
             .chunks(fetch_chunk)
             .into_iter()
@@ -265,53 +1743,179 @@ where
                     keys.insert(key.clone());
                     key_mapping.insert(key.clone(), (cachelib_key, memcache_key));
                 }
-                fill_one_chunk(store, keys, key_mapping)
+
+                // Register a single-flight receiver for every key in this chunk before the fetch
+                // starts, so callers for the same key that arrive while it's outstanding join it
+                // rather than starting a fetch of their own.
+                let broadcast = single_flight.map(|single_flight| {
+                    let memcache_keys: Vec<String> = key_mapping
+                        .values()
+                        .map(|(_, memcache_key)| memcache_key.0.clone())
+                        .collect();
+                    let (sender, receiver) = oneshot::channel();
+                    let receiver = receiver.shared();
+                    let mut inflight = single_flight.inflight.lock().expect("lock poisoned");
+                    for memcache_key in &memcache_keys {
+                        inflight.insert(memcache_key.clone(), receiver.clone());
+                    }
+                    (sender, memcache_keys)
+                });
+
+                let fetch = fill_one_chunk(store, keys, key_mapping, skip_fill);
+                async move {
+                    let result = fetch.await;
+                    if let (Some(single_flight), Some((sender, memcache_keys))) =
+                        (single_flight, broadcast)
+                    {
+                        let to_send = match &result {
+                            Ok(map) => Ok(Arc::new(map.clone())),
+                            Err(e) => Err(Arc::new(anyhow::anyhow!("{:#}", e))),
+                        };
+                        // No receivers is not an error: it just means every joiner that was
+                        // registered already gave up (e.g. its own future was dropped).
+                        let _ = sender.send(to_send);
+                        let mut inflight = single_flight.inflight.lock().expect("lock poisoned");
+                        for memcache_key in &memcache_keys {
+                            inflight.remove(memcache_key);
+                        }
+                    }
+                    result
+                }
+                .boxed()
             })
             .collect();
-        stream::iter(to_fetch_from_store)
+
+        chunk_futures.extend(joined.into_iter().map(|(key, fut)| {
+            async move {
+                match fut.await {
+                    Ok(Ok(map)) => {
+                        let mut ret = HashMap::new();
+                        if let Some(v) = map.get(&key) {
+                            ret.insert(key, v.clone());
+                        }
+                        Ok(ret)
+                    }
+                    Ok(Err(e)) => Err(anyhow::anyhow!("{:#}", e)),
+                    // The leader's fetch was dropped before it could send a result (e.g. its task
+                    // panicked). Don't report that as a miss for this key - fetch it solo instead.
+                    Err(_canceled) => {
+                        let cachelib_key = cachelib_key_for(store, &key);
+                        let memcache_key = memcache_key_for(store, &cachelib_key);
+                        let mut key_mapping = HashMap::new();
+                        key_mapping.insert(key.clone(), (cachelib_key, memcache_key));
+                        let mut keys = HashSet::new();
+                        keys.insert(key);
+                        fill_one_chunk(store, keys, key_mapping, skip_fill).await
+                    }
+                }
+            }
+            .boxed()
+        }));
+
+        let results: Vec<Result<HashMap<K, V>, Error>> = stream::iter(chunk_futures)
             .buffer_unordered(parallel_chunks)
-            .try_fold(&mut ret, |ret, chunk| async move {
-                ret.extend(chunk);
-                Ok::<_, Error>(ret)
-            })
-            .await?;
+            .collect()
+            .await;
+        for result in results {
+            match result {
+                Ok(chunk) => ret.extend(
+                    chunk
+                        .into_iter()
+                        .map(|(k, v)| (k, (v, FetchOrigin::Db))),
+                ),
+                Err(e) => errors.push(e),
+            }
+        }
+
+        // Release any leases this host won now that their fetches are done, so other hosts
+        // don't have to wait out the full lease TTL. Released in the background, same as
+        // cacheblob's memcache lease release, since nothing here depends on it completing.
+        if let (Some(lease), false) = (store.memcache_lease(), acquired_lease_keys.is_empty()) {
+            let lease = *lease;
+            let memcache = store.memcache().clone();
+            tokio::task::spawn(async move {
+                for memcache_key in acquired_lease_keys {
+                    lease.release(&memcache, &memcache_key).await;
+                }
+            });
+        }
+    }
+
+    if !skip_memo {
+        if let Some(memo) = store.request_memo() {
+            memo.insert_many(ret.iter().map(|(k, (v, _))| (k, v)));
+        }
     }
 
-    Ok(ret)
+    let missing = original_keys
+        .into_iter()
+        .filter(|k| !ret.contains_key(k))
+        .collect();
+
+    Ok(OriginFillResult {
+        found: ret,
+        missing,
+        errors,
+        stale,
+    })
 }
 
 async fn fill_one_chunk<K, V>(
     store: &impl KeyedEntityStore<K, V>,
     keys: HashSet<K>,
     mut key_mapping: HashMap<K, (CachelibKey, MemcacheKey)>,
+    skip_fill: bool,
 ) -> Result<HashMap<K, V>, Error>
 where
-    K: Hash + Eq + Clone,
+    K: Hash + Eq + Clone + Send,
     // TODO: We should relax the bounds on cachelib's set_cached. We don't need all of this:
     V: Abomonation + MemcacheEntity + Send + Clone + 'static,
 {
     let n_keys = keys.len();
 
     let stats = store.stats();
-    let data = store
-        .get_from_db(keys)
-        .await
-        .with_context(|| "Error reading from store")?;
+    let mut data = HashMap::with_capacity(n_keys);
+
+    // `ready_chunks` forwards whatever the stream already has available without waiting for a
+    // full batch, so a store that genuinely streams fills the cache as results trickle in, while
+    // the default (wraps get_from_db's HashMap in one shot) still fills it in a single batch.
+    let mut db_stream = store
+        .get_from_db_streamed(keys)
+        .ready_chunks(DB_STREAM_FILL_BATCH);
+    while let Some(batch) = db_stream.next().await {
+        let mut fetched = Vec::with_capacity(batch.len());
+        for item in batch {
+            fetched.push(item.with_context(|| "Error reading from store")?);
+        }
+
+        if !skip_fill {
+            fill_caches_by_key(
+                store,
+                fetched.iter().map(|(key, v)| {
+                    let (cachelib_key, memcache_key) = key_mapping
+                        .remove(key)
+                        .expect(
+                            "caching_ext: Missing entry in key_mapping, this should not happen",
+                        );
+
+                    (cachelib_key, memcache_key, v)
+                }),
+            )
+            .await;
+        }
+
+        data.extend(fetched);
+    }
 
     stats.origin_hit.add_value(data.len() as i64);
+    record_prometheus_stat("mononoke_cache_origin_hit_total", data.len() as i64);
     stats.origin_miss.add_value((n_keys - data.len()) as i64);
+    record_prometheus_stat(
+        "mononoke_cache_origin_miss_total",
+        (n_keys - data.len()) as i64,
+    );
+    store.on_fetch_origin(FetchOrigin::Db, data.len());
 
-    fill_caches_by_key(
-        store,
-        data.iter().map(|(key, v)| {
-            let (cachelib_key, memcache_key) = key_mapping
-                .remove(key)
-                .expect("caching_ext: Missing entry in key_mapping, this should not happen");
-
-            (cachelib_key, memcache_key, v)
-        }),
-    )
-    .await;
     Ok(data)
 }
 
@@ -324,10 +1928,10 @@ pub fn fill_cachelib<'a, K, V>(
 {
     let mut cachelib_keys = Vec::new();
     for (k, v) in data {
-        let cachelib_key = CachelibKey(store.get_cache_key(k));
-        let ttl = match store.cache_determinator(v) {
-            CacheDisposition::Cache(ttl) => ttl,
-            CacheDisposition::Ignore => continue,
+        let cachelib_key = cachelib_key_for(store, k);
+        let ttl = match admission(store, &cachelib_key.0, v).cachelib {
+            Some(ttl) => ttl,
+            None => continue,
         };
         cachelib_keys.push((cachelib_key, ttl, v));
     }
@@ -336,6 +1940,9 @@ pub fn fill_cachelib<'a, K, V>(
 
 /// Directly fill a cache from data you've prefetched outside the caching system
 /// Allows things like microwave to avoid any backing store fetches
+///
+/// If `store.invalidate_on_write()` is set, this deletes `data`'s cachelib/Memcache entries
+/// instead of writing them - see [`EntityStore::invalidate_on_write`].
 pub async fn fill_cache<'a, K, V>(
     store: &impl KeyedEntityStore<K, V>,
     data: impl IntoIterator<Item = (&'a K, &'a V)>,
@@ -343,17 +1950,188 @@ pub async fn fill_cache<'a, K, V>(
     K: Hash + Eq + Clone + 'a,
     V: Abomonation + MemcacheEntity + Send + Clone + 'static,
 {
+    if store.invalidate_on_write() {
+        for (k, _v) in data {
+            let cachelib_key = cachelib_key_for(store, k);
+            let _ = store.cachelib().remove_cached(&cachelib_key.0);
+
+            let memcache = store.memcache();
+            if !memcache.is_noop() {
+                let memcache_key = memcache_key_for(store, &cachelib_key);
+                let _ = memcache.del(memcache_key.0).await;
+            }
+        }
+        return;
+    }
+
     fill_caches_by_key(
         store,
         data.into_iter().map(|(k, v)| {
-            let cachelib_key = CachelibKey(store.get_cache_key(k));
-            let memcache_key = MemcacheKey(store.keygen().key(&cachelib_key.0));
+            let cachelib_key = cachelib_key_for(store, k);
+            let memcache_key = memcache_key_for(store, &cachelib_key);
             (cachelib_key, memcache_key, v)
         }),
     )
     .await;
 }
 
+/// Write `new_value` to the cache for `key`, but only if the value currently in Memcache still
+/// matches `expected` - the value the caller last read and derived `new_value` from. Returns
+/// whether the write went through; a caller that gets back `false` should re-read the key and
+/// retry its update against the newer value instead of assuming its write landed.
+///
+/// Use this instead of [`fill_cache`] for an entity that's updated via read-modify-write: two
+/// callers racing to update the same key from the same `expected` value would otherwise both
+/// succeed with `fill_cache`, leaving Memcache holding whichever write happened to land last
+/// regardless of which one actually observed the more recent state. `expected` of `None` means
+/// "only write if the key isn't cached in Memcache at all yet".
+///
+/// This is a lock-guarded compare-and-swap, not a true memcached CAS command: this crate's
+/// Memcache client doesn't expose the `gets`/`cas` token pair that would take, so instead a short
+/// per-key lock (acquired the same way [`MemcacheLease`] acquires its lease) serializes the
+/// compare-and-write sequence across callers. That's enough to guarantee the property above, but
+/// it does not make this atomic with respect to a concurrent plain `fill_cache`/`get_or_fill` call
+/// for the same key, which can still race with it in either direction - callers that need the
+/// guarantee above should only ever update a given key through `update_cache`.
+pub async fn update_cache<K, V>(
+    store: &impl KeyedEntityStore<K, V>,
+    key: &K,
+    expected: Option<&V>,
+    new_value: &V,
+) -> bool
+where
+    K: Hash + Eq + Clone,
+    V: Abomonation + MemcacheEntity + Send + Clone + 'static,
+{
+    let cachelib_key = cachelib_key_for(store, key);
+    let decision = admission(store, &cachelib_key.0, new_value);
+
+    let memcache = store.memcache();
+    let Some(memcache_ttl) = decision.memcache else {
+        // Nothing in Memcache to race with - fall back to an unconditional cachelib write, the
+        // same way fill_cache would.
+        if let Some(ttl) = decision.cachelib {
+            fill_multiple_cachelib(
+                store.cachelib(),
+                std::iter::once((&cachelib_key, ttl, new_value)),
+            );
+        }
+        return true;
+    };
+    if memcache.is_noop() {
+        if let Some(ttl) = decision.cachelib {
+            fill_multiple_cachelib(
+                store.cachelib(),
+                std::iter::once((&cachelib_key, ttl, new_value)),
+            );
+        }
+        return true;
+    }
+
+    let memcache_key = memcache_key_for(store, &cachelib_key);
+    let lock_key = format!("{}.update_lock", memcache_key.0);
+
+    let acquired = memcache
+        .add_with_ttl(lock_key.clone(), Bytes::new(), UPDATE_CACHE_LOCK_TTL)
+        .await
+        // An error talking to Memcache shouldn't block the update - treat it as a win, the same
+        // way every other best-effort Memcache operation in this module does.
+        .unwrap_or(true);
+    if !acquired {
+        return false;
+    }
+
+    // A Memcache read error is treated as "couldn't confirm a match" rather than "no value",
+    // so a transient error fails the swap closed instead of risking clobbering an unseen write.
+    let current = memcache.get(memcache_key.0.clone()).await.unwrap_or(None);
+    let matches = match (&current, expected) {
+        (None, None) => true,
+        (Some(current), Some(expected)) => *current == expected.serialize(),
+        _ => false,
+    };
+
+    let swapped = if matches {
+        if let Some(ttl) = decision.cachelib {
+            fill_multiple_cachelib(
+                store.cachelib(),
+                std::iter::once((&cachelib_key, ttl, new_value)),
+            );
+        }
+        let bytes = new_value.serialize();
+        if bytes.len() < MEMCACHE_VALUE_MAX_SIZE {
+            match memcache_ttl {
+                CacheTtl::NoTtl => {
+                    let _ = memcache.set(memcache_key.0.clone(), bytes).await;
+                }
+                CacheTtl::Ttl(ttl) => {
+                    let _ = memcache.set_with_ttl(memcache_key.0.clone(), bytes, ttl).await;
+                }
+            }
+        }
+        true
+    } else {
+        false
+    };
+
+    let _ = memcache.del(lock_key).await;
+    swapped
+}
+
+/// The result of comparing a store's cachelib entries for a sample of keys against what
+/// `get_from_db` (the bypass-cache read path) returns for the same keys right now.
+///
+/// A key that isn't cached at all doesn't count towards `diverged`: an empty cache is expected
+/// (e.g. cold start, evicted under memory pressure) and isn't a correctness problem on its own.
+/// What this is looking for is a cached value that disagrees with the source of truth, which
+/// would mean a store is serving stale or wrong data without anyone noticing.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct CacheAuditReport {
+    pub sampled: usize,
+    pub diverged: usize,
+}
+
+impl CacheAuditReport {
+    pub fn divergence_rate(&self) -> f64 {
+        if self.sampled == 0 {
+            0.0
+        } else {
+            self.diverged as f64 / self.sampled as f64
+        }
+    }
+}
+
+/// Sample `keys` from a store built on this crate and compare their cachelib entries against
+/// the source of truth, fetched via the same bypass-cache `get_from_db` path a cache miss would
+/// use. Intended to be called periodically (e.g. by a background auditing job) with a small
+/// random sample of known keys, so persistent divergence between a cache and its backing store
+/// shows up as a nonzero divergence rate rather than as a stream of one-off bug reports.
+pub async fn audit_cache_consistency<K, V>(
+    store: &impl KeyedEntityStore<K, V>,
+    keys: impl IntoIterator<Item = K>,
+) -> Result<CacheAuditReport, Error>
+where
+    K: Hash + Eq + Clone,
+    V: Abomonation + Clone + Send + PartialEq + 'static,
+{
+    let keys: HashSet<K> = keys.into_iter().collect();
+    let truth = store.get_from_db(keys.clone()).await?;
+
+    let mut report = CacheAuditReport {
+        sampled: keys.len(),
+        diverged: 0,
+    };
+    for key in &keys {
+        let cachelib_key = cachelib_key_for(store, key);
+        let cached = store.cachelib().get_cached(&cachelib_key.0)?;
+        if let (Some(cached), Some(actual)) = (cached, truth.get(key)) {
+            if cached != *actual {
+                report.diverged += 1;
+            }
+        }
+    }
+    Ok(report)
+}
+
 async fn fill_caches_by_key<'a, V>(
     store: &impl EntityStore<V>,
     data: impl IntoIterator<Item = (CachelibKey, MemcacheKey, &'a V)>,
@@ -364,63 +2142,167 @@ async fn fill_caches_by_key<'a, V>(
     let mut memcache_keys = Vec::new();
 
     for (cachelib_key, memcache_key, v) in data.into_iter() {
-        let ttl = match store.cache_determinator(v) {
-            CacheDisposition::Cache(ttl) => ttl,
-            CacheDisposition::Ignore => continue,
-        };
+        let decision = admission(store, &cachelib_key.0, v);
 
-        memcache_keys.push((memcache_key, ttl, v));
-        cachelib_keys.push((cachelib_key, ttl, v));
+        if let Some(ttl) = decision.cachelib {
+            cachelib_keys.push((cachelib_key, ttl, v));
+        }
+        if let Some(ttl) = decision.memcache {
+            memcache_keys.push((memcache_key, ttl, v));
+        }
     }
 
     fill_multiple_cachelib(store.cachelib(), cachelib_keys);
 
-    fill_multiple_memcache(store.memcache(), memcache_keys).await;
+    fill_multiple_memcache(
+        store.memcache(),
+        store.stats(),
+        memcache_keys,
+        store.memcache_compression(),
+        store.memcache_chunking(),
+        store.memcache_ttl_jitter_pct(),
+        store.memcache_refresh_ahead().is_some(),
+        store.clock(),
+    )
+    .await;
 }
 
 async fn get_multiple_from_memcache<K, V>(
     memcache: &MemcacheHandler,
     keys: Vec<(K, CachelibKey, MemcacheKey)>,
     stats: &CacheStats,
+    concurrency: usize,
+    compression: bool,
+    chunking: bool,
+    refresh_ahead: Option<Duration>,
+    timeout: Option<Duration>,
+    clock: &dyn Clock,
+    on_deserialize_error: &dyn Fn(&str, &str),
 ) -> (
-    HashMap<K, (V, CachelibKey)>,
+    HashMap<K, (V, CachelibKey, bool)>,
     Vec<(K, CachelibKey, MemcacheKey)>,
 )
 where
     K: Eq + Hash,
     V: MemcacheEntity,
 {
-    let mc_fetch_futs = keys
+    let mut fetched = HashMap::new();
+    let mut left_to_fetch = Vec::new();
+    let now = clock.now();
+
+    if memcache_circuit_breaker_is_open(stats, now) {
+        stats
+            .memcache_circuit_breaker_skip
+            .add_value(keys.len() as i64);
+        record_prometheus_stat(
+            "mononoke_cache_memcache_circuit_breaker_skip_total",
+            keys.len() as i64,
+        );
+        return (fetched, keys);
+    }
+
+    let mut to_fetch = Vec::new();
+    for (key, cachelib_key, memcache_key) in keys {
+        if is_poisoned_memcache_key(&memcache_key.0, now) {
+            stats.memcache_poisoned_skip.add_value(1);
+            record_prometheus_stat("mononoke_cache_memcache_poisoned_skip_total", 1);
+            left_to_fetch.push((key, cachelib_key, memcache_key));
+        } else {
+            to_fetch.push((key, cachelib_key, memcache_key));
+        }
+    }
+
+    let mc_fetch_futs = to_fetch
         .into_iter()
         .map(move |(key, cachelib_key, memcache_key)| {
             cloned!(memcache);
             async move {
-                let res = memcache
-                    .get(memcache_key.0.clone())
-                    .await
-                    .map_err(|_| McErrorKind::MemcacheInternal)
-                    .and_then(|maybe_bytes| maybe_bytes.ok_or(McErrorKind::Missing))
-                    .and_then(V::deserialize);
-
-                (key, cachelib_key, memcache_key, res)
+                let fetch = async {
+                    let fetched = memcache
+                        .get(memcache_key.0.clone())
+                        .await
+                        .map_err(|_| McErrorKind::MemcacheInternal)
+                        .and_then(|maybe_bytes| maybe_bytes.ok_or(McErrorKind::Missing));
+
+                    let res = match fetched {
+                        Ok(bytes) if chunking => {
+                            resolve_chunked_memcache_value(&memcache, &memcache_key.0, bytes).await
+                        }
+                        other => other,
+                    };
+
+                    // Chunked values never carry a refresh-ahead timestamp (see
+                    // `EntityStore::memcache_refresh_ahead`), so only strip and check one for the
+                    // unchunked case.
+                    let (is_stale, res) = match (res, chunking) {
+                        (Ok(bytes), false) if refresh_ahead.is_some() => {
+                            let (age, bytes) = split_refresh_ahead_timestamp(bytes, now);
+                            let is_stale = refresh_ahead.is_some_and(|threshold| age > threshold);
+                            (is_stale, Ok(bytes))
+                        }
+                        (res, _) => (false, res),
+                    };
+
+                    let res = res
+                        .and_then(|bytes| {
+                            // Chunking stores write the flag byte even when not compressing.
+                            if compression || chunking {
+                                decompress_from_memcache(bytes)
+                            } else {
+                                Ok(bytes)
+                            }
+                        })
+                        .and_then(V::deserialize);
+
+                    (res, is_stale)
+                };
+
+                let (res, is_stale) = match timeout {
+                    Some(timeout) => match tokio::time::timeout(timeout, fetch).await {
+                        Ok(outcome) => outcome,
+                        Err(_) => (Err(McErrorKind::Timeout), false),
+                    },
+                    None => fetch.await,
+                };
+
+                (key, cachelib_key, memcache_key, res, is_stale)
             }
         });
 
-    let mut entries = stream::iter(mc_fetch_futs).buffered(MEMCACHE_CONCURRENCY);
-
-    let mut fetched = HashMap::new();
-    let mut left_to_fetch = Vec::new();
+    let mut entries = stream::iter(mc_fetch_futs).buffered(concurrency);
 
-    while let Some((key, cachelib_key, memcache_key, res)) = entries.next().await {
+    while let Some((key, cachelib_key, memcache_key, res, is_stale)) = entries.next().await {
         match res {
             Ok(entity) => {
-                fetched.insert(key, (entity, cachelib_key));
+                record_memcache_deserialization_success(&memcache_key.0);
+                record_memcache_circuit_breaker_success(stats);
+                fetched.insert(key, (entity, cachelib_key, is_stale));
             }
             Err(e) => {
                 match e {
-                    McErrorKind::MemcacheInternal => stats.memcache_internal_err.add_value(1),
-                    McErrorKind::Deserialization => stats.memcache_deserialize_err.add_value(1),
+                    McErrorKind::MemcacheInternal => {
+                        stats.memcache_internal_err.add_value(1);
+                        record_prometheus_stat("mononoke_cache_memcache_internal_err_total", 1);
+                        record_memcache_circuit_breaker_failure(stats, now);
+                    }
+                    McErrorKind::Deserialization(message) => {
+                        stats.memcache_deserialize_err.add_value(1);
+                        record_prometheus_stat("mononoke_cache_memcache_deserialize_err_total", 1);
+                        on_deserialize_error(&memcache_key.0, &message);
+
+                        if record_memcache_deserialization_failure(&memcache_key.0, now) {
+                            cloned!(memcache);
+                            let poisoned_key = memcache_key.0.clone();
+                            tokio::task::spawn(async move {
+                                let _ = memcache.del(poisoned_key).await;
+                            });
+                        }
+                    }
                     McErrorKind::Missing => {} // no op, we record missing at a higher level anyway.
+                    McErrorKind::Timeout => {
+                        stats.memcache_timeout.add_value(1);
+                        record_prometheus_stat("mononoke_cache_memcache_timeout_total", 1);
+                    }
                 };
 
                 left_to_fetch.push((key, cachelib_key, memcache_key));
@@ -437,22 +2319,37 @@ fn fill_multiple_cachelib<'a, V>(
 ) where
     V: Abomonation + Clone + Send + 'static,
 {
-    for (cachelib_key, ttl, v) in data {
-        let cachelib_key = cachelib_key.borrow();
-
-        let ttl = match ttl {
-            CacheTtl::NoTtl => None,
-            CacheTtl::Ttl(ttl) => Some(ttl),
-        };
+    // `set_cached_multiple` needs a `&str` it can hold onto for the whole batch, but
+    // `cachelib_key` may be borrowed through an intermediate (e.g. `Arc<CachelibKey>`) that
+    // only lives for this closure's call - so collect once up front rather than re-borrowing
+    // per entry.
+    let entries: Vec<_> = data
+        .into_iter()
+        .map(|(cachelib_key, ttl, v)| {
+            let ttl = match ttl {
+                CacheTtl::NoTtl => None,
+                CacheTtl::Ttl(ttl) => Some(ttl),
+            };
+            (cachelib_key, ttl, v)
+        })
+        .collect();
 
-        // NOTE: We ignore failures to cache individual entries here.
-        let _ = cachelib.set_cached(&cachelib_key.0, v, ttl);
-    }
+    cachelib.set_cached_multiple(
+        entries
+            .iter()
+            .map(|(cachelib_key, ttl, v)| (cachelib_key.borrow().0.as_str(), *v, *ttl)),
+    );
 }
 
 async fn fill_multiple_memcache<'a, V>(
     memcache: &'a MemcacheHandler,
+    stats: &CacheStats,
     data: impl IntoIterator<Item = (MemcacheKey, CacheTtl, &'a V)>,
+    compression: bool,
+    chunking: bool,
+    ttl_jitter_pct: f64,
+    refresh_ahead: bool,
+    clock: &dyn Clock,
 ) where
     V: MemcacheEntity + 'a,
 {
@@ -460,34 +2357,84 @@ async fn fill_multiple_memcache<'a, V>(
         return;
     }
 
+    let data: Vec<_> = data.into_iter().collect();
+    let now = clock.now();
+
+    if memcache_circuit_breaker_is_open(stats, now) {
+        stats
+            .memcache_circuit_breaker_skip
+            .add_value(data.len() as i64);
+        record_prometheus_stat(
+            "mononoke_cache_memcache_circuit_breaker_skip_total",
+            data.len() as i64,
+        );
+        return;
+    }
+
+    // Chunked values can't carry a refresh-ahead timestamp - see
+    // `EntityStore::memcache_refresh_ahead`.
+    let tag_with_refresh_ahead = refresh_ahead && !chunking;
+
     let futs = data
         .into_iter()
         .filter_map(|(memcache_key, ttl, v)| {
+            let ttl = jittered_ttl(ttl, ttl_jitter_pct);
             let bytes = v.serialize();
+            // A store opted into chunking must still tag every value it writes with a flag byte,
+            // even ones it doesn't compress: `resolve_chunked_memcache_value` needs to be able to
+            // tell an ordinary value apart from a `ChunkIndex` by its first byte alone.
+            let bytes = if compression || chunking {
+                compress_for_memcache(bytes, compression)
+            } else {
+                bytes
+            };
+            let bytes = if tag_with_refresh_ahead {
+                with_refresh_ahead_timestamp(bytes, now)
+            } else {
+                bytes
+            };
+
+            if bytes.len() < MEMCACHE_VALUE_MAX_SIZE {
+                cloned!(memcache);
+                return Some(
+                    async move {
+                        match ttl {
+                            CacheTtl::NoTtl => {
+                                let _ = memcache.set(memcache_key.0, bytes).await;
+                            }
+                            CacheTtl::Ttl(ttl) => {
+                                let _ = memcache.set_with_ttl(memcache_key.0, bytes, ttl).await;
+                            }
+                        }
+                    }
+                    .boxed(),
+                );
+            }
 
-            if bytes.len() >= MEMCACHE_VALUE_MAX_SIZE {
+            if !chunking {
                 return None;
             }
 
             cloned!(memcache);
-
-            Some(async move {
-                match ttl {
-                    CacheTtl::NoTtl => {
-                        let _ = memcache.set(memcache_key.0, bytes).await;
-                    }
-                    CacheTtl::Ttl(ttl) => {
-                        let _ = memcache.set_with_ttl(memcache_key.0, bytes, ttl).await;
-                    }
-                }
-            })
+            Some(write_chunked(memcache, memcache_key, bytes, ttl).boxed())
         })
         .collect::<Vec<_>>();
 
     let fut = stream::iter(futs).for_each_concurrent(MEMCACHE_CONCURRENCY, |fut| fut);
 
     if memcache.is_async() {
-        tokio::task::spawn(fut);
+        match BACKGROUND_MEMCACHE_WRITE_PERMITS.try_acquire() {
+            Ok(permit) => {
+                tokio::task::spawn(async move {
+                    let _permit = permit;
+                    fut.await;
+                });
+            }
+            Err(_) => {
+                stats.memcache_background_write_dropped.add_value(1);
+                record_prometheus_stat("mononoke_cache_memcache_background_write_dropped_total", 1);
+            }
+        }
     } else {
         fut.await;
     }
@@ -524,6 +2471,44 @@ mod test {
         calls: AtomicUsize,
         keys: AtomicUsize,
         data: HashMap<String, TestEntity>,
+        single_flight: SingleFlight<String, TestEntity>,
+        // Artificial delay in `get_from_db`, so tests can arrange for two fetches of the same key
+        // to genuinely overlap.
+        delay: Duration,
+        memcache_concurrency: usize,
+        disposition: CacheDisposition,
+        // When set, `cache_determinator` returns this error instead of `disposition`, to
+        // exercise the error-is-treated-as-Ignore fallback in `admission`.
+        disposition_err: bool,
+        memcache_lease: Option<MemcacheLease>,
+        memcache_compression: bool,
+        memcache_chunking: bool,
+        memcache_ttl_jitter_pct: f64,
+        memcache_refresh_ahead: Option<Duration>,
+        memcache_timeout: Option<Duration>,
+        cache_version: u32,
+        fill_only: bool,
+        cache_mode: CacheMode,
+        invalidate_on_write: bool,
+        // Keys that `get_from_db` should fail on, to exercise partial-failure handling. A
+        // failing key poisons the whole chunk it's fetched in, same as a real storage error.
+        fail_keys: HashSet<String>,
+        // When non-empty, `get_from_db_streamed` yields these pairs one at a time (matching
+        // `keys`) instead of wrapping `get_from_db`'s all-at-once result, with a scheduling
+        // boundary between each so `fill_one_chunk` observes them as separate batches.
+        stream_db: Vec<(String, TestEntity)>,
+        // If set, `get_from_db_streamed` yields an error once it's yielded this many items,
+        // to exercise caching the items seen before a streamed scan fails partway through.
+        stream_fail_after: Option<usize>,
+        // Records every `on_fetch_origin` call, so tests can check a request's fetches were
+        // broken down by layer the way a real caller feeding `CoreContext` perf counters would
+        // expect.
+        fetch_origins: Mutex<Vec<(FetchOrigin, usize)>>,
+        request_memo: Option<RequestMemo<String, TestEntity>>,
+        clock: MockClock,
+        // Every `serialized_size()` value `cache_determinator` was handed, in call order, so a
+        // test can check it matches `TestEntity`'s real `MemcacheEntity::serialize` length.
+        seen_sizes: Mutex<Vec<usize>>,
     }
 
     impl TestStore {
@@ -535,6 +2520,28 @@ mod test {
                 calls: AtomicUsize::new(0),
                 keys: AtomicUsize::new(0),
                 data: HashMap::new(),
+                single_flight: SingleFlight::new(),
+                delay: Duration::ZERO,
+                memcache_concurrency: MEMCACHE_CONCURRENCY,
+                disposition: CacheDisposition::Cache(CacheTtl::NoTtl),
+                disposition_err: false,
+                memcache_lease: None,
+                memcache_compression: false,
+                memcache_chunking: false,
+                memcache_ttl_jitter_pct: 0.0,
+                memcache_refresh_ahead: None,
+                memcache_timeout: None,
+                cache_version: 0,
+                fill_only: false,
+                cache_mode: CacheMode::Normal,
+                invalidate_on_write: false,
+                fail_keys: HashSet::new(),
+                stream_db: Vec::new(),
+                stream_fail_after: None,
+                fetch_origins: Mutex::new(Vec::new()),
+                request_memo: None,
+                clock: MockClock::new(SystemTime::now()),
+                seen_sizes: Mutex::new(Vec::new()),
             }
         }
     }
@@ -552,8 +2559,61 @@ mod test {
             &self.memcache
         }
 
-        fn cache_determinator(&self, _: &TestEntity) -> CacheDisposition {
-            CacheDisposition::Cache(CacheTtl::NoTtl)
+        fn cache_determinator(
+            &self,
+            _key: &str,
+            _: &TestEntity,
+            serialized_size: &dyn Fn() -> usize,
+        ) -> Result<CacheDisposition, Error> {
+            if self.disposition_err {
+                return Err(anyhow::anyhow!("simulated cache_determinator failure"));
+            }
+            self.seen_sizes.lock().expect("lock poisoned").push(serialized_size());
+            Ok(self.disposition)
+        }
+
+        fn memcache_concurrency(&self) -> usize {
+            self.memcache_concurrency
+        }
+
+        fn memcache_compression(&self) -> bool {
+            self.memcache_compression
+        }
+
+        fn memcache_chunking(&self) -> bool {
+            self.memcache_chunking
+        }
+
+        fn memcache_ttl_jitter_pct(&self) -> f64 {
+            self.memcache_ttl_jitter_pct
+        }
+
+        fn memcache_refresh_ahead(&self) -> Option<Duration> {
+            self.memcache_refresh_ahead
+        }
+
+        fn memcache_timeout(&self) -> Option<Duration> {
+            self.memcache_timeout
+        }
+
+        fn cache_version(&self) -> u32 {
+            self.cache_version
+        }
+
+        fn fill_only(&self) -> bool {
+            self.fill_only
+        }
+
+        fn cache_mode(&self) -> CacheMode {
+            self.cache_mode
+        }
+
+        fn invalidate_on_write(&self) -> bool {
+            self.invalidate_on_write
+        }
+
+        fn clock(&self) -> &dyn Clock {
+            &self.clock
         }
 
         impl_singleton_stats!("test");
@@ -572,6 +2632,14 @@ mod test {
             self.calls.fetch_add(1, Ordering::Relaxed);
             self.keys.fetch_add(keys.len(), Ordering::Relaxed);
 
+            if self.delay > Duration::ZERO {
+                tokio::time::sleep(self.delay).await;
+            }
+
+            if keys.iter().any(|k| self.fail_keys.contains(k)) {
+                return Err(anyhow::anyhow!("simulated storage failure"));
+            }
+
             Ok(keys
                 .into_iter()
                 .filter_map(|k| {
@@ -580,6 +2648,62 @@ mod test {
                 })
                 .collect())
         }
+
+        fn get_from_db_streamed(
+            &self,
+            keys: HashSet<String>,
+        ) -> BoxStream<'_, Result<(String, TestEntity), Error>> {
+            if self.stream_db.is_empty() {
+                return self
+                    .get_from_db(keys)
+                    .map(|res| match res {
+                        Ok(data) => stream::iter(data.into_iter().map(Ok)).boxed(),
+                        Err(e) => stream::once(async move { Err(e) }).boxed(),
+                    })
+                    .flatten_stream()
+                    .boxed();
+            }
+
+            let fail_after = self.stream_fail_after;
+            let items: Vec<_> = self
+                .stream_db
+                .iter()
+                .filter(|(k, _)| keys.contains(k))
+                .cloned()
+                .collect();
+
+            stream::iter(items.into_iter().enumerate())
+                .then(move |(i, item)| async move {
+                    // Force a scheduling boundary between items, so `ready_chunks` in
+                    // `fill_one_chunk` sees each one as its own batch instead of coalescing
+                    // them into a single all-at-once fill.
+                    tokio::task::yield_now().await;
+                    match fail_after {
+                        Some(n) if i >= n => Err(anyhow::anyhow!("simulated storage failure")),
+                        _ => Ok(item),
+                    }
+                })
+                .boxed()
+        }
+
+        fn single_flight(&self) -> Option<&SingleFlight<String, TestEntity>> {
+            Some(&self.single_flight)
+        }
+
+        fn memcache_lease(&self) -> Option<&MemcacheLease> {
+            self.memcache_lease.as_ref()
+        }
+
+        fn request_memo(&self) -> Option<&RequestMemo<String, TestEntity>> {
+            self.request_memo.as_ref()
+        }
+
+        fn on_fetch_origin(&self, origin: FetchOrigin, count: usize) {
+            self.fetch_origins
+                .lock()
+                .expect("lock poisoned")
+                .push((origin, count));
+        }
     }
 
     #[tokio::test]
@@ -632,6 +2756,106 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn on_fetch_origin_reports_the_layer_each_fetch_was_served_from() -> Result<(), Error> {
+        let mut store = TestStore::new();
+
+        let e = TestEntity(vec![0]);
+        store.data.insert("key".into(), e.clone());
+
+        // Served from the backing store: a cachelib miss, a memcache miss, then a db hit.
+        get_or_fill(&store, hashset! {"key".into()}).await?;
+        assert_eq!(
+            *store.fetch_origins.lock().expect("lock poisoned"),
+            vec![
+                (FetchOrigin::Cachelib, 0),
+                (FetchOrigin::Memcache, 0),
+                (FetchOrigin::Db, 1)
+            ]
+        );
+
+        // Served from cachelib: no db fetch needed, so none is reported; memcache is still
+        // reported, with nothing left for it to serve.
+        store.fetch_origins.lock().expect("lock poisoned").clear();
+        get_or_fill(&store, hashset! {"key".into()}).await?;
+        assert_eq!(
+            *store.fetch_origins.lock().expect("lock poisoned"),
+            vec![(FetchOrigin::Cachelib, 1), (FetchOrigin::Memcache, 0)]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn request_memo_serves_repeat_lookups_without_touching_cachelib() -> Result<(), Error> {
+        let mut store = TestStore::new();
+        store.request_memo = Some(RequestMemo::new());
+
+        let e = TestEntity(vec![0]);
+        store.data.insert("key".into(), e.clone());
+
+        get_or_fill(&store, hashset! {"key".into()}).await?;
+        assert_eq!(
+            *store.fetch_origins.lock().expect("lock poisoned"),
+            vec![
+                (FetchOrigin::Memo, 0),
+                (FetchOrigin::Cachelib, 0),
+                (FetchOrigin::Memcache, 0),
+                (FetchOrigin::Db, 1)
+            ]
+        );
+
+        // Served from the memo this time: no cachelib or memcache lookup at all, though both
+        // are still reported, with nothing left for them to serve.
+        store.fetch_origins.lock().expect("lock poisoned").clear();
+        let cachelib_gets_before = store.cachelib.gets_count();
+        let res = get_or_fill(&store, hashset! {"key".into()}).await?;
+        assert_eq!(res, hashmap! { "key".to_string() => e });
+        assert_eq!(
+            *store.fetch_origins.lock().expect("lock poisoned"),
+            vec![
+                (FetchOrigin::Memo, 1),
+                (FetchOrigin::Cachelib, 0),
+                (FetchOrigin::Memcache, 0)
+            ]
+        );
+        assert_eq!(store.cachelib.gets_count(), cachelib_gets_before);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_or_fill_with_closure_fetches_and_then_caches() -> Result<(), Error> {
+        let parts = StoreParts {
+            cachelib: CachelibHandler::<TestEntity>::create_mock(),
+            keygen: KeyGen::new("", 0, 0),
+            memcache: MemcacheHandler::create_mock(),
+            cache_disposition: CacheDisposition::Cache(CacheTtl::NoTtl),
+        };
+
+        let db_calls = AtomicUsize::new(0);
+        let get_from_db = |keys: HashSet<String>| {
+            db_calls.fetch_add(1, Ordering::Relaxed);
+            async move {
+                Ok(keys
+                    .into_iter()
+                    .map(|k| (k, TestEntity(vec![0])))
+                    .collect())
+            }
+        };
+
+        let res = get_or_fill_with(&parts, hashset! {"key".to_string()}, get_from_db).await?;
+        assert_eq!(res, hashmap! { "key".to_string() => TestEntity(vec![0]) });
+        assert_eq!(db_calls.load(Ordering::Relaxed), 1);
+
+        // Now served from cachelib, so the closure isn't called again.
+        let res = get_or_fill_with(&parts, hashset! {"key".to_string()}, get_from_db).await?;
+        assert_eq!(res, hashmap! { "key".to_string() => TestEntity(vec![0]) });
+        assert_eq!(db_calls.load(Ordering::Relaxed), 1);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn fetch_from_db() -> Result<(), Error> {
         let mut store = TestStore::new();
@@ -694,6 +2918,242 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn get_or_fill_chunked_fails_whole_call_on_one_bad_chunk() -> Result<(), Error> {
+        let mut store = TestStore::new();
+
+        let e0 = TestEntity(vec![0]);
+        store.data.insert("key0".into(), e0);
+        store.fail_keys.insert("key1".into());
+
+        let res = get_or_fill_chunked(
+            &store,
+            hashset! { "key0".into(), "key1".into() },
+            1,
+            2,
+        )
+        .await;
+
+        assert!(res.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_or_fill_chunked_partial_keeps_keys_from_failing_chunk() -> Result<(), Error> {
+        let mut store = TestStore::new();
+
+        let e0 = TestEntity(vec![0]);
+        let e2 = TestEntity(vec![2]);
+        store.data.insert("key0".into(), e0.clone());
+        store.data.insert("key2".into(), e2.clone());
+        store.fail_keys.insert("key1".into());
+
+        let res = get_or_fill_chunked_partial(
+            &store,
+            hashset! { "key0".into(), "key1".into(), "key2".into() },
+            1,
+            3,
+        )
+        .await?;
+
+        assert_eq!(
+            res.found,
+            hashmap! { "key0".into() => e0, "key2".into() => e2 }
+        );
+        assert!(res.missing.is_empty());
+        assert_eq!(res.errors.len(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_or_fill_chunked_partial_reports_missing_keys() -> Result<(), Error> {
+        let store = TestStore::new();
+
+        let res = get_or_fill_chunked_partial(&store, hashset! { "key0".into() }, 1, 1).await?;
+
+        assert!(res.found.is_empty());
+        assert_eq!(res.missing, hashset! { "key0".into() });
+        assert!(res.errors.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn warm_cache_fills_cachelib_and_reports_progress() -> Result<(), Error> {
+        let mut store = TestStore::new();
+        store.data.insert("key0".into(), TestEntity(vec![0]));
+        store.data.insert("key1".into(), TestEntity(vec![1]));
+        store.data.insert("key2".into(), TestEntity(vec![2]));
+
+        let keys = vec!["key0".to_string(), "key1".to_string(), "key2".to_string()];
+        let progress = Mutex::new(Vec::new());
+        let errors = warm_cache(&store, stream::iter(keys.clone()), 100, &|warmed| {
+            progress.lock().expect("poisoned lock").push(warmed);
+        })
+        .await?;
+
+        assert!(errors.is_empty());
+        assert_eq!(*progress.lock().expect("poisoned lock"), vec![3]);
+
+        for key in keys {
+            assert_eq!(
+                store.cachelib.get_cached(&cachelib_key_for(&store, &key).0)?,
+                store.data.get(&key).cloned()
+            );
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_or_fill_chunked_with_origin_reports_cachelib_hit() -> Result<(), Error> {
+        let store = TestStore::new();
+
+        let e0 = TestEntity(vec![0]);
+        fill_cache(&store, hashmap! { "key0".into() => e0.clone() }.iter()).await;
+
+        let res =
+            get_or_fill_chunked_with_origin(&store, hashset! { "key0".into() }, 1, 1).await?;
+
+        assert_eq!(res.found.get("key0"), Some(&(e0, FetchOrigin::Cachelib)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_or_fill_chunked_with_origin_reports_memcache_hit() -> Result<(), Error> {
+        let mut store = TestStore::new();
+
+        let e0 = TestEntity(vec![0]);
+        fill_cache(&store, hashmap! { "key0".into() => e0.clone() }.iter()).await;
+        // Force a cachelib miss so the fetch has to fall through to memcache.
+        store.cachelib = CachelibHandler::create_mock();
+
+        let res =
+            get_or_fill_chunked_with_origin(&store, hashset! { "key0".into() }, 1, 1).await?;
+
+        assert_eq!(res.found.get("key0"), Some(&(e0, FetchOrigin::Memcache)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_or_fill_chunked_with_origin_reports_db_hit() -> Result<(), Error> {
+        let mut store = TestStore::new();
+
+        let e0 = TestEntity(vec![0]);
+        store.data.insert("key0".into(), e0.clone());
+
+        let res =
+            get_or_fill_chunked_with_origin(&store, hashset! { "key0".into() }, 1, 1).await?;
+
+        assert_eq!(res.found.get("key0"), Some(&(e0, FetchOrigin::Db)));
+        assert!(res.missing.is_empty());
+        assert!(res.errors.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn fetch_from_memcache_respects_concurrency_override() -> Result<(), Error> {
+        let mut store = TestStore::new();
+        store.memcache_concurrency = 1;
+
+        let e0 = TestEntity(vec![0]);
+        let e1 = TestEntity(vec![1]);
+        let e2 = TestEntity(vec![2]);
+
+        fill_cache(
+            &store,
+            hashmap! {
+                "key0".into() => e0.clone(),
+                "key1".into() => e1.clone(),
+                "key2".into() => e2.clone(),
+            }
+            .iter(),
+        )
+        .await;
+
+        // Force a cachelib miss so the fetch actually has to go through memcache.
+        store.cachelib = CachelibHandler::create_mock();
+
+        let res = get_or_fill(
+            &store,
+            hashset! { "key0".into(), "key1".into(), "key2".into() },
+        )
+        .await?;
+
+        assert_eq!(
+            res,
+            hashmap! { "key0".into() => e0, "key1".into() => e1, "key2".into() => e2 }
+        );
+        assert_eq!(store.memcache.gets_count(), 3);
+        assert_eq!(store.calls.load(Ordering::Relaxed), 0);
+
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn single_flight_coalesces_concurrent_fetches() -> Result<(), Error> {
+        let mut store = TestStore::new();
+        store.delay = Duration::from_millis(10);
+
+        let e0 = TestEntity(vec![0]);
+        store.data.insert("key0".into(), e0.clone());
+
+        let (res0, res1) = tokio::join!(
+            get_or_fill(&store, hashset! { "key0".into() }),
+            get_or_fill(&store, hashset! { "key0".into() }),
+        );
+
+        assert_eq!(res0?, hashmap! { "key0".into() => e0.clone() });
+        assert_eq!(res1?, hashmap! { "key0".into() => e0 });
+        // Only one of the two calls should have actually reached the backing store; the other
+        // joined its in-flight fetch.
+        assert_eq!(store.calls.load(Ordering::Relaxed), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn memcache_lease_avoids_thundering_herd_across_hosts() -> Result<(), Error> {
+        // Simulate two hosts: separate stores (so single-flight, which is in-process only,
+        // can't coalesce between them) that share a Memcache and a lease configuration.
+        let shared_memcache = MemcacheHandler::create_mock();
+        let lease = MemcacheLease::new(Duration::from_secs(10), Duration::from_millis(500));
+        let e0 = TestEntity(vec![0]);
+
+        let mut host_a = TestStore::new();
+        host_a.memcache = shared_memcache.clone();
+        host_a.memcache_lease = Some(lease);
+        host_a.delay = Duration::from_millis(50);
+        host_a.data.insert("key0".into(), e0.clone());
+
+        let mut host_b = TestStore::new();
+        host_b.memcache = shared_memcache;
+        host_b.memcache_lease = Some(lease);
+        host_b.delay = Duration::from_millis(50);
+        host_b.data.insert("key0".into(), e0.clone());
+
+        let (res_a, res_b) = tokio::join!(
+            get_or_fill(&host_a, hashset! { "key0".into() }),
+            get_or_fill(&host_b, hashset! { "key0".into() }),
+        );
+
+        assert_eq!(res_a?, hashmap! { "key0".into() => e0.clone() });
+        assert_eq!(res_b?, hashmap! { "key0".into() => e0 });
+        // Only the lease winner should have actually reached the backing store; the other host
+        // waited on the lease and picked up the winner's value from Memcache instead.
+        assert_eq!(
+            host_a.calls.load(Ordering::Relaxed) + host_b.calls.load(Ordering::Relaxed),
+            1
+        );
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn fetch_from_all() -> Result<(), Error> {
         let mut store = TestStore::new();
@@ -792,4 +3252,641 @@ mod test {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn cachelib_only_disposition_skips_memcache() -> Result<(), Error> {
+        let mut store = TestStore::new();
+        store.disposition = CacheDisposition::CachelibOnly(CacheTtl::NoTtl);
+
+        let e0 = TestEntity(vec![0]);
+        fill_cache(&store, hashmap! { "key0".into() => e0.clone() }.iter()).await;
+
+        assert_eq!(store.cachelib.mock_store().unwrap().stats().sets, 1);
+        assert_eq!(store.memcache.mock_store().unwrap().stats().sets, 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn memcache_only_disposition_skips_cachelib() -> Result<(), Error> {
+        let mut store = TestStore::new();
+        store.disposition = CacheDisposition::MemcacheOnly(CacheTtl::NoTtl);
+
+        let e0 = TestEntity(vec![0]);
+        fill_cache(&store, hashmap! { "key0".into() => e0.clone() }.iter()).await;
+
+        assert_eq!(store.cachelib.mock_store().unwrap().stats().sets, 0);
+        assert_eq!(store.memcache.mock_store().unwrap().stats().sets, 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cache_with_ttls_disposition_fills_both_layers() -> Result<(), Error> {
+        let mut store = TestStore::new();
+        store.disposition = CacheDisposition::CacheWithTtls {
+            cachelib: CacheTtl::Ttl(Duration::from_secs(60)),
+            memcache: CacheTtl::Ttl(Duration::from_secs(6 * 60 * 60)),
+        };
+
+        let e0 = TestEntity(vec![0]);
+        fill_cache(&store, hashmap! { "key0".into() => e0.clone() }.iter()).await;
+
+        assert_eq!(store.cachelib.mock_store().unwrap().stats().sets, 1);
+        assert_eq!(store.memcache.mock_store().unwrap().stats().sets, 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn memcache_compression_round_trips_large_and_small_values() -> Result<(), Error> {
+        let mut store = TestStore::new();
+        store.memcache_compression = true;
+
+        // Large enough, and compressible enough, to cross MEMCACHE_COMPRESSION_THRESHOLD.
+        let big = TestEntity(vec![0; 64 * 1024]);
+        let small = TestEntity(vec![7]);
+
+        fill_cache(
+            &store,
+            hashmap! { "big".into() => big.clone(), "small".into() => small.clone() }.iter(),
+        )
+        .await;
+
+        // Force a cachelib miss so both round-trip through the (possibly compressed) Memcache
+        // encoding rather than being served straight from cachelib.
+        store.cachelib = CachelibHandler::create_mock();
+
+        let res = get_or_fill(&store, hashset! { "big".into(), "small".into() }).await?;
+        assert_eq!(
+            res,
+            hashmap! { "big".into() => big, "small".into() => small }
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn memcache_chunking_round_trips_oversized_values() -> Result<(), Error> {
+        let mut store = TestStore::new();
+        store.memcache_chunking = true;
+
+        // Comfortably over MEMCACHE_VALUE_MAX_SIZE, and not a round multiple of
+        // MEMCACHE_CHUNK_SIZE, so reassembly has to handle a partial final chunk.
+        let huge = TestEntity(vec![42; MEMCACHE_VALUE_MAX_SIZE + MEMCACHE_CHUNK_SIZE / 2]);
+        let small = TestEntity(vec![7]);
+
+        fill_cache(
+            &store,
+            hashmap! { "huge".into() => huge.clone(), "small".into() => small.clone() }.iter(),
+        )
+        .await;
+
+        // Force a cachelib miss so both round-trip through Memcache.
+        store.cachelib = CachelibHandler::create_mock();
+
+        let res = get_or_fill(&store, hashset! { "huge".into(), "small".into() }).await?;
+        assert_eq!(
+            res,
+            hashmap! { "huge".into() => huge, "small".into() => small }
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn memcache_chunking_disabled_drops_oversized_values() -> Result<(), Error> {
+        let mut store = TestStore::new();
+
+        let huge = TestEntity(vec![42; MEMCACHE_VALUE_MAX_SIZE]);
+        fill_cache(&store, hashmap! { "huge".into() => huge.clone() }.iter()).await;
+
+        // Not chunked, so the oversized entry was never written to Memcache; only the (missing)
+        // backing-store fetch can serve it.
+        store.data.insert("huge".into(), huge.clone());
+        store.cachelib = CachelibHandler::create_mock();
+
+        let res = get_or_fill(&store, hashset! { "huge".into() }).await?;
+        assert_eq!(res, hashmap! { "huge".into() => huge });
+        assert_eq!(store.memcache.mock_store().unwrap().stats().sets, 0);
+        assert_eq!(store.calls.load(Ordering::Relaxed), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn update_cache_swaps_on_matching_expected() -> Result<(), Error> {
+        let mut store = TestStore::new();
+        let e0 = TestEntity(vec![0]);
+        let e1 = TestEntity(vec![1]);
+
+        // Nothing cached yet - `expected: None` should succeed and populate the cache.
+        assert!(update_cache(&store, &"key".to_string(), None, &e0).await);
+        let res = get_or_fill(&store, hashset! { "key".to_string() }).await?;
+        assert_eq!(res, hashmap! { "key".to_string() => e0.clone() });
+
+        // Updating from the value we just wrote should succeed.
+        assert!(update_cache(&store, &"key".to_string(), Some(&e0), &e1).await);
+        store.cachelib = CachelibHandler::create_mock();
+        let res = get_or_fill(&store, hashset! { "key".to_string() }).await?;
+        assert_eq!(res, hashmap! { "key".to_string() => e1 });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn update_cache_rejects_stale_expected() -> Result<(), Error> {
+        let mut store = TestStore::new();
+        let e0 = TestEntity(vec![0]);
+        let e1 = TestEntity(vec![1]);
+        let stale = TestEntity(vec![255]);
+
+        assert!(update_cache(&store, &"key".to_string(), None, &e0).await);
+
+        // `stale` is not what's currently cached, so this swap must be rejected and the cache
+        // left holding `e0`.
+        assert!(!update_cache(&store, &"key".to_string(), Some(&stale), &e1).await);
+
+        store.cachelib = CachelibHandler::create_mock();
+        let res = get_or_fill(&store, hashset! { "key".to_string() }).await?;
+        assert_eq!(res, hashmap! { "key".to_string() => e0 });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_audit_cache_consistency() -> Result<(), Error> {
+        let mut store = TestStore::new();
+        let e0 = TestEntity(vec![0]);
+        let e1 = TestEntity(vec![1]);
+        store.data.insert("key0".into(), e0.clone());
+        store.data.insert("key1".into(), e1.clone());
+
+        // Nothing is cached yet, so there is nothing to diverge from.
+        let report = audit_cache_consistency(&store, hashset! { "key0".into() }).await?;
+        assert_eq!(report, CacheAuditReport {
+            sampled: 1,
+            diverged: 0
+        });
+
+        // Cache "key0" correctly, and "key1" with a stale value.
+        fill_cache(&store, hashmap! { "key0".into() => e0.clone() }.iter()).await;
+        fill_cache(
+            &store,
+            hashmap! { "key1".into() => TestEntity(vec![255]) }.iter(),
+        )
+        .await;
+
+        let report =
+            audit_cache_consistency(&store, hashset! { "key0".into(), "key1".into() }).await?;
+        assert_eq!(report, CacheAuditReport {
+            sampled: 2,
+            diverged: 1
+        });
+        assert_eq!(report.divergence_rate(), 0.5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn short_key_is_not_hashed() {
+        let store = TestStore::new();
+        let cachelib_key = CachelibKey("key:short".to_string());
+        let memcache_key = memcache_key_for(&store, &cachelib_key);
+        assert_eq!(memcache_key.0, store.keygen.key(&cachelib_key.0));
+    }
+
+    #[test]
+    fn long_key_is_hashed_and_recoverable() {
+        let store = TestStore::new();
+        let cachelib_key = CachelibKey("key:".to_string() + &"x".repeat(MEMCACHE_KEY_MAX_LEN));
+        let full_key = store.keygen.key(&cachelib_key.0);
+        assert!(full_key.len() > MEMCACHE_KEY_MAX_LEN);
+
+        let memcache_key = memcache_key_for(&store, &cachelib_key);
+        assert!(memcache_key.0.len() <= MEMCACHE_KEY_MAX_LEN);
+        assert_ne!(memcache_key.0, full_key);
+
+        assert_eq!(
+            debug_unhash_memcache_key(&memcache_key.0),
+            Some(full_key.clone())
+        );
+
+        // Hashing the same long key again must produce the same hashed key, since the same
+        // value needs to round-trip through Memcache across different processes.
+        let memcache_key_again = memcache_key_for(&store, &cachelib_key);
+        assert_eq!(memcache_key.0, memcache_key_again.0);
+    }
+
+    #[test]
+    fn long_key_keeps_a_readable_prefix() {
+        let store = TestStore::new();
+        let cachelib_key = CachelibKey(format!("key:{}{}", "readable-part-", "x".repeat(400)));
+        let full_key = store.keygen().key(&cachelib_key.0);
+
+        let memcache_key = memcache_key_for(&store, &cachelib_key);
+        assert!(memcache_key.0.len() <= MEMCACHE_KEY_MAX_LEN);
+        assert!(memcache_key.0.starts_with("key:readable-part-"));
+        assert_ne!(memcache_key.0, full_key);
+    }
+
+    // `TestStore`'s `CacheStats` is a process-wide singleton (see `impl_singleton_stats!`), so
+    // the breaker state keyed off it is shared by every test in this module; both halves of the
+    // breaker's behaviour are exercised here, sequentially, rather than in separate `#[test]`s
+    // that could otherwise race on that same global entry.
+    #[test]
+    fn circuit_breaker_opens_after_threshold_and_clears_on_success() {
+        let store = TestStore::new();
+        let stats = store.stats();
+        let now = SystemTime::now();
+
+        for _ in 0..CIRCUIT_BREAKER_FAILURE_THRESHOLD - 1 {
+            record_memcache_circuit_breaker_failure(stats, now);
+            assert!(!memcache_circuit_breaker_is_open(stats, now));
+        }
+
+        record_memcache_circuit_breaker_failure(stats, now);
+        assert!(memcache_circuit_breaker_is_open(stats, now));
+
+        record_memcache_circuit_breaker_success(stats);
+        assert!(!memcache_circuit_breaker_is_open(stats, now));
+
+        // Re-trip it, then let it clear on its own once the cooldown elapses, with no real sleep.
+        let clock = MockClock::new(now);
+        for _ in 0..CIRCUIT_BREAKER_FAILURE_THRESHOLD {
+            record_memcache_circuit_breaker_failure(stats, clock.now());
+        }
+        assert!(memcache_circuit_breaker_is_open(stats, clock.now()));
+
+        clock.advance(CIRCUIT_BREAKER_COOLDOWN - Duration::from_secs(1));
+        assert!(memcache_circuit_breaker_is_open(stats, clock.now()));
+
+        clock.advance(Duration::from_secs(2));
+        assert!(!memcache_circuit_breaker_is_open(stats, clock.now()));
+    }
+
+    #[test]
+    fn poisoned_key_is_treated_as_fresh_once_its_ttl_elapses() {
+        let clock = MockClock::new(SystemTime::now());
+        let key = "poisoned-key-ttl-test";
+
+        for _ in 0..POISONED_KEY_FAILURE_THRESHOLD {
+            record_memcache_deserialization_failure(key, clock.now());
+        }
+        assert!(is_poisoned_memcache_key(key, clock.now()));
+
+        clock.advance(POISONED_KEY_TTL + Duration::from_secs(1));
+        assert!(!is_poisoned_memcache_key(key, clock.now()));
+    }
+
+    #[test]
+    fn jittered_ttl_no_jitter_is_unchanged() {
+        let ttl = Duration::from_secs(100);
+        match jittered_ttl(CacheTtl::Ttl(ttl), 0.0) {
+            CacheTtl::Ttl(jittered) => assert_eq!(jittered, ttl),
+            CacheTtl::NoTtl => panic!("expected a TTL"),
+        }
+
+        match jittered_ttl(CacheTtl::NoTtl, 0.5) {
+            CacheTtl::NoTtl => {}
+            CacheTtl::Ttl(_) => panic!("NoTtl has nothing to jitter"),
+        }
+    }
+
+    #[test]
+    fn jittered_ttl_stays_within_bounds() {
+        let ttl = Duration::from_secs(100);
+        for _ in 0..100 {
+            match jittered_ttl(CacheTtl::Ttl(ttl), 0.1) {
+                CacheTtl::Ttl(jittered) => {
+                    assert!(jittered >= Duration::from_secs(90));
+                    assert!(jittered <= Duration::from_secs(110));
+                }
+                CacheTtl::NoTtl => panic!("expected a TTL"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn memcache_ttl_jitter_does_not_prevent_writes() -> Result<(), Error> {
+        let mut store = TestStore::new();
+        store.memcache_ttl_jitter_pct = 0.5;
+        store.disposition = CacheDisposition::MemcacheOnly(CacheTtl::Ttl(Duration::from_secs(100)));
+
+        let e0 = TestEntity(vec![0]);
+        fill_cache(&store, hashmap! { "key0".into() => e0.clone() }.iter()).await;
+
+        assert_eq!(store.memcache.mock_store().unwrap().data().len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn with_refresh_ahead_timestamp_round_trips_as_fresh() {
+        let payload = Bytes::from_static(b"hello");
+        let now = SystemTime::now();
+        let tagged = with_refresh_ahead_timestamp(payload.clone(), now);
+        let (age, rest) = split_refresh_ahead_timestamp(tagged, now);
+        assert_eq!(age, Duration::ZERO);
+        assert_eq!(rest, payload);
+    }
+
+    #[test]
+    fn split_refresh_ahead_timestamp_computes_age() {
+        let written_at = SystemTime::now() - Duration::from_secs(120);
+        let tagged = with_refresh_ahead_timestamp(Bytes::from_static(b"payload"), written_at);
+
+        let (age, rest) = split_refresh_ahead_timestamp(tagged, SystemTime::now());
+        assert!(age >= Duration::from_secs(119));
+        assert!(age <= Duration::from_secs(121));
+        assert_eq!(rest, Bytes::from_static(b"payload"));
+    }
+
+    #[test]
+    fn split_refresh_ahead_timestamp_too_short_is_treated_as_fresh() {
+        let bytes = Bytes::from_static(b"ab");
+        let (age, rest) = split_refresh_ahead_timestamp(bytes.clone(), SystemTime::now());
+        assert_eq!(age, Duration::ZERO);
+        assert_eq!(rest, bytes);
+    }
+
+    #[test]
+    fn split_refresh_ahead_timestamp_uses_the_given_clock_not_the_wall_clock() {
+        let written_at = SystemTime::now();
+        let tagged = with_refresh_ahead_timestamp(Bytes::from_static(b"payload"), written_at);
+
+        // A mock clock far in the future sees the same write as much older, without any real
+        // passage of time.
+        let (age, _) = split_refresh_ahead_timestamp(tagged, written_at + Duration::from_secs(600));
+        assert_eq!(age, Duration::from_secs(600));
+    }
+
+    #[tokio::test]
+    async fn get_or_fill_chunked_with_origin_fresh_memcache_hit_is_not_stale() -> Result<(), Error> {
+        let mut store = TestStore::new();
+        store.memcache_refresh_ahead = Some(Duration::from_secs(60));
+
+        let e0 = TestEntity(vec![0]);
+        fill_cache(&store, hashmap! { "key0".into() => e0.clone() }.iter()).await;
+        // Force a cachelib miss so the fetch has to fall through to memcache.
+        store.cachelib = CachelibHandler::create_mock();
+
+        let res =
+            get_or_fill_chunked_with_origin(&store, hashset! { "key0".into() }, 1, 1).await?;
+
+        assert_eq!(res.found.get("key0"), Some(&(e0, FetchOrigin::Memcache)));
+        assert!(!res.stale.contains("key0"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_or_fill_chunked_with_origin_reports_stale_memcache_hit() -> Result<(), Error> {
+        let mut store = TestStore::new();
+        store.memcache_refresh_ahead = Some(Duration::from_secs(60));
+
+        let e0 = TestEntity(vec![0]);
+        fill_cache(&store, hashmap! { "key0".into() => e0.clone() }.iter()).await;
+        // Force a cachelib miss so the fetch has to fall through to memcache.
+        store.cachelib = CachelibHandler::create_mock();
+
+        // Advance the mock clock well past the refresh-ahead threshold instead of sleeping in
+        // the test, so the entry Memcache still holds now reads as written an hour ago.
+        store.clock.advance(Duration::from_secs(3600));
+
+        let res =
+            get_or_fill_chunked_with_origin(&store, hashset! { "key0".into() }, 1, 1).await?;
+
+        assert_eq!(res.found.get("key0"), Some(&(e0, FetchOrigin::Memcache)));
+        assert!(res.stale.contains("key0"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn memcache_timeout_falls_back_to_db() -> Result<(), Error> {
+        let mut store = TestStore::new();
+        store.memcache_timeout = Some(Duration::from_millis(10));
+        // Force a cachelib miss so the fetch has to go through memcache.
+        store.cachelib = CachelibHandler::create_mock();
+
+        let e0 = TestEntity(vec![0]);
+        // Present in both memcache and the backing store, so a successful memcache read and a
+        // DB fallback would otherwise be indistinguishable from the result alone.
+        store.data.insert("key0".into(), e0.clone());
+        let cachelib_key = cachelib_key_for(&store, &"key0".to_string());
+        let memcache_key = memcache_key_for(&store, &cachelib_key);
+        store
+            .memcache
+            .mock_store()
+            .unwrap()
+            .set(&memcache_key.0, e0.serialize());
+        store
+            .memcache
+            .mock_store()
+            .unwrap()
+            .set_delay(Duration::from_millis(100));
+
+        let res = get_or_fill(&store, hashset! { "key0".into() }).await?;
+
+        assert_eq!(res, hashmap! { "key0".into() => e0 });
+        assert_eq!(store.calls.load(Ordering::Relaxed), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn cache_version_zero_does_not_change_the_key() {
+        let store = TestStore::new();
+        let key = "key0".to_string();
+        assert_eq!(
+            cachelib_key_for(&store, &key).0,
+            store.get_cache_key(&key)
+        );
+    }
+
+    #[test]
+    fn cache_version_is_folded_into_the_cachelib_key() {
+        let mut store = TestStore::new();
+        let key = "key0".to_string();
+        let unversioned = cachelib_key_for(&store, &key);
+
+        store.cache_version = 1;
+        let v1 = cachelib_key_for(&store, &key);
+        assert_ne!(v1.0, unversioned.0);
+
+        store.cache_version = 2;
+        let v2 = cachelib_key_for(&store, &key);
+        assert_ne!(v2.0, v1.0);
+    }
+
+    #[tokio::test]
+    async fn bumping_cache_version_invalidates_old_entries() -> Result<(), Error> {
+        let mut store = TestStore::new();
+
+        let e0 = TestEntity(vec![0]);
+        fill_cache(&store, hashmap! { "key0".into() => e0.clone() }.iter()).await;
+
+        let res =
+            get_or_fill_chunked_with_origin(&store, hashset! { "key0".into() }, 1, 1).await?;
+        assert_eq!(res.found.get("key0"), Some(&(e0, FetchOrigin::Cachelib)));
+
+        // Bumping the version makes every previously-cached entry unreachable: the generated
+        // keys are different, so lookups for them can never hit what's already cached.
+        store.cache_version = 1;
+        let res =
+            get_or_fill_chunked_with_origin(&store, hashset! { "key0".into() }, 1, 1).await?;
+        assert_eq!(res.missing, hashset! { "key0".to_string() });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn fill_only_bypasses_cache_reads_but_still_warms_them() -> Result<(), Error> {
+        let mut store = TestStore::new();
+
+        let stale = TestEntity(vec![0]);
+        fill_cache(&store, hashmap! { "key0".into() => stale.clone() }.iter()).await;
+
+        let fresh = TestEntity(vec![1]);
+        store.data.insert("key0".into(), fresh.clone());
+
+        store.fill_only = true;
+        let res =
+            get_or_fill_chunked_with_origin(&store, hashset! { "key0".into() }, 1, 1).await?;
+
+        // Source-of-truth data wins even though a (stale) value is already cached.
+        assert_eq!(res.found.get("key0"), Some(&(fresh.clone(), FetchOrigin::Db)));
+
+        // The fresh value still warmed the cache for the next, non-FillOnly reader.
+        store.fill_only = false;
+        let res =
+            get_or_fill_chunked_with_origin(&store, hashset! { "key0".into() }, 1, 1).await?;
+        assert_eq!(res.found.get("key0"), Some(&(fresh, FetchOrigin::Cachelib)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cache_mode_bypass_skips_reads_and_never_fills() -> Result<(), Error> {
+        let mut store = TestStore::new();
+
+        let cached = TestEntity(vec![0]);
+        fill_cache(&store, hashmap! { "key0".into() => cached.clone() }.iter()).await;
+
+        let fresh = TestEntity(vec![1]);
+        store.data.insert("key0".into(), fresh.clone());
+
+        store.cache_mode = CacheMode::Bypass;
+        let res =
+            get_or_fill_chunked_with_origin(&store, hashset! { "key0".into() }, 1, 1).await?;
+        // Source-of-truth data wins, same as FillOnly.
+        assert_eq!(res.found.get("key0"), Some(&(fresh, FetchOrigin::Db)));
+
+        // Unlike FillOnly, Bypass never writes back: the stale value from before is still what's
+        // cached, and a non-Bypass reader sees it rather than what Bypass just fetched.
+        store.cache_mode = CacheMode::Normal;
+        let res =
+            get_or_fill_chunked_with_origin(&store, hashset! { "key0".into() }, 1, 1).await?;
+        assert_eq!(res.found.get("key0"), Some(&(cached, FetchOrigin::Cachelib)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cache_mode_read_only_reads_cache_but_never_fills() -> Result<(), Error> {
+        let mut store = TestStore::new();
+        store.cache_mode = CacheMode::ReadOnly;
+
+        let e0 = TestEntity(vec![0]);
+        store.data.insert("key0".into(), e0.clone());
+
+        let res =
+            get_or_fill_chunked_with_origin(&store, hashset! { "key0".into() }, 1, 1).await?;
+        assert_eq!(res.found.get("key0"), Some(&(e0.clone(), FetchOrigin::Db)));
+
+        // The backfill-style read above didn't leave anything behind in the cache.
+        store.cache_mode = CacheMode::Normal;
+        store.data.remove("key0");
+        let res =
+            get_or_fill_chunked_with_origin(&store, hashset! { "key0".into() }, 1, 1).await?;
+        assert_eq!(res.missing, hashset! { "key0".to_string() });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cache_determinator_err_is_treated_as_ignore() -> Result<(), Error> {
+        let mut store = TestStore::new();
+        store.disposition_err = true;
+
+        let e0 = TestEntity(vec![0]);
+        fill_cache(&store, hashmap! { "key0".into() => e0.clone() }.iter()).await;
+
+        // `cache_determinator` errored, so nothing got written to either cache layer - the same
+        // as if it had returned `Ok(CacheDisposition::Ignore)`.
+        assert_eq!(store.cachelib.mock_store().unwrap().stats().sets, 0);
+        assert_eq!(store.memcache.mock_store().unwrap().stats().sets, 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cache_determinator_sees_the_real_serialized_size() -> Result<(), Error> {
+        let store = TestStore::new();
+        let e0 = TestEntity(vec![7; 1234]);
+
+        fill_cache(&store, hashmap! { "key0".into() => e0.clone() }.iter()).await;
+
+        assert_eq!(*store.seen_sizes.lock().expect("lock poisoned"), vec![1234]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn invalidate_on_write_deletes_instead_of_filling() -> Result<(), Error> {
+        let mut store = TestStore::new();
+
+        let stale = TestEntity(vec![0]);
+        fill_cache(&store, hashmap! { "key0".into() => stale.clone() }.iter()).await;
+        assert_eq!(store.cachelib.mock_store().unwrap().stats().sets, 1);
+        assert_eq!(store.memcache.mock_store().unwrap().stats().sets, 1);
+
+        store.invalidate_on_write = true;
+        let fresh = TestEntity(vec![1]);
+        fill_cache(&store, hashmap! { "key0".into() => fresh.clone() }.iter()).await;
+
+        // The new value was never written - the old one was deleted instead.
+        assert_eq!(store.cachelib.mock_store().unwrap().stats().sets, 1);
+        assert!(store.memcache.mock_store().unwrap().data().is_empty());
+
+        let res = get_or_fill(&store, hashset! { "key0".to_string() }).await?;
+        assert_eq!(res.get("key0"), None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_from_db_streamed_caches_items_seen_before_a_later_failure() -> Result<(), Error> {
+        let mut store = TestStore::new();
+        store.stream_db = vec![
+            ("key0".into(), TestEntity(vec![0])),
+            ("key1".into(), TestEntity(vec![1])),
+        ];
+        // key1 is the second item streamed back, so key0 is already cached by the time it fails.
+        store.stream_fail_after = Some(1);
+
+        let res = get_or_fill(&store, hashset! { "key0".into(), "key1".into() }).await;
+        assert!(res.is_err());
+
+        // A later reader for key0 alone hits the cache this streamed fetch already warmed,
+        // rather than going back to the (now-empty) backing store.
+        store.stream_db = Vec::new();
+        store.calls.store(0, Ordering::Relaxed);
+        let res = get_or_fill(&store, hashset! { "key0".into() }).await?;
+        assert_eq!(res, hashmap! { "key0".into() => TestEntity(vec![0]) });
+        assert_eq!(store.calls.load(Ordering::Relaxed), 0);
+
+        Ok(())
+    }
 }
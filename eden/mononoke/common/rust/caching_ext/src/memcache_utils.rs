@@ -49,7 +49,13 @@ impl MemcacheHandler {
             MemcacheHandler::Real(ref client) => {
                 client.get(key).await.map(|value| value.map(Bytes::from))
             }
-            MemcacheHandler::Mock(store) => Ok(store.get(&key)),
+            MemcacheHandler::Mock(store) => {
+                let delay = store.delay();
+                if delay > Duration::ZERO {
+                    tokio::time::sleep(delay).await;
+                }
+                Ok(store.get(&key))
+            }
             MemcacheHandler::Noop => Ok(None),
         }
     }
@@ -86,6 +92,36 @@ impl MemcacheHandler {
         }
     }
 
+    /// Atomically set `key` to `value`, but only if it isn't already set. Returns whether this
+    /// call won the race, i.e. whether `key` is now set to `value`. Used by
+    /// [`crate::MemcacheLease`] to implement lease-by-add, where only one of several racing
+    /// callers should proceed. A no-op handler stores nothing and always reports a win.
+    pub async fn add_with_ttl<V>(&self, key: String, value: V, duration: Duration) -> Result<bool>
+    where
+        MemcacheSetType: From<V>,
+        Bytes: From<V>,
+        V: 'static,
+    {
+        match self {
+            MemcacheHandler::Real(ref client) => client.add_with_ttl(key, value, duration).await,
+            MemcacheHandler::Mock(store) => {
+                Ok(store.add_with_ttl(&key, value.into(), Some(duration)))
+            }
+            MemcacheHandler::Noop => Ok(true),
+        }
+    }
+
+    pub async fn del(&self, key: String) -> Result<()> {
+        match self {
+            MemcacheHandler::Real(ref client) => client.del(key).await,
+            MemcacheHandler::Mock(store) => {
+                store.remove(&key);
+                Ok(())
+            }
+            MemcacheHandler::Noop => Ok(()),
+        }
+    }
+
     pub fn create_mock() -> Self {
         MemcacheHandler::Mock(MockStore::new())
     }
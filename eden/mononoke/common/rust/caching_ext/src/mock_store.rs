@@ -10,6 +10,8 @@ use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
 
 #[derive(Debug, PartialEq)]
 pub struct MockStoreStats {
@@ -21,11 +23,14 @@ pub struct MockStoreStats {
 
 #[derive(Clone, Debug)]
 pub struct MockStore<T> {
-    data: Arc<Mutex<HashMap<String, T>>>,
+    data: Arc<Mutex<HashMap<String, (T, Option<Instant>)>>>,
     pub(crate) set_count: Arc<AtomicUsize>,
     pub(crate) get_count: Arc<AtomicUsize>,
     pub(crate) hit_count: Arc<AtomicUsize>,
     pub(crate) miss_count: Arc<AtomicUsize>,
+    // Artificial delay applied by `MemcacheHandler::get`, so tests can exercise
+    // `EntityStore::memcache_timeout` without a real, slow Memcache host.
+    delay: Arc<Mutex<Duration>>,
 }
 
 impl<T> MockStore<T> {
@@ -36,9 +41,19 @@ impl<T> MockStore<T> {
             get_count: Arc::new(AtomicUsize::new(0)),
             hit_count: Arc::new(AtomicUsize::new(0)),
             miss_count: Arc::new(AtomicUsize::new(0)),
+            delay: Arc::new(Mutex::new(Duration::ZERO)),
         }
     }
 
+    /// Delay every subsequent `get` by `delay`, to simulate a slow Memcache host in tests.
+    pub fn set_delay(&self, delay: Duration) {
+        *self.delay.lock().expect("poisoned lock") = delay;
+    }
+
+    pub(crate) fn delay(&self) -> Duration {
+        *self.delay.lock().expect("poisoned lock")
+    }
+
     pub fn stats(&self) -> MockStoreStats {
         MockStoreStats {
             sets: self.set_count.load(Ordering::SeqCst),
@@ -56,7 +71,15 @@ impl<T> MockStore<T> {
 impl<T: Clone> MockStore<T> {
     pub fn get(&self, key: &String) -> Option<T> {
         self.get_count.fetch_add(1, Ordering::SeqCst);
-        let value = self.data.lock().expect("poisoned lock").get(key).cloned();
+
+        let mut data = self.data.lock().expect("poisoned lock");
+        let expired =
+            matches!(data.get(key), Some((_, Some(expires_at))) if *expires_at <= Instant::now());
+        if expired {
+            data.remove(key);
+        }
+        let value = data.get(key).map(|(v, _)| v.clone());
+
         match &value {
             Some(..) => self.hit_count.fetch_add(1, Ordering::SeqCst),
             None => self.miss_count.fetch_add(1, Ordering::SeqCst),
@@ -65,16 +88,61 @@ impl<T: Clone> MockStore<T> {
     }
 
     pub fn set(&self, key: &str, value: T) {
+        self.set_with_ttl(key, value, None);
+    }
+
+    /// Like [`Self::set`], but the entry stops being visible to `get` once `ttl` elapses,
+    /// mirroring how the real cachelib pool honors a TTL passed to `set_cached`.
+    pub fn set_with_ttl(&self, key: &str, value: T, ttl: Option<Duration>) {
         self.set_count.fetch_add(1, Ordering::SeqCst);
+        let expires_at = ttl.map(|ttl| Instant::now() + ttl);
         self.data
             .lock()
             .expect("poisoned lock")
-            .insert(key.to_owned(), value);
+            .insert(key.to_owned(), (value, expires_at));
+    }
+
+    /// Like calling [`Self::set_with_ttl`] once per entry, but the lock is only taken once for
+    /// the whole batch.
+    pub fn set_multiple_with_ttl(
+        &self,
+        entries: impl IntoIterator<Item = (String, T, Option<Duration>)>,
+    ) {
+        let mut data = self.data.lock().expect("poisoned lock");
+        for (key, value, ttl) in entries {
+            self.set_count.fetch_add(1, Ordering::SeqCst);
+            let expires_at = ttl.map(|ttl| Instant::now() + ttl);
+            data.insert(key, (value, expires_at));
+        }
+    }
+
+    /// Like [`Self::set_with_ttl`], but only inserts if `key` isn't already present (and not
+    /// expired). Returns whether this call won the insert, mirroring Memcache's `add` command.
+    pub fn add_with_ttl(&self, key: &str, value: T, ttl: Option<Duration>) -> bool {
+        let mut data = self.data.lock().expect("poisoned lock");
+        let present =
+            matches!(data.get(key), Some((_, expires_at)) if expires_at.map_or(true, |e| e > Instant::now()));
+        if present {
+            return false;
+        }
+        self.set_count.fetch_add(1, Ordering::SeqCst);
+        let expires_at = ttl.map(|ttl| Instant::now() + ttl);
+        data.insert(key.to_owned(), (value, expires_at));
+        true
+    }
+
+    pub fn remove(&self, key: &str) {
+        self.data.lock().expect("poisoned lock").remove(key);
     }
 
     #[cfg(test)]
     pub(crate) fn data(&self) -> HashMap<String, T> {
-        self.data.lock().expect("poisoned lock").clone()
+        self.data
+            .lock()
+            .expect("poisoned lock")
+            .iter()
+            .map(|(k, (v, _))| (k.clone(), v.clone()))
+            .collect()
     }
 }
 
@@ -128,4 +196,34 @@ mod test {
             }
         );
     }
+
+    #[test]
+    fn ttl_expires_entries() {
+        let store = MockStore::new();
+
+        store.set_with_ttl("foo", "bar", Some(Duration::from_millis(1)));
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(store.get(&"foo".to_string()), None);
+
+        store.set_with_ttl("foo", "bar", None);
+        assert_eq!(store.get(&"foo".to_string()), Some("bar"));
+    }
+
+    #[test]
+    fn add_with_ttl_only_wins_when_absent() {
+        let store = MockStore::new();
+
+        assert!(store.add_with_ttl("foo", "first", None));
+        assert_eq!(store.get(&"foo".to_string()), Some("first"));
+
+        // Already present - the second caller loses the race.
+        assert!(!store.add_with_ttl("foo", "second", None));
+        assert_eq!(store.get(&"foo".to_string()), Some("first"));
+
+        // Once the entry expires, it's as if it was never set.
+        store.set_with_ttl("bar", "first", Some(Duration::from_millis(1)));
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(store.add_with_ttl("bar", "second", None));
+        assert_eq!(store.get(&"bar".to_string()), Some("second"));
+    }
 }
@@ -594,11 +594,16 @@ impl<F, T> EntityStore<CachedQueryResult<Vec<T>>> for QueryCacheStore<'_, F, T>
         &self.memcache
     }
 
-    fn cache_determinator(&self, v: &CachedQueryResult<Vec<T>>) -> CacheDisposition {
+    fn cache_determinator(
+        &self,
+        _key: &str,
+        v: &CachedQueryResult<Vec<T>>,
+        _serialized_size: &dyn Fn() -> usize,
+    ) -> Result<CacheDisposition> {
         if v.0.is_empty() {
-            CacheDisposition::Ignore
+            Ok(CacheDisposition::Ignore)
         } else {
-            CacheDisposition::Cache(CacheTtl::NoTtl)
+            Ok(CacheDisposition::Cache(CacheTtl::NoTtl))
         }
     }
 
@@ -645,7 +650,7 @@ where
     fn deserialize(bytes: Bytes) -> McResult<Self> {
         match serde_cbor::from_slice(bytes.as_ref()) {
             Ok(ok) => Ok(Self(ok)),
-            Err(_) => Err(McErrorKind::Deserialization),
+            Err(e) => Err(McErrorKind::Deserialization(e.to_string())),
         }
     }
 }
@@ -0,0 +1,122 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! A minimal Prometheus text-exposition facade for non-fbcode Mononoke builds.
+//!
+//! `stats::prelude`'s `define_stats!`/`define_stats_struct!` macros report through Meta's
+//! internal ODS pipeline; outside of fbcode builds, the counters they define compile down to
+//! no-ops, leaving OSS operators with nothing to scrape. This crate doesn't hook into that
+//! macro (its expansion isn't visible outside fbcode), so it can't make existing `stats` calls
+//! export themselves automatically. Instead, it's a small, independent counter registry that a
+//! call site can record the same numbers into, guarded by `#[cfg(not(fbcode_build))]`, and
+//! render in the Prometheus text exposition format.
+//!
+//! This only provides the registry and rendering; wiring an HTTP endpoint that serves
+//! [`PrometheusStatsRegistry::render`] is left to each OSS server binary's setup code.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::AtomicI64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+/// A monotonically-increasing counter, identified by name when rendered.
+#[derive(Default)]
+pub struct Counter(AtomicI64);
+
+impl Counter {
+    pub fn increment(&self) {
+        self.add(1);
+    }
+
+    pub fn add(&self, delta: i64) {
+        self.0.fetch_add(delta, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> i64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A registry of named counters, rendered in the Prometheus text exposition format.
+///
+/// Counter names should be valid Prometheus metric names (e.g. `mononoke_cache_hit_total`).
+/// This registry doesn't validate that, since all current callers use compile-time-constant
+/// names.
+#[derive(Default)]
+pub struct PrometheusStatsRegistry {
+    counters: Mutex<BTreeMap<&'static str, Arc<Counter>>>,
+}
+
+impl PrometheusStatsRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get, creating it if necessary, the counter registered under `name`.
+    pub fn counter(&self, name: &'static str) -> Arc<Counter> {
+        let mut counters = self
+            .counters
+            .lock()
+            .expect("PrometheusStatsRegistry lock poisoned");
+        counters
+            .entry(name)
+            .or_insert_with(|| Arc::new(Counter::default()))
+            .clone()
+    }
+
+    /// Render every registered counter in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let counters = self
+            .counters
+            .lock()
+            .expect("PrometheusStatsRegistry lock poisoned");
+        let mut out = String::new();
+        for (name, counter) in counters.iter() {
+            out.push_str(&format!("# TYPE {name} counter\n{name} {}\n", counter.get()));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn counter_starts_at_zero() {
+        let registry = PrometheusStatsRegistry::new();
+        assert_eq!(registry.counter("foo").get(), 0);
+    }
+
+    #[test]
+    fn increment_and_add_accumulate() {
+        let registry = PrometheusStatsRegistry::new();
+        registry.counter("mononoke_cache_hit_total").add(3);
+        registry.counter("mononoke_cache_hit_total").increment();
+        assert_eq!(registry.counter("mononoke_cache_hit_total").get(), 4);
+    }
+
+    #[test]
+    fn distinct_names_are_independent() {
+        let registry = PrometheusStatsRegistry::new();
+        registry.counter("a").increment();
+        registry.counter("b").add(5);
+        assert_eq!(registry.counter("a").get(), 1);
+        assert_eq!(registry.counter("b").get(), 5);
+    }
+
+    #[test]
+    fn render_matches_prometheus_text_exposition_format() {
+        let registry = PrometheusStatsRegistry::new();
+        registry.counter("mononoke_cache_hit_total").add(4);
+        assert_eq!(
+            registry.render(),
+            "# TYPE mononoke_cache_hit_total counter\nmononoke_cache_hit_total 4\n"
+        );
+    }
+}
@@ -47,6 +47,7 @@ use gotham_ext::state_ext::StateExt;
 use hyper::Body;
 use hyper::Response;
 use mime::Mime;
+use rate_limiting::RateLimitReason;
 use serde::Deserialize;
 use serde::Serialize;
 use time_ext::DurationExt;
@@ -187,6 +188,15 @@ impl HandlerInfo {
 struct JsonError {
     message: String,
     request_id: String,
+    /// Machine-readable error code, set when the error is one that a client might
+    /// reasonably want to react to automatically (e.g. by retrying after a delay),
+    /// as opposed to a generic failure that should just be surfaced to the user.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<&'static str>,
+    /// Number of seconds after which the client may retry, if known. Only set
+    /// alongside `code` values that represent throttling.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry_after_secs: Option<u64>,
 }
 
 struct JsonErrorFomatter;
@@ -197,10 +207,18 @@ impl ErrorFormatter for JsonErrorFomatter {
     fn format(&self, error: &Error, state: &State) -> Result<(Self::Body, Mime), Error> {
         let message = format!("{:#}", error);
 
+        let reason = error
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<RateLimitReason>());
+
         // Package the error message into a JSON response.
         let res = JsonError {
             message,
             request_id: state.short_request_id().to_string(),
+            code: reason.map(RateLimitReason::error_code),
+            retry_after_secs: reason
+                .and_then(RateLimitReason::retry_after)
+                .map(|d| d.as_secs()),
         };
 
         let body = serde_json::to_vec(&res).context("Failed to serialize error")?;
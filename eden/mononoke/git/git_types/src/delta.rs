@@ -9,6 +9,7 @@
 //! NOTE: We can represent Git objects as Deltas only if the size of the objects is less than 4GB
 
 use std::cmp::Ordering;
+use std::io::Write;
 use std::ops::Range;
 use std::str::FromStr;
 
@@ -17,19 +18,27 @@ use anyhow::Result;
 use blobstore::BlobstoreBytes;
 use bytes::Bytes;
 use fbthrift::compact_protocol;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use futures::stream;
+use futures::stream::BoxStream;
+use futures::StreamExt;
 use gix_diff::blob::diff;
 use gix_diff::blob::intern::InternedInput;
 use gix_diff::blob::intern::TokenSource;
 use gix_diff::blob::sink::Sink;
 use gix_diff::blob::Algorithm;
+use gix_hash::ObjectId;
 use mononoke_types::path::MPath;
 use mononoke_types::private::Blake2;
 use mononoke_types::private::MononokeTypeError;
 use mononoke_types::BlobstoreKey;
 use mononoke_types::ChangesetId;
+use packfile::types::PackfileItem;
 use tokio::io::AsyncWrite;
 use tokio::io::AsyncWriteExt;
 
+use crate::store::HeaderState;
 use crate::thrift;
 
 /// The maximum size of raw bytes that can be contained within a single
@@ -282,6 +291,91 @@ impl DeltaInstruction {
         out.write_all(&buffer).await?;
         Ok(())
     }
+
+    /// Decode a single instruction from the start of `data`, the inverse of
+    /// [`DeltaInstruction::write`]. Returns the instruction and how many leading bytes of `data`
+    /// it consumed, so the caller can advance past it and decode the next one.
+    ///
+    /// Unlike the test-only `apply` helper this crate also has, this validates as it goes: it
+    /// errors (rather than panicking or reading out of bounds) on a truncated instruction, and
+    /// rejects a leading command byte of `0`, which Git's format never assigns to either
+    /// instruction kind. It doesn't validate a `Copy`'s offset/size against the base object
+    /// that's actually being deltified - that needs the base object's size, which isn't available
+    /// per-instruction - see [`DeltaInstructions::parse`] for that check.
+    fn decode(data: &[u8]) -> Result<(Self, usize)> {
+        let cmd = *data
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("Encountered empty delta instruction stream"))?;
+        let mut pos = 1;
+        if cmd & COPY_INSTRUCTION != 0 {
+            let mut base_offset: u32 = 0;
+            for (bit, shift) in [(0u8, 0u32), (1, 8), (2, 16), (3, 24)] {
+                if cmd & (1 << bit) != 0 {
+                    let byte = *data.get(pos).ok_or_else(|| {
+                        anyhow::anyhow!("Truncated copy instruction: missing offset byte")
+                    })?;
+                    base_offset |= (byte as u32) << shift;
+                    pos += 1;
+                }
+            }
+            let mut size: u32 = 0;
+            for (bit, shift) in [(4u8, 0u32), (5, 8), (6, 16)] {
+                if cmd & (1 << bit) != 0 {
+                    let byte = *data.get(pos).ok_or_else(|| {
+                        anyhow::anyhow!("Truncated copy instruction: missing size byte")
+                    })?;
+                    size |= (byte as u32) << shift;
+                    pos += 1;
+                }
+            }
+            // Inverse of the `COPY_SPECIAL_SIZE` exception in `DeltaInstruction::write`: an
+            // all-zero size byte sequence actually means the special-cased 65536.
+            if size == 0 {
+                size = COPY_SPECIAL_SIZE;
+            }
+            Ok((DeltaInstruction::Copy { base_offset, size }, pos))
+        } else if cmd == 0 {
+            anyhow::bail!("Encountered unsupported delta instruction command code: 0");
+        } else {
+            let size = (cmd & DATA_BITMASK) as usize;
+            let data_end = pos
+                .checked_add(size)
+                .filter(|&end| end <= data.len())
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Truncated data instruction: expected {} bytes, only {} remain",
+                        size,
+                        data.len() - pos
+                    )
+                })?;
+            let bytes = Bytes::copy_from_slice(&data[pos..data_end]);
+            Ok((DeltaInstruction::Data(bytes), data_end))
+        }
+    }
+
+    /// Number of bytes this instruction will occupy once encoded, computed the same way as
+    /// [`DeltaInstruction::write`] without actually writing anything. Used to compare candidate
+    /// deltas by size without paying for a full encode of each one.
+    fn encoded_size(&self) -> usize {
+        match self {
+            DeltaInstruction::Data(bytes) => 1 + bytes.len(),
+            DeltaInstruction::Copy { base_offset, size } => {
+                let size = if *size == COPY_SPECIAL_SIZE {
+                    0u32
+                } else {
+                    *size
+                };
+                let non_zero_bytes = base_offset
+                    .to_le_bytes()
+                    .into_iter()
+                    .chain(size.to_le_bytes())
+                    .filter(|&byte| byte != 0)
+                    .count();
+                // Instruction byte plus the non-zero offset/size bytes that follow it.
+                1 + non_zero_bytes
+            }
+        }
+    }
 }
 
 impl std::fmt::Debug for DeltaInstruction {
@@ -307,35 +401,89 @@ pub enum ObjectKind {
     Target,
 }
 
+/// Result of [`DeltaInstructions::parse`]: the declared base/new object sizes read from the
+/// stream's header, and the ordered instructions to apply against a base object of
+/// `base_object_size` to produce `new_object_size` bytes. There's no `base_object`/`new_object`
+/// to carry alongside them the way [`DeltaInstructions`] does - those only exist on the encode
+/// side, where the delta was generated from real object bytes already in hand.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DecodedDeltaInstructions {
+    pub base_object_size: u64,
+    pub new_object_size: u64,
+    pub instructions: Vec<DeltaInstruction>,
+}
+
+impl DecodedDeltaInstructions {
+    /// Apply these instructions to `base_object`, writing the resulting object bytes to `out`.
+    /// Unlike [`DeltaInstructions::apply`], the base object has to be supplied separately here -
+    /// a `DecodedDeltaInstructions` doesn't carry one (see its doc comment) since it comes from
+    /// [`DeltaInstructions::parse`]-ing a byte stream that never included the base object itself.
+    pub async fn apply(
+        &self,
+        base_object: &[u8],
+        out: &mut (impl AsyncWrite + Unpin),
+    ) -> Result<()> {
+        if base_object.len() as u64 != self.base_object_size {
+            anyhow::bail!(
+                "Base object is {} bytes but delta instructions were decoded against a base object of size {}",
+                base_object.len(),
+                self.base_object_size,
+            );
+        }
+        apply_instructions(base_object, &self.instructions, out).await
+    }
+}
+
 /// List of instructions which when applied in order form a
 /// complete new object based on delta of a base object
 #[derive(Clone, Hash, Eq, PartialEq)]
 pub struct DeltaInstructions {
     base_object: Bytes,
-    base_object_chunk_size: usize,
+    // Byte offset of the start of each token (chunk or line - see `ObjectData`) `base_object` was
+    // tokenized into for diffing, plus a final entry for `base_object`'s length. Lets
+    // `object_byte_range` turn a token-index range from the diff algorithm back into a byte range
+    // without assuming every token is the same size, which a line tokenizer's tokens aren't.
+    base_object_token_offsets: Vec<u32>,
     new_object: Bytes,
-    new_object_chunk_size: usize,
+    new_object_token_offsets: Vec<u32>,
+    /// Whether `base_object` and `new_object` include the null-terminated loose-object header
+    /// or are raw content. Both objects are always diffed under the same convention, and this
+    /// is recorded so that consumers (e.g. packfile encoding) can validate the delta was built
+    /// over the representation they expect, instead of silently mixing conventions.
+    header_state: HeaderState,
     processed_till: u32, // To keep track of the byte position till which the delta has been processed
     instructions: Vec<DeltaInstruction>,
 }
 
 impl DeltaInstructions {
     // Generate set of DeltaInstructions for the given base and new object by diffing them
-    // using the provided diff algorithm
+    // using the provided diff algorithm. `header_state` records whether `base_object` and
+    // `new_object` include the loose-object header, so callers that later encode or apply
+    // these instructions can validate they're using the representation they were generated
+    // against.
     pub fn generate(
         base_object: Bytes,
         new_object: Bytes,
         diff_algorithm: Algorithm,
+        header_state: HeaderState,
     ) -> Result<Self> {
+        // Copy instruction offsets/sizes are encoded as u32 (see `DeltaInstruction::Copy`), so an
+        // object on either side of the delta beyond this size can't be addressed at all - without
+        // this check `len() as u32` elsewhere in this module would silently truncate instead.
+        // Callers needing to handle arbitrarily large objects should fall back to storing them in
+        // full (non-deltified) rather than calling `generate` on them.
+        validate_deltifiable_size(base_object.len(), "base")?;
+        validate_deltifiable_size(new_object.len(), "new")?;
         let base_object_vec = base_object.to_vec();
         let new_object_vec = new_object.to_vec();
         let tokened_base_object = ObjectData::new(&base_object_vec);
         let tokened_new_object = ObjectData::new(&new_object_vec);
         let delta_instructions = Self {
+            base_object_token_offsets: tokened_base_object.token_offsets().to_vec(),
             base_object,
+            new_object_token_offsets: tokened_new_object.token_offsets().to_vec(),
             new_object,
-            base_object_chunk_size: tokened_base_object.chunk_size(),
-            new_object_chunk_size: tokened_new_object.chunk_size(),
+            header_state,
             instructions: Vec::new(),
             processed_till: 0,
         };
@@ -360,23 +508,424 @@ impl DeltaInstructions {
         Ok(())
     }
 
-    /// Given the chunk-based range in the base or target object, return the equivalent
-    /// byte level range by multiplying the offset
+    /// Decode a delta instruction stream as produced by [`DeltaInstructions::write`], the inverse
+    /// operation. Needed for ingesting packs received over push, where the delta bytes come from
+    /// an untrusted client rather than our own `write` - unlike the test-only `apply` function
+    /// elsewhere in this module, this doesn't just panic on malformed input: every `Copy`
+    /// instruction's `base_offset..base_offset + size` is checked against the declared base
+    /// object size, and the total bytes the instructions produce is checked against the declared
+    /// new object size, before any of it is trusted by a caller.
+    pub fn parse(data: &[u8]) -> Result<DecodedDeltaInstructions> {
+        let (base_object_size, consumed) = read_size(data)
+            .context("Failed to decode base object size from delta instruction stream")?;
+        let data = &data[consumed..];
+        let (new_object_size, consumed) = read_size(data)
+            .context("Failed to decode new object size from delta instruction stream")?;
+        let mut data = &data[consumed..];
+
+        let mut instructions = Vec::new();
+        let mut produced_size: u64 = 0;
+        while !data.is_empty() {
+            let (instruction, consumed) = DeltaInstruction::decode(data)?;
+            data = &data[consumed..];
+            match &instruction {
+                DeltaInstruction::Data(bytes) => produced_size += bytes.len() as u64,
+                DeltaInstruction::Copy { base_offset, size } => {
+                    let range_end = (*base_offset as u64) + (*size as u64);
+                    if range_end > base_object_size {
+                        anyhow::bail!(
+                            "Copy instruction references range {}..{} outside base object of size {}",
+                            base_offset,
+                            range_end,
+                            base_object_size,
+                        );
+                    }
+                    produced_size += *size as u64;
+                }
+            }
+            instructions.push(instruction);
+        }
+
+        if produced_size != new_object_size {
+            anyhow::bail!(
+                "Delta instructions produce {} bytes but declared new object size is {}",
+                produced_size,
+                new_object_size,
+            );
+        }
+
+        Ok(DecodedDeltaInstructions {
+            base_object_size,
+            new_object_size,
+            instructions,
+        })
+    }
+
+    /// Apply these instructions to `self.base_object`, writing the resulting object bytes to
+    /// `out`. This is the production counterpart to the test-only `apply` free function in this
+    /// module's tests: it validates as it goes and returns an error instead of panicking, so it's
+    /// safe to use from the packfile read path and other jobs applying deltas built or decoded
+    /// from untrusted input.
+    pub async fn apply(&self, out: &mut (impl AsyncWrite + Unpin)) -> Result<()> {
+        apply_instructions(&self.base_object, &self.instructions, out).await
+    }
+
+    /// Given the token-index range (chunk or line index, depending on which tokenization mode
+    /// [`ObjectData`] picked for this object) in the base or target object, return the equivalent
+    /// byte level range.
     pub fn object_byte_range(&self, range: Range<u32>, kind: ObjectKind) -> Range<u32> {
-        let (chunk_size, object_len) = match kind {
-            ObjectKind::Base => (
-                self.base_object_chunk_size as u32,
-                self.base_object.len() as u32,
-            ),
-            ObjectKind::Target => (
-                self.new_object_chunk_size as u32,
-                self.new_object.len() as u32,
-            ),
+        let token_offsets = match kind {
+            ObjectKind::Base => &self.base_object_token_offsets,
+            ObjectKind::Target => &self.new_object_token_offsets,
         };
-        let range_start = std::cmp::min(range.start * chunk_size, object_len);
-        let range_end = std::cmp::min(range.end * chunk_size, object_len);
-        range_start..range_end
+        token_range_to_byte_range(range, token_offsets)
+    }
+
+    /// Whether `base_object` and `new_object` were deltified including their loose-object
+    /// header, or as raw content.
+    pub fn header_state(&self) -> &HeaderState {
+        &self.header_state
+    }
+
+    /// Encode and Zlib-compress this delta, and wrap it as a [`PackfileItem`] so it can be
+    /// handed directly to `packfile::pack::PackfileWriter` without the caller needing to know
+    /// the on-disk delta encoding.
+    pub async fn into_packfile_item(&self, oid: ObjectId, base_oid: ObjectId) -> Result<PackfileItem> {
+        // Packfile delta entries are applied against the base object's raw content: the pack
+        // format encodes each object's type and size in its own entry header, so a delta
+        // deltified over loose-object-header-prefixed bytes would silently corrupt the
+        // resulting pack.
+        if self.header_state != HeaderState::Excluded {
+            anyhow::bail!(
+                "Cannot encode a packfile delta item from DeltaInstructions generated over \
+                 header-included bytes; regenerate with HeaderState::Excluded"
+            );
+        }
+        let mut encoded = Vec::new();
+        self.write(&mut encoded).await?;
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(&encoded)
+            .context("Failure in writing delta instructions to ZLib buffer")?;
+        let compressed = encoder
+            .finish()
+            .context("Failure in ZLib encoding delta instructions")?;
+        Ok(PackfileItem::new_delta(
+            oid,
+            base_oid,
+            encoded.len() as u64,
+            Bytes::from(compressed),
+        ))
+    }
+
+}
+
+/// Bounds on how much work [`generate_best_delta`] will do trying out candidate bases.
+#[derive(Clone, Debug)]
+pub struct DeltaSelectionConfig {
+    /// Diff algorithm to use when deltifying against each candidate.
+    pub diff_algorithm: Algorithm,
+    /// `new_object` and every candidate must share this convention (see [`HeaderState`]).
+    pub header_state: HeaderState,
+    /// Skip any candidate base larger than this many bytes. Deltifying against a much bigger
+    /// base rarely wins, and the diff itself only gets more expensive as the base grows.
+    pub max_candidate_size: u64,
+    /// Stop trying further candidates once this many have been evaluated (after skips), so a
+    /// long candidate list can't make selection arbitrarily expensive.
+    pub max_candidates_tried: usize,
+}
+
+/// The winning result of [`generate_best_delta`].
+#[derive(Debug)]
+pub struct BestDelta {
+    /// The smallest delta found among the candidates that were tried.
+    pub instructions: DeltaInstructions,
+    /// Index into the `candidates` passed to [`generate_best_delta`] of the base object that
+    /// produced [`BestDelta::instructions`].
+    pub winning_candidate: usize,
+}
+
+/// Estimate the encoded size (in the same units as [`DeltaInstruction::encoded_size`]) that
+/// diffing `base_object` against `new_object` would produce, without materializing any
+/// [`DeltaInstruction`]s or copying either object's bytes into a [`DeltaInstructions`]. Used by
+/// [`generate_best_delta`] to cheaply rank candidate bases before paying to fully encode the
+/// winner.
+struct DeltaSizeEstimate {
+    base_object_token_offsets: Vec<u32>,
+    base_object_len: u32,
+    new_object_token_offsets: Vec<u32>,
+    processed_till: u32,
+    encoded_size: usize,
+    error: Option<anyhow::Error>,
+}
+
+impl DeltaSizeEstimate {
+    fn add_copy(&mut self, range: Range<u32>) {
+        if self.error.is_some() {
+            return;
+        }
+        match DeltaInstruction::from_copy(range.clone()) {
+            Ok(instruction) => {
+                self.encoded_size += instruction.encoded_size();
+                self.processed_till = range.end;
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    fn add_data(&mut self, range: Range<u32>) {
+        if self.error.is_some() {
+            return;
+        }
+        let len = (range.end - range.start) as usize;
+        if len > MAX_DATA_BYTES {
+            self.error = Some(anyhow::anyhow!(
+                "Data instruction of size {} exceeds the max size of {} bytes",
+                len,
+                MAX_DATA_BYTES
+            ));
+            return;
+        }
+        self.encoded_size += 1 + len;
+    }
+}
+
+// Mirrors `impl Sink for FallibleDeltaInstructions` below, with the instruction list replaced by a
+// running byte count - see that impl for the reasoning behind the chunked Copy/Data splitting.
+impl Sink for DeltaSizeEstimate {
+    type Out = Result<usize>;
+
+    fn process_change(&mut self, before: Range<u32>, after: Range<u32>) {
+        let before = token_range_to_byte_range(before, &self.base_object_token_offsets);
+        let after = token_range_to_byte_range(after, &self.new_object_token_offsets);
+        let processed_till = self.processed_till;
+        match before.start.cmp(&processed_till) {
+            Ordering::Less => {
+                self.error = Some(anyhow::anyhow!(
+                    "Encountered invalid processed range {:?} while diffing content",
+                    before
+                ));
+                return;
+            }
+            Ordering::Equal => {}
+            Ordering::Greater => {
+                let range_start = before.start;
+                let mut copied_till = processed_till;
+                for subrange_start in (processed_till..range_start).step_by(MAX_COPY_BYTES as usize)
+                {
+                    copied_till =
+                        std::cmp::min(range_start, subrange_start.saturating_add(MAX_COPY_BYTES));
+                    self.add_copy(subrange_start..copied_till);
+                }
+                if copied_till < range_start {
+                    self.add_copy(copied_till..range_start);
+                }
+            }
+        }
+        let range_start = after.start;
+        let mut written_till = range_start;
+        for subrange_start in after.clone().step_by(MAX_DATA_BYTES) {
+            written_till = std::cmp::min(after.end, subrange_start.saturating_add(MAX_DATA_BYTES as u32));
+            self.add_data(subrange_start..written_till);
+        }
+        if written_till < after.end {
+            self.add_data(written_till..after.end);
+        }
+        self.processed_till = before.end;
+    }
+
+    fn finish(mut self) -> Self::Out {
+        let base_obj_len = self.base_object_len;
+        let processed_till = self.processed_till;
+        match base_obj_len.cmp(&processed_till) {
+            Ordering::Less => anyhow::bail!(
+                "Processed till position {} which is greater than base object size {}",
+                processed_till,
+                base_obj_len
+            ),
+            Ordering::Equal => {}
+            Ordering::Greater => {
+                let mut copied_till = processed_till;
+                for subrange_start in (processed_till..base_obj_len).step_by(MAX_COPY_BYTES as usize)
+                {
+                    copied_till =
+                        std::cmp::min(base_obj_len, subrange_start.saturating_add(MAX_COPY_BYTES));
+                    self.add_copy(subrange_start..copied_till);
+                }
+                if copied_till < base_obj_len {
+                    self.add_copy(copied_till..base_obj_len);
+                }
+            }
+        }
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        Ok(self.encoded_size)
+    }
+}
+
+/// Estimate the encoded delta size (see [`DeltaInstruction::encoded_size`]) that would result
+/// from deltifying `new_object` against `base_object`, without building the `Vec<DeltaInstruction>`
+/// that [`DeltaInstructions::generate`] would. Useful for base-selection or "is deltifying worth
+/// it" decisions on large objects, where materializing instructions for every candidate would be
+/// wasteful.
+pub fn estimate_delta_size(
+    base_object: &[u8],
+    new_object: &[u8],
+    diff_algorithm: Algorithm,
+) -> Result<usize> {
+    validate_deltifiable_size(base_object.len(), "base")?;
+    validate_deltifiable_size(new_object.len(), "new")?;
+    let base_object_vec = base_object.to_vec();
+    let new_object_vec = new_object.to_vec();
+    let tokened_base_object = ObjectData::new(&base_object_vec);
+    let tokened_new_object = ObjectData::new(&new_object_vec);
+    let estimate = DeltaSizeEstimate {
+        base_object_token_offsets: tokened_base_object.token_offsets().to_vec(),
+        base_object_len: base_object.len() as u32,
+        new_object_token_offsets: tokened_new_object.token_offsets().to_vec(),
+        processed_till: 0,
+        encoded_size: 0,
+        error: None,
+    };
+    let interned_input = InternedInput::new(tokened_base_object, tokened_new_object);
+    diff(diff_algorithm, &interned_input, estimate)
+}
+
+/// Deltify `new_object` against each of `candidates` in turn, bounded by `config`'s size/count
+/// budget, and return the smallest resulting delta along with which candidate produced it. This
+/// gives the packing layer git-quality base selection among several historical candidates
+/// without having to re-implement the comparison loop itself. Returns `Ok(None)` if every
+/// candidate was skipped, e.g. because all of them exceeded `config.max_candidate_size`.
+pub fn generate_best_delta(
+    new_object: Bytes,
+    candidates: Vec<Bytes>,
+    config: &DeltaSelectionConfig,
+) -> Result<Option<BestDelta>> {
+    let mut best_candidate: Option<(usize, Bytes, usize)> = None;
+    let mut candidates_tried = 0;
+    for (candidate_index, candidate) in candidates.into_iter().enumerate() {
+        if candidates_tried >= config.max_candidates_tried {
+            break;
+        }
+        if candidate.len() as u64 > config.max_candidate_size {
+            continue;
+        }
+        candidates_tried += 1;
+        let estimated_size =
+            estimate_delta_size(&candidate, &new_object, config.diff_algorithm)?;
+        let is_better = best_candidate
+            .as_ref()
+            .map_or(true, |(_, _, current_size)| estimated_size < *current_size);
+        if is_better {
+            best_candidate = Some((candidate_index, candidate, estimated_size));
+        }
     }
+    let Some((winning_candidate, candidate, _)) = best_candidate else {
+        return Ok(None);
+    };
+    let instructions = DeltaInstructions::generate(
+        candidate,
+        new_object,
+        config.diff_algorithm,
+        config.header_state.clone(),
+    )?;
+    Ok(Some(BestDelta {
+        instructions,
+        winning_candidate,
+    }))
+}
+
+/// Alias for [`generate_best_delta`] for callers thinking in terms of "which base object should I
+/// use" rather than "generate me a delta" - e.g. a packer deciding between several historical
+/// versions of the same path before it has any need for the resulting [`DeltaInstructions`]
+/// themselves. Behaves identically; see [`generate_best_delta`] for the full contract.
+pub fn select_best_base(
+    target: Bytes,
+    candidates: Vec<Bytes>,
+    config: &DeltaSelectionConfig,
+) -> Result<Option<BestDelta>> {
+    generate_best_delta(target, candidates, config)
+}
+
+/// Compute deltas for many (base, target) pairs concurrently, bounded by at most `concurrency`
+/// diffs in flight at a time, returning a stream of results in the same order as `pairs`. Pack
+/// generation otherwise runs one CPU-bound Myers (or other algorithm) diff at a time on whatever
+/// task calls [`DeltaInstructions::generate`]; spreading the work across `spawn_blocking` workers
+/// lets a multi-core packer keep several diffs in flight instead of serializing them all on a
+/// single task.
+pub fn generate_deltas_concurrently(
+    pairs: Vec<(Bytes, Bytes)>,
+    diff_algorithm: Algorithm,
+    header_state: HeaderState,
+    concurrency: usize,
+) -> BoxStream<'static, Result<DeltaInstructions>> {
+    stream::iter(pairs)
+        .map(move |(base_object, new_object)| {
+            let header_state = header_state.clone();
+            async move {
+                tokio::task::spawn_blocking(move || {
+                    DeltaInstructions::generate(
+                        base_object,
+                        new_object,
+                        diff_algorithm,
+                        header_state,
+                    )
+                })
+                .await
+                .context("Delta generation worker task panicked or was cancelled")?
+            }
+        })
+        .buffered(concurrency)
+        .boxed()
+}
+
+/// How a deltified pack entry addresses its base object: by how many bytes earlier in the same
+/// pack stream it begins (OFS_DELTA) or by its full object id (REF_DELTA). See
+/// https://git-scm.com/docs/pack-format#_deltified_representation.
+///
+/// The actual byte distance for [`DeltaBaseRef::Offset`] is only known once both objects have
+/// been placed in the output stream, so this is produced by the packfile writer at write time
+/// rather than alongside [`DeltaInstructions`] themselves; [`DeltaBaseRef::write_header`] is the
+/// shared place that turns either addressing scheme into the bytes the pack format expects.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DeltaBaseRef {
+    /// `this_object_offset - base_object_offset`, i.e. how many bytes earlier in the pack the
+    /// base object begins.
+    Offset(u64),
+    /// The SHA1 of the base object.
+    Oid(ObjectId),
+}
+
+impl DeltaBaseRef {
+    /// Encode the delta-base portion of a pack entry's header - the part that follows the
+    /// type+size header and precedes the zlib-compressed delta instructions - appending it to
+    /// `out`. An [`DeltaBaseRef::Offset`] is written as Git's "negative offset" varint; a
+    /// [`DeltaBaseRef::Oid`] is written as the raw 20-byte SHA1.
+    pub fn write_header(&self, out: &mut Vec<u8>) {
+        match self {
+            DeltaBaseRef::Offset(distance) => write_ofs_delta_offset(*distance, out),
+            DeltaBaseRef::Oid(oid) => out.extend_from_slice(oid.as_bytes()),
+        }
+    }
+}
+
+/// Encode the Git "negative offset" varint used by an OFS_DELTA pack entry to identify its base
+/// object's position. Unlike the plain 7-bit/byte varint used for object sizes (see
+/// [`write_size`]), each continuation byte folds in a "+1" to avoid two different encodings of
+/// the same distance - mirrors the decode loop that reads this back, found alongside the rest of
+/// the incoming-pack parser in `packfile::unpack::read_ofs_delta_offset`.
+fn write_ofs_delta_offset(distance: u64, out: &mut Vec<u8>) {
+    let mut remaining = distance;
+    let mut bytes = vec![(remaining & 0x7f) as u8];
+    remaining >>= 7;
+    while remaining != 0 {
+        remaining -= 1;
+        bytes.push(0x80 | (remaining & 0x7f) as u8);
+        remaining >>= 7;
+    }
+    bytes.reverse();
+    out.extend_from_slice(&bytes);
 }
 
 impl std::fmt::Debug for DeltaInstructions {
@@ -473,9 +1022,9 @@ impl Sink for FallibleDeltaInstructions {
     fn process_change(&mut self, before: Range<u32>, after: Range<u32>) {
         match self {
             Self::Valid(delta_instructions) => {
-                // The before and after ranges are essentially chunk indices where each
-                // chunk can be `chunk_size` bytes long. To get the actual byte level index,
-                // we need to multiply the `chunk_size` with the chunk index
+                // The before and after ranges are token indices (chunks or, for text objects,
+                // whole lines - see `ObjectData`), not byte offsets. `object_byte_range`
+                // translates them back to the byte ranges we actually need here.
                 let before = delta_instructions.object_byte_range(before, ObjectKind::Base);
                 let after = delta_instructions.object_byte_range(after, ObjectKind::Target);
                 let processed_till = delta_instructions.processed_till.clone();
@@ -589,40 +1138,161 @@ impl Sink for FallibleDeltaInstructions {
     }
 }
 
+/// How many leading bytes of an object we sniff to decide [`TokenizationMode`] - matches the
+/// prefix size Git itself inspects to decide whether a blob is text or binary.
+const BINARY_SNIFF_LEN: usize = 8000;
+
+/// Whether [`ObjectData`] should tokenize an object by fixed-size byte chunks or by line. Chunked
+/// tokenization is the only mode that makes sense for binary content, where "line" has no
+/// meaning; line tokenization lets text objects diff in O(lines) instead of O(bytes / chunk_size)
+/// and produces Copy instructions that track the file's actual line structure rather than
+/// arbitrary chunk boundaries.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum TokenizationMode {
+    Chunked,
+    Lines,
+}
+
+/// Sniff whether `data` looks like text or binary content, the same way Git itself does: a NUL
+/// byte anywhere in the first [`BINARY_SNIFF_LEN`] bytes marks it as binary.
+fn sniff_tokenization_mode(data: &[u8]) -> TokenizationMode {
+    let sniff_len = std::cmp::min(data.len(), BINARY_SNIFF_LEN);
+    if data[..sniff_len].contains(&0) {
+        TokenizationMode::Chunked
+    } else {
+        TokenizationMode::Lines
+    }
+}
+
+fn is_newline(byte: &u8) -> bool {
+    *byte == b'\n'
+}
+
+/// Byte offset of the start of each token `data` is split into under `mode`, plus a final entry
+/// for `data.len()`. Shared between [`ObjectData`] (which needs the token count up front to
+/// implement [`TokenSource::estimate_tokens`]) and [`DeltaInstructions`]/[`DeltaSizeEstimate`]
+/// (which need to turn a token-index range from the diff algorithm back into a byte range).
+fn compute_token_offsets(data: &[u8], mode: TokenizationMode) -> Vec<u32> {
+    match mode {
+        TokenizationMode::Chunked => {
+            let chunk_size = std::cmp::max(data.len() / DELTA_CHUNK_COUNT, 1);
+            let mut offsets: Vec<u32> = (0..data.len())
+                .step_by(chunk_size)
+                .map(|offset| offset as u32)
+                .collect();
+            offsets.push(data.len() as u32);
+            offsets
+        }
+        TokenizationMode::Lines => {
+            let mut offsets = vec![0u32];
+            let mut pos = 0u32;
+            for line in data.split_inclusive(is_newline as fn(&u8) -> bool) {
+                pos += line.len() as u32;
+                offsets.push(pos);
+            }
+            offsets
+        }
+    }
+}
+
+/// Turn a token-index range (as produced by the diff algorithm over an [`ObjectData`]) into the
+/// byte range it covers, using the token boundaries computed by [`compute_token_offsets`]. Works
+/// regardless of tokenization mode, since unlike chunk size, line length isn't uniform.
+fn token_range_to_byte_range(range: Range<u32>, token_offsets: &[u32]) -> Range<u32> {
+    let last = *token_offsets.last().unwrap_or(&0);
+    let start = token_offsets
+        .get(range.start as usize)
+        .copied()
+        .unwrap_or(last);
+    let end = token_offsets.get(range.end as usize).copied().unwrap_or(last);
+    start..end
+}
+
+/// Iterator over an [`ObjectData`]'s tokens - either fixed-size byte chunks or whole lines,
+/// depending on [`TokenizationMode`].
+enum ObjectTokens<'a> {
+    Chunks(std::slice::Chunks<'a, u8>),
+    Lines(std::slice::SplitInclusive<'a, u8, fn(&u8) -> bool>),
+}
+
+impl<'a> Iterator for ObjectTokens<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            ObjectTokens::Chunks(tokens) => tokens.next(),
+            ObjectTokens::Lines(tokens) => tokens.next(),
+        }
+    }
+}
+
 /// Wrapper type over the bytes representing the data of the Git Object, used
 /// for bypassing the orphan rule for implementing the TokenSource trait
 struct ObjectData<'a> {
     data: &'a Vec<u8>,
-    chunk_size: usize,
+    mode: TokenizationMode,
+    token_offsets: Vec<u32>,
 }
 
 impl<'a> ObjectData<'a> {
     pub fn new(data: &'a Vec<u8>) -> Self {
-        let chunk_size = std::cmp::max(data.len() / DELTA_CHUNK_COUNT, 1);
-        Self { data, chunk_size }
+        let mode = sniff_tokenization_mode(data);
+        let token_offsets = compute_token_offsets(data, mode);
+        Self {
+            data,
+            mode,
+            token_offsets,
+        }
     }
 
-    pub fn chunk_size(&self) -> usize {
-        self.chunk_size
+    /// Byte offset of the start of each token, plus a final entry for `data.len()` - see
+    /// [`compute_token_offsets`].
+    pub fn token_offsets(&self) -> &[u32] {
+        &self.token_offsets
     }
 }
 
 impl<'a> TokenSource for ObjectData<'a> {
-    // Depending upon the input, the granularity could be individual bytes (for file less than 100KB)
-    // or chunks of bytes (for large files)
+    // Depending upon the input, the granularity could be individual bytes (for file less than
+    // 100KB), chunks of bytes (for large binary files), or whole lines (for text files)
     type Token = &'a [u8];
 
-    type Tokenizer = std::slice::Chunks<'a, u8>;
+    type Tokenizer = ObjectTokens<'a>;
 
     fn tokenize(&self) -> Self::Tokenizer {
-        self.data.chunks(self.chunk_size)
+        match self.mode {
+            TokenizationMode::Chunked => {
+                let chunk_size = std::cmp::max(self.data.len() / DELTA_CHUNK_COUNT, 1);
+                ObjectTokens::Chunks(self.data.chunks(chunk_size))
+            }
+            TokenizationMode::Lines => {
+                ObjectTokens::Lines(self.data.split_inclusive(is_newline as fn(&u8) -> bool))
+            }
+        }
     }
 
     fn estimate_tokens(&self) -> u32 {
-        (self.data.len() / self.chunk_size) as u32
+        (self.token_offsets.len() - 1) as u32
     }
 }
 
+/// Check that an object of `len` bytes can actually be addressed by a delta's u32 offsets/sizes,
+/// erroring out (naming which side of the delta it is, for the caller's error message) rather
+/// than letting a `len() as u32` elsewhere in this module silently truncate it. Pulled out as its
+/// own function so the 4GB boundary can be exercised in tests without allocating a real 4GB buffer.
+fn validate_deltifiable_size(len: usize, role: &str) -> Result<()> {
+    if len > u32::MAX as usize {
+        anyhow::bail!(
+            "Cannot generate a delta: {} object is {} bytes, exceeding the {} byte limit \
+             representable by a delta's u32 offsets/sizes",
+            role,
+            len,
+            u32::MAX,
+        );
+    }
+    Ok(())
+}
+
 /// Write the size "size" using the size encoding scheme used by Git
 /// The encoding scheme is one of variable length where the bytes are written
 /// in little-endian order. Only the lower 7 bits of each byte are used to represent
@@ -652,6 +1322,63 @@ async fn write_size(size_to_write: usize, out: &mut (impl AsyncWrite + Unpin)) -
     Ok(())
 }
 
+/// Every continuation byte of the varint contributes 7 bits to a `u64` accumulator, so 10 bytes
+/// (70 bits) is already more than enough to hold any value that fits in a `u64` - a varint that
+/// hasn't terminated by then is corrupt input, not merely a large size.
+const MAX_SIZE_VARINT_BYTES: usize = 10;
+
+/// Inverse of [`write_size`]: decode a size written in Git's variable-length, 7-bits-per-byte,
+/// high-bit-continuation encoding off the front of `data`. Returns the decoded size and how many
+/// leading bytes of `data` it consumed. Errors instead of panicking if `data` runs out before a
+/// byte without the continuation bit is seen, or if the varint runs past `MAX_SIZE_VARINT_BYTES`
+/// without terminating (which would otherwise shift `size` past bit 63).
+fn read_size(data: &[u8]) -> Result<(u64, usize)> {
+    let mut size: u64 = 0;
+    for (consumed, &byte) in data.iter().enumerate() {
+        if consumed >= MAX_SIZE_VARINT_BYTES {
+            anyhow::bail!("Corrupt delta instruction stream: size varint is too long");
+        }
+        size |= ((byte & DATA_BITMASK) as u64) << (7 * consumed);
+        if byte & CONTINUATION_BITMASK == 0 {
+            return Ok((size, consumed + 1));
+        }
+    }
+    anyhow::bail!("Truncated delta instruction stream: size varint never terminated")
+}
+
+/// Shared implementation behind [`DeltaInstructions::apply`] and
+/// [`DecodedDeltaInstructions::apply`]: write the object produced by applying `instructions` to
+/// `base_object` to `out`. Every `Copy`'s range is checked against `base_object` before being
+/// read, rather than trusted outright the way the test-only `apply` free function is.
+async fn apply_instructions(
+    base_object: &[u8],
+    instructions: &[DeltaInstruction],
+    out: &mut (impl AsyncWrite + Unpin),
+) -> Result<()> {
+    for instruction in instructions {
+        match instruction {
+            DeltaInstruction::Data(bytes) => {
+                out.write_all(bytes).await?;
+            }
+            DeltaInstruction::Copy { base_offset, size } => {
+                let start = *base_offset as u64;
+                let end = start + *size as u64;
+                if end > base_object.len() as u64 {
+                    anyhow::bail!(
+                        "Copy instruction references range {}..{} outside base object of size {}",
+                        start,
+                        end,
+                        base_object.len(),
+                    );
+                }
+                out.write_all(&base_object[start as usize..end as usize])
+                    .await?;
+            }
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use std::io::Write;
@@ -672,6 +1399,7 @@ mod test {
     use memblob::Memblob;
     use mononoke_types_mocks::changesetid::ONES_CSID;
     use mononoke_types_mocks::changesetid::TWOS_CSID;
+    use quickcheck::quickcheck;
     use rand::Rng;
 
     use super::*;
@@ -768,7 +1496,12 @@ mod test {
             .collect();
         let new_object = Bytes::from(new_object);
         let delta_instructions =
-            DeltaInstructions::generate(base_object.clone(), new_object.clone(), Algorithm::Myers)?;
+            DeltaInstructions::generate(
+                base_object.clone(),
+                new_object.clone(),
+                Algorithm::Myers,
+                HeaderState::Excluded,
+            )?;
         let mut encoded_instructions = Vec::new();
         delta_instructions
             .write_instructions(&mut encoded_instructions)
@@ -819,7 +1552,12 @@ mod test {
         let base_object = Bytes::from_static(b"So close no matter how far");
         let new_object = Bytes::from_static(b"So close no matter if very far");
         let delta_instructions =
-            DeltaInstructions::generate(base_object, new_object, Algorithm::Myers);
+            DeltaInstructions::generate(
+                base_object,
+                new_object,
+                Algorithm::Myers,
+                HeaderState::Excluded,
+            );
         // Validate that the delta instructions get created successfully
         assert!(
             delta_instructions.is_ok(),
@@ -828,12 +1566,91 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_generate_best_delta_picks_smallest() -> Result<()> {
+        let new_object = Bytes::from_static(b"So close no matter how far");
+        // A candidate that barely differs from new_object should produce a much smaller delta
+        // than one that shares almost nothing with it.
+        let close_candidate = Bytes::from_static(b"So close no matter how near");
+        let far_candidate = Bytes::from_static(b"Nothing here resembles the target at all");
+        let config = DeltaSelectionConfig {
+            diff_algorithm: Algorithm::Myers,
+            header_state: HeaderState::Excluded,
+            max_candidate_size: u64::MAX,
+            max_candidates_tried: 10,
+        };
+        let best = generate_best_delta(
+            new_object,
+            vec![far_candidate, close_candidate],
+            &config,
+        )?
+        .ok_or_else(|| anyhow::anyhow!("Expected a winning candidate"))?;
+        assert_eq!(best.winning_candidate, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_best_delta_respects_candidate_size_limit() -> Result<()> {
+        let new_object = Bytes::from_static(b"So close no matter how far");
+        let oversized_candidate = Bytes::from_static(b"So close no matter how near");
+        let config = DeltaSelectionConfig {
+            diff_algorithm: Algorithm::Myers,
+            header_state: HeaderState::Excluded,
+            max_candidate_size: oversized_candidate.len() as u64 - 1,
+            max_candidates_tried: 10,
+        };
+        let best = generate_best_delta(new_object, vec![oversized_candidate], &config)?;
+        assert!(
+            best.is_none(),
+            "Candidate exceeding max_candidate_size should have been skipped",
+        );
+        Ok(())
+    }
+
+    #[fbinit::test]
+    async fn test_generate_deltas_concurrently_preserves_order() -> Result<()> {
+        let pairs: Vec<(Bytes, Bytes)> = (0..10)
+            .map(|i| {
+                (
+                    Bytes::from(format!("base object number {}", i)),
+                    Bytes::from(format!("new object number {}", i)),
+                )
+            })
+            .collect();
+        let results: Vec<DeltaInstructions> = generate_deltas_concurrently(
+            pairs.clone(),
+            Algorithm::Myers,
+            HeaderState::Excluded,
+            4,
+        )
+        .try_collect()
+        .await?;
+
+        assert_eq!(results.len(), pairs.len());
+        for (i, (instructions, (base_object, new_object))) in
+            results.into_iter().zip(pairs.into_iter()).enumerate()
+        {
+            assert_eq!(
+                instructions.base_object, base_object,
+                "Result at index {} should correspond to the pair at the same index",
+                i
+            );
+            assert_eq!(instructions.new_object, new_object);
+        }
+        Ok(())
+    }
+
     #[fbinit::test]
     async fn test_basic_delta_encoding() -> Result<()> {
         let base_object = Bytes::from_static(b"So close no matter how far");
         let new_object = Bytes::from_static(b"So close no matter if very far");
         let delta_instructions =
-            DeltaInstructions::generate(base_object, new_object, Algorithm::Myers)?;
+            DeltaInstructions::generate(
+                base_object,
+                new_object,
+                Algorithm::Myers,
+                HeaderState::Excluded,
+            )?;
         let mut encoded_instructions = Vec::new();
         let result = delta_instructions.write(&mut encoded_instructions).await;
         assert!(result.is_ok(), "Failure in encoding delta instructions");
@@ -845,7 +1662,12 @@ mod test {
         let base_object = Bytes::from_static(b"So close no matter how far");
         let new_object = Bytes::from_static(b"So close no matter if very far");
         let delta_instructions =
-            DeltaInstructions::generate(base_object.clone(), new_object.clone(), Algorithm::Myers)?;
+            DeltaInstructions::generate(
+                base_object.clone(),
+                new_object.clone(),
+                Algorithm::Myers,
+                HeaderState::Excluded,
+            )?;
         let mut encoded_instructions = Vec::new();
         delta_instructions
             .write_instructions(&mut encoded_instructions)
@@ -880,6 +1702,7 @@ mod test {
             base_object.clone(),
             target_object.clone(),
             Algorithm::Myers,
+            HeaderState::Excluded,
         )?;
 
         let mut encoded_instructions = Vec::new();
@@ -914,7 +1737,12 @@ mod test {
         let new_bytes = Bytes::from(new_object.into_bytes());
 
         let delta_instructions =
-            DeltaInstructions::generate(base_bytes.clone(), new_bytes.clone(), Algorithm::Myers)?;
+            DeltaInstructions::generate(
+                base_bytes.clone(),
+                new_bytes.clone(),
+                Algorithm::Myers,
+                HeaderState::Excluded,
+            )?;
         let mut encoded_instructions = Vec::new();
         delta_instructions
             .write_instructions(&mut encoded_instructions)
@@ -960,7 +1788,12 @@ mod test {
             .collect();
         let new_object = Bytes::from(new_object);
         let delta_instructions =
-            DeltaInstructions::generate(base_object.clone(), new_object.clone(), Algorithm::Myers)?;
+            DeltaInstructions::generate(
+                base_object.clone(),
+                new_object.clone(),
+                Algorithm::Myers,
+                HeaderState::Excluded,
+            )?;
         let mut encoded_instructions = Vec::new();
         delta_instructions
             .write_instructions(&mut encoded_instructions)
@@ -991,7 +1824,12 @@ mod test {
             .collect();
         let new_object = Bytes::from(new_object);
         let delta_instructions =
-            DeltaInstructions::generate(base_object.clone(), new_object.clone(), Algorithm::Myers)?;
+            DeltaInstructions::generate(
+                base_object.clone(),
+                new_object.clone(),
+                Algorithm::Myers,
+                HeaderState::Excluded,
+            )?;
         let mut encoded_instructions = Vec::new();
         delta_instructions
             .write_instructions(&mut encoded_instructions)
@@ -1034,7 +1872,12 @@ mod test {
         tag.write_to(new_object.by_ref())?;
         let new_object = Bytes::from(new_object);
         let delta_instructions =
-            DeltaInstructions::generate(base_object.clone(), new_object.clone(), Algorithm::Myers)?;
+            DeltaInstructions::generate(
+                base_object.clone(),
+                new_object.clone(),
+                Algorithm::Myers,
+                HeaderState::Included,
+            )?;
         let mut encoded_instructions = Vec::new();
         delta_instructions
             .write_instructions(&mut encoded_instructions)
@@ -1069,7 +1912,12 @@ mod test {
         let base_object = Bytes::from_static(b"So close no matter how far");
         let new_object = Bytes::from_static(b"So close no matter if very far");
         let delta_instructions =
-            DeltaInstructions::generate(base_object.clone(), new_object.clone(), Algorithm::Myers)?;
+            DeltaInstructions::generate(
+                base_object.clone(),
+                new_object.clone(),
+                Algorithm::Myers,
+                HeaderState::Excluded,
+            )?;
         // Validate that the delta instructions get created successfully
         let mut encoded_instructions = Vec::new();
         delta_instructions.write(&mut encoded_instructions).await?;
@@ -1098,7 +1946,12 @@ mod test {
             .collect();
         let new_object = Bytes::from(new_object);
         let delta_instructions =
-            DeltaInstructions::generate(base_object.clone(), new_object.clone(), Algorithm::Myers)?;
+            DeltaInstructions::generate(
+                base_object.clone(),
+                new_object.clone(),
+                Algorithm::Myers,
+                HeaderState::Excluded,
+            )?;
         // Validate that the delta instructions get created successfully
         let mut encoded_instructions = Vec::new();
         delta_instructions.write(&mut encoded_instructions).await?;
@@ -1141,7 +1994,12 @@ mod test {
         tag.write_to(new_object.by_ref())?;
         let new_object = Bytes::from(new_object);
         let delta_instructions =
-            DeltaInstructions::generate(base_object.clone(), new_object.clone(), Algorithm::Myers)?;
+            DeltaInstructions::generate(
+                base_object.clone(),
+                new_object.clone(),
+                Algorithm::Myers,
+                HeaderState::Included,
+            )?;
         let chunk_prefix =
             DeltaInstructionChunkIdPrefix::new(ONES_CSID, MPath::ROOT, TWOS_CSID, MPath::ROOT);
         let stored_metadata = store_delta_instructions(
@@ -1201,4 +2059,310 @@ mod test {
         assert_eq!(tag, output_tag, "Git tag objects do not match");
         Ok(())
     }
+
+    #[fbinit::test]
+    async fn test_parse_roundtrips_generated_instructions() -> Result<()> {
+        let base_object = Bytes::from_static(b"So close no matter how far");
+        let new_object = Bytes::from_static(b"So close no matter if very far");
+        let delta_instructions = DeltaInstructions::generate(
+            base_object.clone(),
+            new_object.clone(),
+            Algorithm::Myers,
+            HeaderState::Excluded,
+        )?;
+        let mut encoded = Vec::new();
+        delta_instructions.write(&mut encoded).await?;
+
+        let decoded = DeltaInstructions::parse(&encoded)?;
+        assert_eq!(decoded.base_object_size, base_object.len() as u64);
+        assert_eq!(decoded.new_object_size, new_object.len() as u64);
+        assert_eq!(decoded.instructions, delta_instructions.instructions);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_rejects_copy_past_base_object_end() -> Result<()> {
+        let mut encoded = Vec::new();
+        // base_object_size = 4, new_object_size = 10
+        encoded.push(4u8);
+        encoded.push(10u8);
+        // A single Copy instruction reading 10 bytes starting at offset 0, well past the
+        // 4-byte base object.
+        let copy = DeltaInstruction::Copy {
+            base_offset: 0,
+            size: 10,
+        };
+        let rt = tokio::runtime::Runtime::new()?;
+        rt.block_on(copy.write(&mut encoded))?;
+
+        let err = DeltaInstructions::parse(&encoded).expect_err("should reject out-of-range copy");
+        assert!(err.to_string().contains("outside base object"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_rejects_size_mismatch() -> Result<()> {
+        let mut encoded = Vec::new();
+        // base_object_size = 4, new_object_size = 10, but the single Data instruction below
+        // only produces 3 bytes.
+        encoded.push(4u8);
+        encoded.push(10u8);
+        let rt = tokio::runtime::Runtime::new()?;
+        rt.block_on(DeltaInstruction::from_data(Bytes::from_static(b"abc"))?.write(&mut encoded))?;
+
+        let err = DeltaInstructions::parse(&encoded).expect_err("should reject size mismatch");
+        assert!(err.to_string().contains("declared new object size"));
+        Ok(())
+    }
+
+    #[fbinit::test]
+    async fn test_apply_matches_test_only_apply() -> Result<()> {
+        let base_object = Bytes::from_static(b"So close no matter how far");
+        let new_object = Bytes::from_static(b"So close no matter if very far");
+        let delta_instructions = DeltaInstructions::generate(
+            base_object.clone(),
+            new_object.clone(),
+            Algorithm::Myers,
+            HeaderState::Excluded,
+        )?;
+
+        let mut produced = Vec::new();
+        delta_instructions.apply(&mut produced).await?;
+        assert_eq!(new_object, Bytes::from(produced));
+
+        // The decoded-from-bytes path should produce the same result when handed the same base
+        // object back.
+        let mut encoded = Vec::new();
+        delta_instructions.write(&mut encoded).await?;
+        let decoded = DeltaInstructions::parse(&encoded)?;
+        let mut produced_from_decoded = Vec::new();
+        decoded.apply(&base_object, &mut produced_from_decoded).await?;
+        assert_eq!(new_object, Bytes::from(produced_from_decoded));
+        Ok(())
+    }
+
+    #[fbinit::test]
+    async fn test_apply_rejects_wrong_base_object() -> Result<()> {
+        let base_object = Bytes::from_static(b"So close no matter how far");
+        let new_object = Bytes::from_static(b"So close no matter if very far");
+        let delta_instructions = DeltaInstructions::generate(
+            base_object,
+            new_object,
+            Algorithm::Myers,
+            HeaderState::Excluded,
+        )?;
+        let mut encoded = Vec::new();
+        delta_instructions.write(&mut encoded).await?;
+        let decoded = DeltaInstructions::parse(&encoded)?;
+
+        let wrong_base_object = Bytes::from_static(b"totally different base");
+        let mut out = Vec::new();
+        let err = decoded
+            .apply(&wrong_base_object, &mut out)
+            .await
+            .expect_err("should reject base object of the wrong size");
+        assert!(
+            err.to_string()
+                .contains("delta instructions were decoded against a base object")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_deltifiable_size_rejects_objects_over_4gb() {
+        assert!(validate_deltifiable_size(u32::MAX as usize, "base").is_ok());
+        let err = validate_deltifiable_size(u32::MAX as usize + 1, "new")
+            .expect_err("should reject an object larger than u32::MAX bytes");
+        assert!(err.to_string().contains("new object is"));
+    }
+
+    #[test]
+    fn test_estimate_delta_size_matches_generated_instructions() -> Result<()> {
+        let base_object = b"hello world, this is the base object".to_vec();
+        let new_object = b"hello world, this is the new object".to_vec();
+
+        let estimated_size =
+            estimate_delta_size(&base_object, &new_object, Algorithm::Myers)?;
+        let instructions = DeltaInstructions::generate(
+            Bytes::from(base_object),
+            Bytes::from(new_object),
+            Algorithm::Myers,
+            HeaderState::Included,
+        )?;
+        let actual_size: usize = instructions
+            .instructions
+            .iter()
+            .map(DeltaInstruction::encoded_size)
+            .sum();
+        assert_eq!(estimated_size, actual_size);
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_best_delta_picks_smallest_estimated_candidate() -> Result<()> {
+        let new_object = Bytes::from_static(b"the quick brown fox jumps over the lazy dog");
+        let candidates = vec![
+            Bytes::from_static(b"completely unrelated content with no overlap at all"),
+            Bytes::from_static(b"the quick brown fox jumps over the lazy dog, mostly"),
+        ];
+        let config = DeltaSelectionConfig {
+            diff_algorithm: Algorithm::Myers,
+            header_state: HeaderState::Included,
+            max_candidate_size: u64::MAX,
+            max_candidates_tried: usize::MAX,
+        };
+        let best = generate_best_delta(new_object, candidates, &config)?
+            .expect("should find a best delta among the candidates");
+        assert_eq!(best.winning_candidate, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_ofs_delta_offset_matches_known_encoding() {
+        // Hand-derived from Git's offset encoding: 200 decodes as (0+1)<<7 | 0x48 == 200.
+        let mut out = Vec::new();
+        write_ofs_delta_offset(200, &mut out);
+        assert_eq!(out, vec![0x80, 0x48]);
+
+        let mut out = Vec::new();
+        write_ofs_delta_offset(0, &mut out);
+        assert_eq!(out, vec![0x00]);
+
+        let mut out = Vec::new();
+        write_ofs_delta_offset(127, &mut out);
+        assert_eq!(out, vec![0x7f]);
+    }
+
+    #[test]
+    fn test_delta_base_ref_write_header() {
+        let mut out = Vec::new();
+        DeltaBaseRef::Offset(200).write_header(&mut out);
+        assert_eq!(out, vec![0x80, 0x48]);
+
+        let oid = ObjectId::empty_blob(gix_hash::Kind::Sha1);
+        let mut out = Vec::new();
+        DeltaBaseRef::Oid(oid.clone()).write_header(&mut out);
+        assert_eq!(out, oid.as_bytes());
+    }
+
+    // Reimplements the decode loop from `packfile::unpack::read_ofs_delta_offset` (that function
+    // is private to `packfile::unpack`, so it's duplicated rather than shared) to check that
+    // `write_ofs_delta_offset` round-trips through it for arbitrary distances.
+    fn decode_ofs_delta_offset(data: &[u8]) -> u64 {
+        let mut iter = data.iter();
+        let mut byte = *iter.next().expect("non-empty");
+        let mut base_offset = (byte & 0x7f) as u64;
+        while byte & 0x80 != 0 {
+            byte = *iter.next().expect("truncated");
+            base_offset = ((base_offset + 1) << 7) | (byte & 0x7f) as u64;
+        }
+        base_offset
+    }
+
+    quickcheck! {
+        fn prop_ofs_delta_offset_roundtrip(distance: u64) -> bool {
+            let mut out = Vec::new();
+            write_ofs_delta_offset(distance, &mut out);
+            decode_ofs_delta_offset(&out) == distance
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_truncated_stream() {
+        // A data instruction command byte claiming 5 bytes follow, with only 2 actually present.
+        let encoded = vec![0u8, 0u8, 5u8, b'a', b'b'];
+        let err = DeltaInstructions::parse(&encoded).expect_err("should reject truncated stream");
+        assert!(err.to_string().contains("Truncated data instruction"));
+    }
+
+    #[test]
+    fn test_read_size_rejects_never_terminating_varint() {
+        // Every byte has the continuation bit set, so the varint never terminates. Before the
+        // `MAX_SIZE_VARINT_BYTES` cap this would shift `size` past bit 63 and panic instead of
+        // returning this error.
+        let encoded = vec![CONTINUATION_BITMASK; 32];
+        let err = read_size(&encoded).expect_err("should reject an unterminated size varint");
+        assert!(err.to_string().contains("size varint is too long"));
+    }
+
+    #[test]
+    fn test_sniff_tokenization_mode_picks_lines_for_text_and_chunked_for_binary() {
+        assert_eq!(
+            sniff_tokenization_mode(b"line one\nline two\nline three\n"),
+            TokenizationMode::Lines,
+        );
+        assert_eq!(
+            sniff_tokenization_mode(b"line one\n\0line two\n"),
+            TokenizationMode::Chunked,
+        );
+    }
+
+    #[test]
+    fn test_generate_deltifies_text_object_by_line() -> Result<()> {
+        let base_object = Bytes::from_static(b"line one\nline two\nline three\nline four\n");
+        // Only "line two" changed; the rest of the lines are unmodified and should be copied from
+        // the base object rather than being bundled in with the changed line.
+        let new_object =
+            Bytes::from_static(b"line one\nline two changed\nline three\nline four\n");
+
+        let delta_instructions = DeltaInstructions::generate(
+            base_object.clone(),
+            new_object.clone(),
+            Algorithm::Myers,
+            HeaderState::Excluded,
+        )?;
+
+        let mut encoded_instructions = Vec::new();
+        let rt = tokio::runtime::Runtime::new()?;
+        rt.block_on(delta_instructions.write_instructions(&mut encoded_instructions))?;
+        let mut recreated_new_object = Vec::new();
+        apply(
+            base_object.as_ref(),
+            &mut recreated_new_object,
+            encoded_instructions.as_ref(),
+        );
+        assert_eq!(new_object, Bytes::from(recreated_new_object));
+
+        // Since "line one" is unchanged and appears before the edit, it should be copied from the
+        // base object rather than re-sent as a Data instruction - the signal that diffing actually
+        // operated at line granularity rather than re-chunking the whole object.
+        assert!(delta_instructions.instructions.iter().any(|instruction| matches!(
+            instruction,
+            DeltaInstruction::Copy { base_offset: 0, size: 9 }
+        )));
+        Ok(())
+    }
+
+    // Property-based coverage on top of the handcrafted/random cases above: for any pair of
+    // byte buffers, quickcheck shrinks a failure down to a minimal reproducing pair instead of
+    // leaving us with a single opaque large-random-input failure. This only covers instruction
+    // sequences that `generate()` itself produces, not arbitrary hand-built ones - an arbitrary
+    // Copy instruction could reference an offset past the end of whatever `base_object` it's
+    // applied against, which `apply` (like real Git delta consumers) isn't required to handle.
+    quickcheck! {
+        fn prop_delta_roundtrip(base_object: Vec<u8>, new_object: Vec<u8>) -> bool {
+            let base_object = Bytes::from(base_object);
+            let new_object = Bytes::from(new_object);
+            let delta_instructions = DeltaInstructions::generate(
+                base_object.clone(),
+                new_object.clone(),
+                Algorithm::Myers,
+                HeaderState::Excluded,
+            )
+            .expect("generating a delta between two arbitrary byte buffers should not fail");
+
+            let mut encoded_instructions = Vec::new();
+            let rt = tokio::runtime::Runtime::new().expect("failed to create tokio runtime");
+            rt.block_on(delta_instructions.write_instructions(&mut encoded_instructions))
+                .expect("encoding delta instructions should not fail");
+
+            let mut recreated_new_object = Vec::new();
+            apply(
+                base_object.as_ref(),
+                &mut recreated_new_object,
+                encoded_instructions.as_ref(),
+            );
+            new_object == Bytes::from(recreated_new_object)
+        }
+    }
 }
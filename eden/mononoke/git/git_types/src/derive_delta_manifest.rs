@@ -43,6 +43,7 @@ use git_delta::git_delta;
 use gix_diff::blob::Algorithm;
 use gix_hash::ObjectId;
 use manifest::ManifestOps;
+use metaconfig_types::GitDeltaManifestDiffAlgorithm;
 use mononoke_types::path::MPath;
 use mononoke_types::BonsaiChangeset;
 use mononoke_types::ChangesetId;
@@ -57,11 +58,10 @@ use crate::delta_manifest::ObjectDelta;
 use crate::delta_manifest::ObjectEntry;
 use crate::fetch_git_object_bytes;
 use crate::mode;
-use crate::store::store_delta_instructions;
+use crate::store::fetch_or_generate_cached_delta;
 use crate::store::store_raw_delta;
 use crate::store::GitIdentifier;
 use crate::store::HeaderState;
-use crate::DeltaInstructions;
 use crate::DeltaObjectKind;
 use crate::MappedGitCommitId;
 use crate::TreeHandle;
@@ -85,6 +85,16 @@ pub enum DeltaCreationMethod {
     Git,
 }
 
+/// Map the repo-configured [`GitDeltaManifestDiffAlgorithm`] to the [`Algorithm`] that
+/// `gix_diff` actually expects, so repos can tune delta quality vs CPU via config instead of a
+/// code change.
+fn diff_algorithm(config: GitDeltaManifestDiffAlgorithm) -> Algorithm {
+    match config {
+        GitDeltaManifestDiffAlgorithm::Myers => Algorithm::Myers,
+        GitDeltaManifestDiffAlgorithm::Histogram => Algorithm::Histogram,
+    }
+}
+
 impl RootGitDeltaManifestId {
     pub fn new(id: GitDeltaManifestId) -> Self {
         Self(id)
@@ -150,6 +160,7 @@ async fn metadata_to_manifest_entry(
     blobstore: Arc<dyn Blobstore>,
     ctx: &CoreContext,
     delta_creation_method: DeltaCreationMethod,
+    diff_algorithm: Algorithm,
 ) -> Result<GitDeltaManifestEntry> {
     let full_object_entry = tree_member_to_object_entry(&metadata.actual, path.clone())
         .with_context(|| {
@@ -197,11 +208,30 @@ async fn metadata_to_manifest_entry(
                     if actual_object.is_empty() || base_object.is_empty() {
                         return anyhow::Ok(None);
                     }
+                    // Deltas address offsets/sizes with a u32 (see `DeltaInstructions::generate`),
+                    // so an object at or beyond 4GB on either side can't be deltified at all - fall
+                    // back to storing it in full rather than letting `generate` error out.
+                    if actual_object.len() > u32::MAX as usize || base_object.len() > u32::MAX as usize
+                    {
+                        return anyhow::Ok(None);
+                    }
                     let stored_instructions_metadata = match delta_creation_method {
                         DeltaCreationMethod::Internal => {
-                        let instructions = DeltaInstructions::generate(
-                            base_object,actual_object,Algorithm::Myers,
+                        // Reuse a previously computed delta for this exact (base, target) oid
+                        // pair if one is cached, rather than always re-running the diff - the
+                        // same base/target pair can recur across commits that modify a path the
+                        // same way, or across a re-derivation of this manifest entry.
+                        let raw_instruction_bytes = fetch_or_generate_cached_delta(
+                            &ctx,
+                            &blobstore,
+                            &base.oid,
+                            &full_object_entry.oid,
+                            base_object,
+                            actual_object,
+                            diff_algorithm,
+                            HeaderState::Excluded,
                         )
+                        .await
                         .with_context(|| {
                             format!(
                                 "Error while computing delta between base object {:?} and actual object {:?}",
@@ -213,7 +243,7 @@ async fn metadata_to_manifest_entry(
                         let chunk_prefix =
                             DeltaInstructionChunkIdPrefix::new(commit, path.clone(), origin, path.clone());
                         let chunk_size = Some(CHUNK_SIZE);
-                        store_delta_instructions(&ctx, &blobstore, instructions, chunk_prefix, chunk_size)
+                        store_raw_delta(&ctx, &blobstore, raw_instruction_bytes.to_vec(), chunk_prefix, chunk_size)
                             .await
                             .with_context(|| {
                                 format!(
@@ -484,6 +514,7 @@ async fn derive_git_delta_manifest(
     .into_iter()
     .collect::<HashMap<_, _>>();
     // For each modified path, find the correct origin commit that introduced the previous modification to the path and generate the delta entries
+    let diff_algorithm = self::diff_algorithm(derivation_ctx.config().git_delta_manifest_diff_algorithm);
     let manifest_entries = stream::iter(diff_items.into_iter()).map(|(path, mut entry)| {
         let parent_unodes_with_commit = &parent_unodes_with_commit;
         let commit = bonsai.get_changeset_id();
@@ -543,7 +574,7 @@ async fn derive_git_delta_manifest(
                 entry.deltas = deltas_with_correct_origin;
             }
             // Use the metadata of the delta entry to construct GitDeltaManifestEntry
-            let manifest_entry = metadata_to_manifest_entry(&commit, path.clone(), entry, blobstore, ctx, DeltaCreationMethod::Internal)
+            let manifest_entry = metadata_to_manifest_entry(&commit, path.clone(), entry, blobstore, ctx, DeltaCreationMethod::Internal, diff_algorithm)
                     .await.with_context(|| format!("Error in generating git delta manifest entry for path {}", path))?;
             anyhow::Ok((path, manifest_entry))
         }
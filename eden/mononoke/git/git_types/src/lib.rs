@@ -33,8 +33,15 @@ pub use object::ObjectKind;
 
 pub use crate::blob::BlobHandle;
 pub use crate::commit::MappedGitCommitId;
+pub use crate::delta::generate_best_delta;
+pub use crate::delta::BestDelta;
+pub use crate::delta::DeltaBaseRef;
 pub use crate::delta::DeltaInstructionChunkIdPrefix;
 pub use crate::delta::DeltaInstructions;
+pub use crate::delta::DeltaSelectionConfig;
+pub use crate::delta::estimate_delta_size;
+pub use crate::delta::generate_deltas_concurrently;
+pub use crate::delta::select_best_base;
 pub use crate::delta_manifest::GitDeltaManifestEntry;
 pub use crate::delta_manifest::ObjectDelta;
 pub use crate::delta_manifest::ObjectEntry;
@@ -44,6 +51,7 @@ pub use crate::delta_manifest_ops::GitDeltaManifestOps;
 pub use crate::delta_manifest_ops::ObjectDeltaOps;
 pub use crate::derive_delta_manifest::RootGitDeltaManifestId;
 pub use crate::errors::GitError;
+pub use crate::store::fetch_cached_delta;
 pub use crate::store::fetch_delta_instructions;
 pub use crate::store::fetch_git_object;
 pub use crate::store::fetch_git_object_bytes;
@@ -51,6 +59,8 @@ pub use crate::store::fetch_non_blob_git_object;
 pub use crate::store::fetch_non_blob_git_object_bytes;
 pub use crate::store::fetch_packfile_base_item;
 pub use crate::store::fetch_packfile_base_item_if_exists;
+pub use crate::store::fetch_or_generate_cached_delta;
+pub use crate::store::generate_and_cache_delta;
 pub use crate::store::upload_non_blob_git_object;
 pub use crate::store::upload_packfile_base_item;
 pub use crate::store::GitIdentifier;
@@ -27,6 +27,7 @@ use futures::stream;
 use futures::stream::BoxStream;
 use futures::StreamExt;
 use futures::TryStreamExt;
+use gix_diff::blob::Algorithm;
 use gix_object::WriteTo;
 use mononoke_types::hash::GitSha1;
 use mononoke_types::hash::RichGitSha1;
@@ -54,6 +55,7 @@ impl_loadable_storable! {
 
 const GIT_OBJECT_PREFIX: &str = "git_object";
 const GIT_PACKFILE_BASE_ITEM_PREFIX: &str = "git_packfile_base_item";
+const GIT_DELTA_CACHE_PREFIX: &str = "git_delta_cache";
 const SEPARATOR: &str = ".";
 
 /// Free function for uploading serialized git objects to blobstore.
@@ -147,7 +149,7 @@ where
 
 /// Enum determining the state of the git header in the raw
 /// git object bytes
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum HeaderState {
     /// Include the null-terminated git header when fetching the bytes
     /// of the raw git object
@@ -471,6 +473,141 @@ where
         .boxed()
 }
 
+fn delta_cache_key(
+    base_oid: &gix_hash::oid,
+    target_oid: &gix_hash::oid,
+    header_state: &HeaderState,
+) -> String {
+    let header_tag = match header_state {
+        HeaderState::Included => "included",
+        HeaderState::Excluded => "excluded",
+    };
+    format!(
+        "{}{}{}{}{}{}{}",
+        GIT_DELTA_CACHE_PREFIX,
+        SEPARATOR,
+        header_tag,
+        SEPARATOR,
+        base_oid.to_hex(),
+        SEPARATOR,
+        target_oid.to_hex(),
+    )
+}
+
+/// Fetch a previously cached, raw (pre-chunking, pre-compression) encoded delta instruction
+/// blob for `base_oid` -> `target_oid` under the given `header_state` - i.e. exactly the bytes
+/// [`DeltaInstructions::write`] would produce - if one was stored by [`generate_and_cache_delta`].
+/// This is a pure cache lookup and returns `None` on a miss rather than an error - the caller's
+/// own fallback on a miss is simply to compute the delta fresh via [`generate_and_cache_delta`],
+/// which [`fetch_or_generate_cached_delta`] does for you.
+///
+/// Cached entries carry no TTL of their own: like the other git object blobs this module stores
+/// (see [`upload_non_blob_git_object`], [`upload_packfile_base_item`]), they're expected to be
+/// reclaimed by the blobstore's usual GC sweep rather than expired on a timer. This cache is
+/// never the system of record for either object's content - it can always be safely repopulated
+/// by recomputing the delta - so callers wanting sooner eviction should do so out of band (e.g. a
+/// TTL'd wrapper around the blobstore passed in here) rather than this module tracking expiry
+/// itself.
+pub async fn fetch_cached_delta<B>(
+    ctx: &CoreContext,
+    blobstore: &B,
+    base_oid: &gix_hash::oid,
+    target_oid: &gix_hash::oid,
+    header_state: &HeaderState,
+) -> anyhow::Result<Option<Bytes>>
+where
+    B: Blobstore,
+{
+    let blobstore_key = delta_cache_key(base_oid, target_oid, header_state);
+    Ok(blobstore
+        .get(ctx, &blobstore_key)
+        .await?
+        .map(|bytes| bytes.into_raw_bytes()))
+}
+
+/// Compute the delta from `base_object` to `new_object` via [`DeltaInstructions::generate`],
+/// cache its raw encoded form (the bytes [`DeltaInstructions::write`] produces) keyed by
+/// `(base_oid, target_oid, header_state)`, and return those bytes - the same representation
+/// [`fetch_cached_delta`] returns on a hit. Left uncompressed rather than Zlib-compressed here
+/// since callers (e.g. [`store_raw_delta`]) already apply their own compression before writing
+/// the delta to its eventual destination; compressing twice would only waste CPU. Always
+/// recomputes the delta; callers on the read path should check [`fetch_cached_delta`] first, or
+/// just call [`fetch_or_generate_cached_delta`] to get both in one call.
+pub async fn generate_and_cache_delta<B>(
+    ctx: &CoreContext,
+    blobstore: &B,
+    base_oid: &gix_hash::oid,
+    target_oid: &gix_hash::oid,
+    base_object: Bytes,
+    new_object: Bytes,
+    diff_algorithm: Algorithm,
+    header_state: HeaderState,
+) -> anyhow::Result<Bytes>
+where
+    B: Blobstore,
+{
+    let instructions = DeltaInstructions::generate(
+        base_object,
+        new_object,
+        diff_algorithm,
+        header_state.clone(),
+    )?;
+    let mut encoded_instructions = Vec::new();
+    instructions
+        .write(&mut encoded_instructions)
+        .await
+        .context("Error in converting DeltaInstructions to raw bytes")?;
+    let encoded_instructions = Bytes::from(encoded_instructions);
+
+    let blobstore_key = delta_cache_key(base_oid, target_oid, &header_state);
+    blobstore
+        .put(
+            ctx,
+            blobstore_key,
+            BlobstoreBytes::from_bytes(encoded_instructions.clone()),
+        )
+        .await
+        .context("Error in caching computed delta instructions")?;
+    Ok(encoded_instructions)
+}
+
+/// Read-through cache for the delta between `base_oid` and `target_oid`: return the cached, raw
+/// encoded delta instruction blob if one already exists, otherwise compute and cache one via
+/// [`generate_and_cache_delta`]. Used by [`crate::derive_delta_manifest`] so that re-deriving a
+/// `GitDeltaManifest` entry whose base/target pair was already deltified elsewhere - or deriving
+/// the same pair for two different commits that happen to modify a path identically - doesn't
+/// recompute an identical diff.
+pub async fn fetch_or_generate_cached_delta<B>(
+    ctx: &CoreContext,
+    blobstore: &B,
+    base_oid: &gix_hash::oid,
+    target_oid: &gix_hash::oid,
+    base_object: Bytes,
+    new_object: Bytes,
+    diff_algorithm: Algorithm,
+    header_state: HeaderState,
+) -> anyhow::Result<Bytes>
+where
+    B: Blobstore,
+{
+    if let Some(cached) =
+        fetch_cached_delta(ctx, blobstore, base_oid, target_oid, &header_state).await?
+    {
+        return Ok(cached);
+    }
+    generate_and_cache_delta(
+        ctx,
+        blobstore,
+        base_oid,
+        target_oid,
+        base_object,
+        new_object,
+        diff_algorithm,
+        header_state,
+    )
+    .await
+}
+
 async fn store_delta_instruction_chunk<B>(
     ctx: &CoreContext,
     blobstore: &B,
@@ -553,4 +690,48 @@ mod test {
         assert_eq!(fetched_packfile_base_item, original_packfile_base_item);
         anyhow::Ok(())
     }
+
+    #[fbinit::test]
+    async fn fetch_or_generate_cached_delta_reuses_cached_bytes(fb: FacebookInit) -> Result<()> {
+        let repo = fixtures::Linear::getrepo(fb).await;
+        let ctx = CoreContext::test_mock(fb);
+        let blobstore = repo.repo_blobstore_arc();
+        let base_object = Bytes::from_static(b"hello world\nhow are you\n");
+        let new_object = Bytes::from_static(b"hello world\nhow are you today\n");
+        let base_oid = ObjectId::empty_blob(gix_hash::Kind::Sha1);
+        let target_oid = ObjectId::empty_tree(gix_hash::Kind::Sha1);
+        // Cache miss should generate the delta and persist it
+        let generated = fetch_or_generate_cached_delta(
+            &ctx,
+            &blobstore,
+            &base_oid,
+            &target_oid,
+            base_object.clone(),
+            new_object.clone(),
+            Algorithm::Myers,
+            HeaderState::Included,
+        )
+        .await?;
+        // A subsequent call should be served from the cache and return identical bytes, even if
+        // given objects that would produce a different delta, proving the cached value was reused
+        // rather than recomputed
+        let cached = fetch_or_generate_cached_delta(
+            &ctx,
+            &blobstore,
+            &base_oid,
+            &target_oid,
+            Bytes::from_static(b"completely different content"),
+            Bytes::from_static(b"completely different content, changed"),
+            Algorithm::Myers,
+            HeaderState::Included,
+        )
+        .await?;
+        assert_eq!(generated, cached);
+        // And the direct accessor should observe the same cached bytes
+        let fetched =
+            fetch_cached_delta(&ctx, &blobstore, &base_oid, &target_oid, &HeaderState::Included)
+                .await?;
+        assert_eq!(fetched, Some(generated));
+        anyhow::Ok(())
+    }
 }
@@ -9,5 +9,6 @@ pub mod bundle;
 mod hash_writer;
 pub mod pack;
 pub mod types;
+pub mod unpack;
 
 pub use packfile_thrift as thrift;
@@ -0,0 +1,452 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! Support for the receive side of the Git pack protocol: parsing the raw bytes of an incoming
+//! pack (as sent by `git push`), resolving OFS_DELTA/REF_DELTA chains against objects seen
+//! earlier in the same pack, and verifying that the pack is well-formed before handing the fully
+//! resolved objects onward to the import layer (e.g. [`import_tools::GitUploader`]).
+//!
+//! Unlike [`crate::pack::PackfileWriter`], which writes packs incrementally to an async sink,
+//! the client is expected to buffer the whole request body first (mirroring how
+//! `git_server::command` parses `ls-refs`/`fetch` arguments from an already-buffered packetline),
+//! so parsing here is synchronous.
+
+use std::collections::HashMap;
+use std::io::Read;
+
+use bytes::Bytes;
+use flate2::read::ZlibDecoder;
+use gix_hash::oid;
+use gix_hash::ObjectId;
+use gix_object::Kind;
+use sha1::Digest;
+use sha1::Sha1;
+use thiserror::Error;
+
+/// Header + trailer size of a pack: 4 byte magic, 4 byte version, 4 byte object count, 20 byte
+/// trailing SHA1 checksum.
+const PACK_HEADER_LEN: usize = 12;
+const PACK_CHECKSUM_LEN: usize = 20;
+const PACK_MAGIC: &[u8; 4] = b"PACK";
+const SUPPORTED_PACK_VERSION: u32 = 2;
+
+/// Limits enforced while unpacking an incoming pack, so that a single push cannot exhaust
+/// server resources.
+#[derive(Debug, Clone, Copy)]
+pub struct UnpackLimits {
+    /// Maximum number of objects the pack is allowed to contain.
+    pub max_objects: u32,
+    /// Maximum size, in bytes, of any single resolved (i.e. post-delta) object.
+    pub max_object_size: u64,
+}
+
+impl Default for UnpackLimits {
+    fn default() -> Self {
+        Self {
+            max_objects: 1_000_000,
+            max_object_size: 4 * 1024 * 1024 * 1024,
+        }
+    }
+}
+
+/// Errors that can occur while unpacking an incoming pack. Kept granular so that callers can
+/// report back to the pushing client exactly what was wrong with the pack.
+#[derive(Debug, Error)]
+pub enum UnpackError {
+    #[error("Pack is missing the {PACK_HEADER_LEN} byte PACK header")]
+    TruncatedHeader,
+    #[error("Pack has an invalid magic, expected 'PACK'")]
+    InvalidMagic,
+    #[error("Unsupported pack version {0}, only version {SUPPORTED_PACK_VERSION} is supported")]
+    UnsupportedVersion(u32),
+    #[error("Pack declares {declared} objects, exceeding the limit of {limit}")]
+    TooManyObjects { declared: u32, limit: u32 },
+    #[error("Resolved object {oid} has size {size} bytes, exceeding the limit of {limit} bytes")]
+    ObjectTooLarge { oid: ObjectId, size: u64, limit: u64 },
+    #[error("Delta at pack offset {offset} declares a result size of {size} bytes, exceeding the limit of {limit} bytes")]
+    DeltaResultTooLarge { offset: u64, size: u64, limit: u64 },
+    #[error("Object at pack offset {0} has an unrecognized type id {1}")]
+    UnrecognizedObjectType(u64, u8),
+    #[error("Object at pack offset {0} is an OFS_DELTA that references a base outside the pack")]
+    UnknownOffsetDeltaBase(u64),
+    #[error("Object at pack offset {0} is a REF_DELTA referencing base {1}, which was not found earlier in the pack")]
+    UnknownRefDeltaBase(u64, ObjectId),
+    #[error("Failed to inflate zlib payload for object at pack offset {0}: {1}")]
+    InflateFailed(u64, std::io::Error),
+    #[error("Delta instructions for object at pack offset {0} are corrupt: {1}")]
+    CorruptDelta(u64, &'static str),
+    #[error("Pack is truncated: expected more data after offset {0}")]
+    TruncatedPack(u64),
+    #[error("Pack trailer checksum mismatch: pack claims {expected} but content hashes to {actual}")]
+    ChecksumMismatch {
+        expected: ObjectId,
+        actual: ObjectId,
+    },
+}
+
+/// A single fully-resolved Git object extracted from an incoming pack. Any delta chain leading
+/// to it has already been applied, so `bytes` is the raw, undeltified object content and `oid`
+/// is the SHA1 of that content (verified against the pack's own hashing scheme, i.e. recomputed
+/// rather than trusted from the wire).
+#[derive(Debug, Clone)]
+pub struct ResolvedObject {
+    pub oid: ObjectId,
+    pub kind: Kind,
+    pub bytes: Bytes,
+}
+
+/// Parse the object type nibble from a pack object header into a [`Kind`], if it identifies a
+/// base (i.e. non-delta) object.
+fn base_kind_from_type_id(type_id: u8) -> Option<Kind> {
+    match type_id {
+        1 => Some(Kind::Commit),
+        2 => Some(Kind::Tree),
+        3 => Some(Kind::Blob),
+        4 => Some(Kind::Tag),
+        _ => None,
+    }
+}
+
+/// Every varint parsed in this file packs 7 bits of payload per continuation byte into a `u64`.
+/// 10 bytes (70 bits) is already more than enough to cover every value a `u64` can hold, so a
+/// pack that hasn't terminated its varint by then is corrupt (or hostile) rather than merely
+/// large - reject it instead of shifting the accumulator past bit 63.
+const MAX_VARINT_BYTES: usize = 10;
+
+/// Read the variable-length `(type, size)` header that precedes every object in a pack.
+/// Returns the type id, the declared decompressed size, and the number of bytes consumed.
+fn read_object_header(data: &[u8], offset: u64) -> Result<(u8, u64, usize), UnpackError> {
+    let mut consumed = 0;
+    let byte = *data
+        .first()
+        .ok_or(UnpackError::TruncatedPack(offset))?;
+    consumed += 1;
+    let type_id = (byte >> 4) & 0x7;
+    let mut size = (byte & 0x0f) as u64;
+    let mut shift = 4;
+    let mut more = byte & 0x80 != 0;
+    while more {
+        if consumed >= MAX_VARINT_BYTES {
+            return Err(UnpackError::CorruptDelta(
+                offset,
+                "object header size varint is too long",
+            ));
+        }
+        let byte = *data
+            .get(consumed)
+            .ok_or(UnpackError::TruncatedPack(offset))?;
+        size |= ((byte & 0x7f) as u64) << shift;
+        shift += 7;
+        more = byte & 0x80 != 0;
+        consumed += 1;
+    }
+    Ok((type_id, size, consumed))
+}
+
+/// Read the "negative offset" varint used to encode an OFS_DELTA base offset.
+fn read_ofs_delta_offset(data: &[u8], offset: u64) -> Result<(u64, usize), UnpackError> {
+    let mut consumed = 0;
+    let mut byte = *data
+        .first()
+        .ok_or(UnpackError::TruncatedPack(offset))?;
+    consumed += 1;
+    let mut base_offset = (byte & 0x7f) as u64;
+    while byte & 0x80 != 0 {
+        if consumed >= MAX_VARINT_BYTES {
+            return Err(UnpackError::CorruptDelta(
+                offset,
+                "OFS_DELTA base offset varint is too long",
+            ));
+        }
+        byte = *data
+            .get(consumed)
+            .ok_or(UnpackError::TruncatedPack(offset))?;
+        consumed += 1;
+        base_offset = base_offset
+            .checked_add(1)
+            .and_then(|base_offset| base_offset.checked_shl(7))
+            .ok_or(UnpackError::CorruptDelta(
+                offset,
+                "OFS_DELTA base offset overflows a u64",
+            ))?
+            | (byte & 0x7f) as u64;
+    }
+    Ok((base_offset, consumed))
+}
+
+/// Read the base-size/result-size varint that precedes the copy/insert instructions inside a
+/// delta payload (distinct encoding from [`read_object_header`]: plain 7-bit little endian, no
+/// type bits in the first byte).
+fn read_delta_size(data: &[u8], pos: &mut usize, offset: u64) -> Result<u64, UnpackError> {
+    let mut size = 0u64;
+    let mut shift = 0;
+    let mut consumed = 0;
+    loop {
+        if consumed >= MAX_VARINT_BYTES {
+            return Err(UnpackError::CorruptDelta(
+                offset,
+                "delta size varint is too long",
+            ));
+        }
+        let byte = *data
+            .get(*pos)
+            .ok_or(UnpackError::CorruptDelta(offset, "truncated size header"))?;
+        *pos += 1;
+        consumed += 1;
+        size |= ((byte & 0x7f) as u64) << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+    Ok(size)
+}
+
+/// Apply the copy/insert instructions of a Git delta to `base`, producing the target object's
+/// content. See https://git-scm.com/docs/pack-format#_deltified_representation for the format.
+fn apply_delta(
+    base: &[u8],
+    delta: &[u8],
+    offset: u64,
+    max_object_size: u64,
+) -> Result<Vec<u8>, UnpackError> {
+    let mut pos = 0;
+    let base_size = read_delta_size(delta, &mut pos, offset)?;
+    if base_size as usize != base.len() {
+        return Err(UnpackError::CorruptDelta(
+            offset,
+            "delta base size does not match the resolved base object",
+        ));
+    }
+    let result_size = read_delta_size(delta, &mut pos, offset)?;
+    if result_size > max_object_size {
+        return Err(UnpackError::DeltaResultTooLarge {
+            offset,
+            size: result_size,
+            limit: max_object_size,
+        });
+    }
+    let mut target = Vec::with_capacity(result_size as usize);
+    while pos < delta.len() {
+        let cmd = delta[pos];
+        pos += 1;
+        if cmd & 0x80 != 0 {
+            let mut copy_offset: u32 = 0;
+            let mut copy_size: u32 = 0;
+            for (bit, shift) in [(0, 0), (1, 8), (2, 16), (3, 24)] {
+                if cmd & (1 << bit) != 0 {
+                    let byte = *delta
+                        .get(pos)
+                        .ok_or(UnpackError::CorruptDelta(offset, "truncated copy operand"))?;
+                    copy_offset |= (byte as u32) << shift;
+                    pos += 1;
+                }
+            }
+            for (bit, shift) in [(4, 0), (5, 8), (6, 16)] {
+                if cmd & (1 << bit) != 0 {
+                    let byte = *delta
+                        .get(pos)
+                        .ok_or(UnpackError::CorruptDelta(offset, "truncated copy operand"))?;
+                    copy_size |= (byte as u32) << shift;
+                    pos += 1;
+                }
+            }
+            if copy_size == 0 {
+                copy_size = 0x10000;
+            }
+            let start = copy_offset as usize;
+            let end = start
+                .checked_add(copy_size as usize)
+                .ok_or(UnpackError::CorruptDelta(offset, "copy operand overflow"))?;
+            let slice = base
+                .get(start..end)
+                .ok_or(UnpackError::CorruptDelta(offset, "copy operand out of range"))?;
+            target.extend_from_slice(slice);
+        } else if cmd != 0 {
+            let len = cmd as usize;
+            let slice = delta
+                .get(pos..pos + len)
+                .ok_or(UnpackError::CorruptDelta(offset, "truncated insert operand"))?;
+            target.extend_from_slice(slice);
+            pos += len;
+        } else {
+            return Err(UnpackError::CorruptDelta(offset, "reserved opcode 0"));
+        }
+    }
+    if target.len() as u64 != result_size {
+        return Err(UnpackError::CorruptDelta(
+            offset,
+            "delta produced an object of the wrong size",
+        ));
+    }
+    Ok(target)
+}
+
+/// Compute the Git object id of `content`, i.e. the SHA1 of its loose-object header
+/// (`"<kind> <len>\0"`) followed by the content itself.
+fn hash_object(kind: Kind, content: &[u8]) -> ObjectId {
+    let mut hasher = Sha1::new();
+    hasher.update(kind.as_str().as_bytes());
+    hasher.update(b" ");
+    hasher.update(content.len().to_string().as_bytes());
+    hasher.update(b"\0");
+    hasher.update(content);
+    oid::try_from_bytes(hasher.finalize().as_ref())
+        .expect("SHA1 digest is always 20 bytes")
+        .to_owned()
+}
+
+/// A record of a previously resolved object kept around so that later objects in the pack can
+/// use it as a delta base, addressed either by its pack offset (OFS_DELTA) or its object id
+/// (REF_DELTA).
+struct BaseRecord {
+    kind: Kind,
+    content: Bytes,
+}
+
+/// Stream-parse the bytes of an incoming pack, resolving every OFS_DELTA/REF_DELTA chain and
+/// verifying the pack's trailing checksum, returning the fully resolved objects in pack order.
+///
+/// REF_DELTA bases must be present earlier in the same pack; thin packs whose bases live only in
+/// the destination repository are not yet supported and are reported as
+/// [`UnpackError::UnknownRefDeltaBase`].
+pub fn unpack(data: &[u8], limits: UnpackLimits) -> Result<Vec<ResolvedObject>, UnpackError> {
+    if data.len() < PACK_HEADER_LEN + PACK_CHECKSUM_LEN {
+        return Err(UnpackError::TruncatedHeader);
+    }
+    if &data[0..4] != PACK_MAGIC {
+        return Err(UnpackError::InvalidMagic);
+    }
+    let version = u32::from_be_bytes(data[4..8].try_into().expect("slice is 4 bytes"));
+    if version != SUPPORTED_PACK_VERSION {
+        return Err(UnpackError::UnsupportedVersion(version));
+    }
+    let declared_objects = u32::from_be_bytes(data[8..12].try_into().expect("slice is 4 bytes"));
+    if declared_objects > limits.max_objects {
+        return Err(UnpackError::TooManyObjects {
+            declared: declared_objects,
+            limit: limits.max_objects,
+        });
+    }
+
+    let mut by_offset: HashMap<u64, BaseRecord> = HashMap::new();
+    let mut by_oid: HashMap<ObjectId, BaseRecord> = HashMap::new();
+    let mut resolved = Vec::with_capacity(declared_objects as usize);
+
+    let mut cursor = PACK_HEADER_LEN;
+    for _ in 0..declared_objects {
+        let object_offset = cursor as u64;
+        let (type_id, _declared_size, header_len) =
+            read_object_header(&data[cursor..], object_offset)?;
+        cursor += header_len;
+
+        let delta_base = match type_id {
+            6 => {
+                let (negative_offset, len) = read_ofs_delta_offset(&data[cursor..], object_offset)?;
+                cursor += len;
+                let base_offset = object_offset
+                    .checked_sub(negative_offset)
+                    .ok_or(UnpackError::UnknownOffsetDeltaBase(object_offset))?;
+                Some(DeltaBaseRef::Offset(base_offset))
+            }
+            7 => {
+                let oid_bytes = data
+                    .get(cursor..cursor + 20)
+                    .ok_or(UnpackError::TruncatedPack(object_offset))?;
+                cursor += 20;
+                let base_oid = oid::try_from_bytes(oid_bytes)
+                    .expect("slice is exactly 20 bytes")
+                    .to_owned();
+                Some(DeltaBaseRef::Oid(base_oid))
+            }
+            _ => None,
+        };
+
+        let mut decoder = ZlibDecoder::new(&data[cursor..]);
+        let mut payload = Vec::new();
+        decoder
+            .read_to_end(&mut payload)
+            .map_err(|e| UnpackError::InflateFailed(object_offset, e))?;
+        cursor += decoder.total_in() as usize;
+
+        let (kind, content) = match delta_base {
+            None => {
+                let kind = base_kind_from_type_id(type_id)
+                    .ok_or(UnpackError::UnrecognizedObjectType(object_offset, type_id))?;
+                (kind, payload)
+            }
+            Some(DeltaBaseRef::Offset(base_offset)) => {
+                let base = by_offset
+                    .get(&base_offset)
+                    .ok_or(UnpackError::UnknownOffsetDeltaBase(object_offset))?;
+                let content =
+                    apply_delta(&base.content, &payload, object_offset, limits.max_object_size)?;
+                (base.kind, content)
+            }
+            Some(DeltaBaseRef::Oid(base_oid)) => {
+                let base = by_oid
+                    .get(&base_oid)
+                    .ok_or(UnpackError::UnknownRefDeltaBase(object_offset, base_oid))?;
+                let content =
+                    apply_delta(&base.content, &payload, object_offset, limits.max_object_size)?;
+                (base.kind, content)
+            }
+        };
+
+        if content.len() as u64 > limits.max_object_size {
+            let oid = hash_object(kind, &content);
+            return Err(UnpackError::ObjectTooLarge {
+                oid,
+                size: content.len() as u64,
+                limit: limits.max_object_size,
+            });
+        }
+
+        let oid = hash_object(kind, &content);
+        let content = Bytes::from(content);
+        by_offset.insert(
+            object_offset,
+            BaseRecord {
+                kind,
+                content: content.clone(),
+            },
+        );
+        by_oid.insert(
+            oid.clone(),
+            BaseRecord {
+                kind,
+                content: content.clone(),
+            },
+        );
+        resolved.push(ResolvedObject {
+            oid,
+            kind,
+            bytes: content,
+        });
+    }
+
+    let trailer = data
+        .get(cursor..cursor + PACK_CHECKSUM_LEN)
+        .ok_or(UnpackError::TruncatedPack(cursor as u64))?;
+    let expected = oid::try_from_bytes(trailer)
+        .expect("slice is exactly 20 bytes")
+        .to_owned();
+    let actual = oid::try_from_bytes(Sha1::digest(&data[..cursor]).as_ref())
+        .expect("SHA1 digest is always 20 bytes")
+        .to_owned();
+    if expected != actual {
+        return Err(UnpackError::ChecksumMismatch { expected, actual });
+    }
+
+    Ok(resolved)
+}
+
+/// How a deltified object's base is addressed within the pack.
+enum DeltaBaseRef {
+    Offset(u64),
+    Oid(ObjectId),
+}
@@ -15,6 +15,7 @@ use flate2::write::ZlibEncoder;
 use flate2::Compression;
 use futures::stream;
 use git_types::DeltaInstructions;
+use git_types::HeaderState;
 use gix_diff::blob::Algorithm;
 use gix_hash::ObjectId;
 use gix_object::Object;
@@ -71,8 +72,12 @@ async fn get_objects_stream(
             .hash()
             .to_owned();
         let tag_hash = BaseObject::new(tag_bytes.clone())?.hash().to_owned();
-        let delta_instructions =
-            DeltaInstructions::generate(tag_bytes, another_tag_bytes, Algorithm::Myers)?;
+        let delta_instructions = DeltaInstructions::generate(
+            tag_bytes,
+            another_tag_bytes,
+            Algorithm::Myers,
+            HeaderState::Excluded,
+        )?;
         let mut raw_instructions = Vec::new();
         delta_instructions.write(&mut raw_instructions).await?;
         let decompressed_size = raw_instructions.len() as u64;
@@ -0,0 +1,105 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! Support for precomputed full-clone bundles, so that a fresh `git clone` of a large repo can
+//! download a static, infrequently-changing packfile plus a small incremental fetch instead of
+//! Mononoke generating a bespoke giant pack on every request. See the `packfile-uris` section of
+//! the Git protocol-v2 `fetch` response, and Git's `bundle-uri` capability.
+
+use anyhow::Context;
+use anyhow::Result;
+use blobstore::Blobstore;
+use bytes::Bytes;
+use context::CoreContext;
+use mononoke_types::BlobstoreBytes;
+use packfile::pack::DeltaForm;
+use packfile::pack::PackfileWriter;
+use repo_blobstore::RepoBlobstoreArc;
+
+use crate::generator::generate_pack_item_stream;
+use crate::generator::Repo;
+use crate::types::DeltaInclusion;
+use crate::types::PackItemStreamRequest;
+use crate::types::PackfileItemInclusion;
+use crate::types::TagInclusion;
+
+/// Blobstore key under which the URI of the most recently published full-clone bundle for a repo
+/// is stored. Kept separate per-repo since the blobstore is already repo-scoped.
+const BUNDLE_URI_BLOBSTORE_KEY: &str = "git_bundle_uri";
+
+/// Generate a full-clone packfile for `repo` covering every ref, using the same
+/// [`PackfileWriter`] used for regular fetch responses, and write it to `writer`.
+///
+/// Intended to be invoked periodically by an offline job; the resulting bytes are typically
+/// uploaded to a CDN-backed static store and the CDN URI recorded with [`set_bundle_uri`] so
+/// that fetch responses can advertise it via the `packfile-uris` section instead of streaming a
+/// fresh pack for every clone.
+pub async fn generate_full_repo_bundle<'a>(
+    ctx: CoreContext,
+    repo: &'a impl Repo,
+    writer: impl tokio::io::AsyncWrite + Unpin,
+) -> Result<gix_hash::ObjectId> {
+    let request = PackItemStreamRequest::full_repo(
+        DeltaInclusion::standard(),
+        TagInclusion::AsIs,
+        PackfileItemInclusion::FetchAndStore,
+    );
+    let response = generate_pack_item_stream(ctx, repo, request)
+        .await
+        .with_context(|| {
+            format!(
+                "Error generating full-repo bundle for repo {}",
+                repo.repo_identity().name()
+            )
+        })?;
+    let mut pack_writer = PackfileWriter::new(
+        writer,
+        response.num_items as u32,
+        1,
+        DeltaForm::RefAndOffset,
+    );
+    pack_writer
+        .write(response.items)
+        .await
+        .context("Error writing full-repo bundle packfile")?;
+    pack_writer
+        .finish()
+        .await
+        .context("Error finishing full-repo bundle packfile")
+}
+
+/// Record the URI at which the most recently generated full-clone bundle for `repo` can be
+/// downloaded, so that [`advertised_bundle_uri`] can surface it to clients.
+pub async fn set_bundle_uri(ctx: &CoreContext, repo: &impl Repo, uri: &str) -> Result<()> {
+    repo.repo_blobstore_arc()
+        .put(
+            ctx,
+            BUNDLE_URI_BLOBSTORE_KEY.to_string(),
+            BlobstoreBytes::from_bytes(Bytes::copy_from_slice(uri.as_bytes())),
+        )
+        .await
+        .context("Error storing bundle URI")
+}
+
+/// Fetch the URI of the most recently published full-clone bundle for `repo`, if one has been
+/// generated, so it can be advertised to a client that requested the `packfile-uris` capability.
+pub async fn advertised_bundle_uri(
+    ctx: &CoreContext,
+    repo: &impl Repo,
+) -> Result<Option<String>> {
+    let bytes = repo
+        .repo_blobstore_arc()
+        .get(ctx, BUNDLE_URI_BLOBSTORE_KEY)
+        .await
+        .context("Error fetching bundle URI")?;
+    bytes
+        .map(|bytes| {
+            String::from_utf8(bytes.into_raw_bytes().to_vec())
+                .context("Stored bundle URI is not valid UTF-8")
+        })
+        .transpose()
+}
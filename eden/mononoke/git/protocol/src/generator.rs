@@ -55,6 +55,7 @@ use metaconfig_types::RepoConfigRef;
 use mononoke_types::hash::GitSha1;
 use mononoke_types::path::MPath;
 use mononoke_types::ChangesetId;
+use packfile::pack::DeltaForm;
 use packfile::types::PackfileItem;
 use repo_blobstore::ArcRepoBlobstore;
 use repo_blobstore::RepoBlobstore;
@@ -1467,7 +1468,17 @@ pub async fn fetch_response<'a>(
     repo: &'a impl Repo,
     mut request: FetchRequest,
 ) -> Result<FetchResponse<'a>> {
-    let delta_inclusion = DeltaInclusion::standard();
+    // Offset deltas are only safe to emit if the client has advertised support for them via the
+    // `ofs-delta` capability; otherwise fall back to the ref-delta-only form, which every client
+    // that can fetch a pack at all is guaranteed to understand.
+    let delta_inclusion = DeltaInclusion::Include {
+        form: if request.offset_delta {
+            DeltaForm::OnlyOffset
+        } else {
+            DeltaForm::RefAndOffset
+        },
+        inclusion_threshold: 0.8,
+    };
     let filter = Arc::new(request.filter.clone());
     let packfile_item_inclusion = PackfileItemInclusion::FetchAndStore;
     let ctx = Arc::new(ctx);
@@ -1584,6 +1595,29 @@ pub async fn shallow_info(
             .await
             .context("Error in getting ancestors within distance from shallow commits during shallow-info")?,
         ShallowVariant::None => AncestorsWithinDistance::default(),
+        ShallowVariant::FromServerWithOid(oid) => {
+            // `deepen-not <rev>`: the client wants history up to (but not including) the
+            // history already reachable from `rev`, so `rev` itself becomes the new shallow
+            // boundary.
+            let translated_boundary = git_shas_to_bonsais(&ctx, repo, std::iter::once(oid))
+                .await
+                .context(
+                    "Error converting deepen-not Git commit to Bonsai during shallow-info",
+                )?;
+            let ancestors = repo
+                .commit_graph()
+                .ancestors_difference(
+                    &ctx,
+                    translated_sha_heads.bonsais.clone(),
+                    translated_boundary.bonsais.clone(),
+                )
+                .await
+                .context("Error in getting ancestors difference for deepen-not during shallow-info")?;
+            AncestorsWithinDistance {
+                ancestors,
+                boundaries: translated_boundary.bonsais,
+            }
+        }
         variant => anyhow::bail!("Shallow variant {:?} is not supported yet", variant),
     };
     Ok(ShallowInfoResponse::new(
@@ -7,5 +7,6 @@
 
 #![feature(trait_alias)]
 
+pub mod bundle;
 pub mod generator;
 pub mod types;
@@ -22,7 +22,7 @@ use crate::model::ServiceType;
 
 const CORE_CAPABILITIES: &[&str] = &[
     "ls-refs=unborn",
-    "fetch=shallow wait-for-done filter",
+    "fetch=shallow wait-for-done filter packfile-uris",
     "ref-in-want",
     "object-format=sha1",
 ];
@@ -38,6 +38,7 @@ use packetline::encode::write_text_packetline;
 use packetline::FLUSH_LINE;
 use packfile::pack::DeltaForm;
 use packfile::pack::PackfileWriter;
+use protocol::bundle::advertised_bundle_uri;
 use protocol::generator::bonsai_git_mappings_by_bonsai;
 use protocol::generator::fetch_response;
 use protocol::generator::ls_refs_response;
@@ -46,6 +47,7 @@ use protocol::generator::shallow_info as fetch_shallow_info;
 use protocol::types::PackfileConcurrency;
 use protocol::types::ShallowInfoResponse;
 use rustc_hash::FxHashSet;
+use stats::prelude::*;
 use tokio::io::ErrorKind;
 use tokio::sync::mpsc;
 use tokio_util::io::CopyToBytes;
@@ -77,6 +79,11 @@ const ACK: &str = "ACK";
 /// Acknowledgement that the object sent by the client does not exist on the server
 const NAK: &[u8] = b"NAK";
 
+define_stats! {
+    prefix = "mononoke.git.fetch";
+    delta_form_used: dynamic_timeseries("{}.delta_form_used", (form: String); Rate, Sum),
+}
+
 #[derive(Debug, Clone)]
 struct FetchResponseHeaders {
     acknowledgements: Option<Bytes>,
@@ -230,12 +237,30 @@ async fn wanted_refs(
     Ok(Some(Bytes::from(output_buffer)))
 }
 
+/// The header for the packfile-uris section of the response
+const PACKFILE_URIS_HEADER: &[u8] = b"packfile-uris";
+
 async fn packfile_uris(
-    _context: Arc<RepositoryRequestContext>,
-    _args: Arc<FetchArgs>,
+    context: Arc<RepositoryRequestContext>,
+    args: Arc<FetchArgs>,
 ) -> Result<Option<Bytes>, Error> {
-    // TODO(rajshar): Implement packfile-uris support
-    Ok(None)
+    if args.packfile_uris.is_empty() {
+        // The client did not advertise support for any packfile URI protocol, so there is
+        // nothing to include in this section of the response
+        return Ok(None);
+    }
+    let bundle_uri = match advertised_bundle_uri(&context.ctx, &context.repo).await? {
+        Some(uri) => uri,
+        // No precomputed full-clone bundle has been published for this repo yet
+        None => return Ok(None),
+    };
+    let mut output_buffer = Vec::new();
+    write_text_packetline(PACKFILE_URIS_HEADER, &mut output_buffer).await?;
+    write_text_packetline(bundle_uri.as_bytes(), &mut output_buffer).await?;
+    // Add a delim line to indicate the end of the packfile-uris section. Note that
+    // the delim line will not be followed by a newline character
+    delim_to_write(&mut output_buffer).await?;
+    Ok(Some(Bytes::from(output_buffer)))
 }
 
 impl FetchResponseHeaders {
@@ -404,6 +429,14 @@ pub async fn fetch(
     request_context: &RepositoryRequestContext,
     args: FetchArgs,
 ) -> Result<impl TryIntoResponse, Error> {
+    // Only emit offset deltas if the client has advertised support for them; otherwise stick to
+    // ref deltas, which every client that can fetch a pack at all is guaranteed to understand.
+    let delta_form = if args.ofs_delta {
+        DeltaForm::OnlyOffset
+    } else {
+        DeltaForm::RefAndOffset
+    };
+    STATS::delta_form_used.add_value(1, (format!("{:?}", delta_form),));
     let (writer, reader) = mpsc::channel::<Bytes>(100_000_000);
     let sink_writer = SinkWriter::new(CopyToBytes::new(
         PollSender::new(writer).sink_map_err(|_| std::io::Error::from(ErrorKind::BrokenPipe)),
@@ -446,7 +479,7 @@ pub async fn fetch(
                 sink_writer,
                 response_stream.num_items as u32,
                 5000,
-                DeltaForm::RefAndOffset,
+                delta_form,
             );
             pack_writer.write(response_stream.items).await?;
             pack_writer.finish().await?;
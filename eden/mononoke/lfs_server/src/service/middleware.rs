@@ -50,12 +50,15 @@ impl Middleware for ThrottleMiddleware {
                 return chain(state);
             }
         }
-        let identities = state
+        let metadata = state
             .try_borrow::<MetadataState>()
-            .map(|metadata_state| metadata_state.metadata().identities());
+            .map(|metadata_state| metadata_state.metadata());
+        let identities = metadata.map(|metadata| metadata.identities());
+        let entry_point = metadata.and_then(|metadata| metadata.client_request_info());
+        let entry_point = entry_point.map(|cri| &cri.entry_point);
 
         for limit in self.handle.get().loadshedding_limits().iter() {
-            if let Err(err) = limit.should_load_shed(self.fb, identities) {
+            if let Err(err) = limit.should_load_shed(self.fb, identities, entry_point) {
                 let err = HttpError::e429(err);
 
                 let res =
@@ -141,10 +141,11 @@ impl CachedHgMutationStore {
 }
 
 fn memcache_deserialize(bytes: Bytes) -> McResult<HgMutationCacheEntry> {
-    let thrift_entry =
-        compact_protocol::deserialize(bytes).map_err(|_| McErrorKind::Deserialization);
+    let thrift_entry = compact_protocol::deserialize(bytes)
+        .map_err(|e| McErrorKind::Deserialization(e.to_string()));
     thrift_entry.and_then(|entry| {
-        HgMutationCacheEntry::from_thrift(entry).map_err(|_| McErrorKind::Deserialization)
+        HgMutationCacheEntry::from_thrift(entry)
+            .map_err(|e| McErrorKind::Deserialization(e.to_string()))
     })
 }
 
@@ -223,8 +224,13 @@ impl EntityStore<HgMutationCacheEntry> for CacheRequest<'_> {
         &inner_store.memcache
     }
 
-    fn cache_determinator(&self, _: &HgMutationCacheEntry) -> CacheDisposition {
-        CacheDisposition::Cache(CacheTtl::Ttl(Duration::from_secs(3600)))
+    fn cache_determinator(
+        &self,
+        _key: &str,
+        _: &HgMutationCacheEntry,
+        _serialized_size: &dyn Fn() -> usize,
+    ) -> Result<CacheDisposition, Error> {
+        Ok(CacheDisposition::Cache(CacheTtl::Ttl(Duration::from_secs(3600))))
     }
 
     caching_ext::impl_singleton_stats!("hg_mutation_store");
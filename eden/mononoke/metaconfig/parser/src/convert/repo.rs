@@ -25,6 +25,7 @@ use metaconfig_types::CrossRepoCommitValidation;
 use metaconfig_types::DerivedDataConfig;
 use metaconfig_types::DerivedDataTypesConfig;
 use metaconfig_types::GitConcurrencyParams;
+use metaconfig_types::GitDeltaManifestDiffAlgorithm;
 use metaconfig_types::GitDeltaManifestVersion;
 use metaconfig_types::GlobalrevConfig;
 use metaconfig_types::HgSyncConfig;
@@ -489,6 +490,17 @@ impl Convert for RawDerivedDataTypesConfig {
             Some(1) => GitDeltaManifestVersion::V1,
             Some(version) => return Err(anyhow!("unknown git delta manifest version {}", version)),
         };
+        let git_delta_manifest_diff_algorithm = match self.git_delta_manifest_diff_algorithm {
+            None => GitDeltaManifestDiffAlgorithm::default(),
+            Some(1) => GitDeltaManifestDiffAlgorithm::Myers,
+            Some(2) => GitDeltaManifestDiffAlgorithm::Histogram,
+            Some(algorithm) => {
+                return Err(anyhow!(
+                    "unknown git delta manifest diff algorithm {}",
+                    algorithm
+                ));
+            }
+        };
         Ok(DerivedDataTypesConfig {
             types,
             mapping_key_prefixes,
@@ -497,6 +509,7 @@ impl Convert for RawDerivedDataTypesConfig {
             hg_set_committer_extra: self.hg_set_committer_extra.unwrap_or(false),
             blame_version,
             git_delta_manifest_version,
+            git_delta_manifest_diff_algorithm,
         })
     }
 }
@@ -407,6 +407,10 @@ pub struct DerivedDataTypesConfig {
 
     /// What `GitDeltaManifest` version should be used.
     pub git_delta_manifest_version: GitDeltaManifestVersion,
+
+    /// What diff algorithm should be used when computing the deltas stored in
+    /// `GitDeltaManifest` entries.
+    pub git_delta_manifest_diff_algorithm: GitDeltaManifestDiffAlgorithm,
 }
 
 /// What type of unode derived data to generate
@@ -433,6 +437,18 @@ pub enum GitDeltaManifestVersion {
     V1,
 }
 
+/// What diff algorithm should be used when computing the deltas stored in `GitDeltaManifest`
+/// entries. Histogram tends to produce deltas that are cheaper to compute at the cost of being
+/// somewhat larger than Myers; repos with very large files may prefer it for that tradeoff.
+#[derive(Eq, Clone, Copy, Debug, Default, PartialEq)]
+pub enum GitDeltaManifestDiffAlgorithm {
+    /// Myers diff algorithm
+    #[default]
+    Myers,
+    /// Histogram diff algorithm
+    Histogram,
+}
+
 impl RepoConfig {
     /// Returns the address of the primary metadata database, or None if there is none.
     pub fn primary_metadata_db_address(&self) -> Option<String> {
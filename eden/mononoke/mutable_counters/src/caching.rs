@@ -0,0 +1,94 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! A thin, optional caching layer in front of another [`MutableCounters`] implementation.
+//!
+//! Only [`MutableCounters::get_maybe_stale_counter`] is cached: it is the method callers use when
+//! they are happy to poll a replica, which is exactly the case (e.g. watching a highest-imported
+//! rev marker) where a short-lived cachelib entry can turn a hot loop of replica queries into an
+//! occasional one. `get_counter` and `get_all_counters` always go straight to the inner store,
+//! since callers of those reach for master reads specifically because they need the latest value.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use caching_ext::CacheHandlerFactory;
+use caching_ext::CachelibHandler;
+use context::CoreContext;
+use mononoke_types::RepositoryId;
+
+use crate::MutableCounters;
+
+/// Counters are polled at high rates by some callers, so a few seconds of staleness on top of
+/// the "maybe stale" replica read is an acceptable trade for cutting most of that query volume.
+const CACHE_TTL: Duration = Duration::from_secs(5);
+
+#[derive(Clone)]
+pub struct CachingMutableCounters {
+    repo_id: RepositoryId,
+    mutable_counters: Arc<dyn MutableCounters>,
+    cache: CachelibHandler<i64>,
+}
+
+impl CachingMutableCounters {
+    pub fn new(
+        repo_id: RepositoryId,
+        mutable_counters: Arc<dyn MutableCounters>,
+        cache_handler_factory: CacheHandlerFactory,
+    ) -> Self {
+        Self {
+            repo_id,
+            mutable_counters,
+            cache: cache_handler_factory.cachelib(),
+        }
+    }
+
+    fn cache_key(&self, name: &str) -> String {
+        format!("mutable_counters.{}.{}", self.repo_id.id(), name)
+    }
+}
+
+#[async_trait]
+impl MutableCounters for CachingMutableCounters {
+    async fn get_counter(&self, ctx: &CoreContext, name: &str) -> Result<Option<i64>> {
+        self.mutable_counters.get_counter(ctx, name).await
+    }
+
+    async fn get_maybe_stale_counter(&self, ctx: &CoreContext, name: &str) -> Result<Option<i64>> {
+        let key = self.cache_key(name);
+        if let Some(value) = self.cache.get_cached(&key)? {
+            return Ok(Some(value));
+        }
+
+        let value = self
+            .mutable_counters
+            .get_maybe_stale_counter(ctx, name)
+            .await?;
+        if let Some(value) = value {
+            let _ = self.cache.set_cached(&key, &value, Some(CACHE_TTL));
+        }
+        Ok(value)
+    }
+
+    async fn set_counter(
+        &self,
+        ctx: &CoreContext,
+        name: &str,
+        value: i64,
+        prev_value: Option<i64>,
+    ) -> Result<bool> {
+        self.mutable_counters
+            .set_counter(ctx, name, value, prev_value)
+            .await
+    }
+
+    async fn get_all_counters(&self, ctx: &CoreContext) -> Result<Vec<(String, i64)>> {
+        self.mutable_counters.get_all_counters(ctx).await
+    }
+}
@@ -25,6 +25,10 @@ use sql_ext::SqlConnections;
 use sql_ext::TransactionResult;
 use stats::prelude::*;
 
+pub mod caching;
+
+pub use crate::caching::CachingMutableCounters;
+
 define_stats! {
     prefix = "mononoke.mutable_counters";
     cur_value: dynamic_singleton_counter("{}.cur_value", (name: String)),
@@ -154,7 +154,12 @@ impl MemcacheEntity for HasMutableRename {
         } else if bytes == FALSE {
             Ok(HasMutableRename(false))
         } else {
-            Err(McErrorKind::Deserialization)
+            Err(McErrorKind::Deserialization(format!(
+                "expected {:?} or {:?}, got {} bytes",
+                TRUE,
+                FALSE,
+                bytes.len()
+            )))
         }
     }
 }
@@ -180,14 +185,19 @@ impl<'a> EntityStore<HasMutableRename> for CachedHasMutableRename<'a> {
         self.memcache
     }
 
-    fn cache_determinator(&self, _v: &HasMutableRename) -> CacheDisposition {
+    fn cache_determinator(
+        &self,
+        _key: &str,
+        _v: &HasMutableRename,
+        _serialized_size: &dyn Fn() -> usize,
+    ) -> Result<CacheDisposition, Error> {
         // A cache TTL of 4 hours means that worst case is 8 hours from making
         // a change to caches all showing it.
         //
         // Worst case is we fill memcache just before the change, giving us 4 hours
         // in memcache, then all tasks fill from memcache just before it expires,
         // giving us a further 4 hours (8 total) where all tasks have the stale data.
-        CacheDisposition::Cache(CacheTtl::Ttl(Duration::from_secs(4 * 60 * 60)))
+        Ok(CacheDisposition::Cache(CacheTtl::Ttl(Duration::from_secs(4 * 60 * 60))))
     }
 
     caching_ext::impl_singleton_stats!("mutable_renames.presence");
@@ -338,18 +348,19 @@ impl MemcacheEntity for CachedMutableRenameEntry {
                     src_unode,
                     is_tree,
                 }),
-        } = compact_protocol::deserialize(bytes).map_err(|_| McErrorKind::Deserialization)?
+        } = compact_protocol::deserialize(bytes)
+            .map_err(|e| McErrorKind::Deserialization(e.to_string()))?
         {
-            let dst_cs_id =
-                ChangesetId::from_thrift(dst_cs_id).map_err(|_| McErrorKind::Deserialization)?;
-            let dst_path_hash =
-                path_hash_from_thrift(dst_path_hash).map_err(|_| McErrorKind::Deserialization)?;
-            let src_cs_id =
-                ChangesetId::from_thrift(src_cs_id).map_err(|_| McErrorKind::Deserialization)?;
-            let src_path_hash =
-                path_hash_from_thrift(src_path_hash).map_err(|_| McErrorKind::Deserialization)?;
-            let src_unode =
-                Blake2::from_thrift(src_unode).map_err(|_| McErrorKind::Deserialization)?;
+            let dst_cs_id = ChangesetId::from_thrift(dst_cs_id)
+                .map_err(|e| McErrorKind::Deserialization(e.to_string()))?;
+            let dst_path_hash = path_hash_from_thrift(dst_path_hash)
+                .map_err(|e| McErrorKind::Deserialization(e.to_string()))?;
+            let src_cs_id = ChangesetId::from_thrift(src_cs_id)
+                .map_err(|e| McErrorKind::Deserialization(e.to_string()))?;
+            let src_path_hash = path_hash_from_thrift(src_path_hash)
+                .map_err(|e| McErrorKind::Deserialization(e.to_string()))?;
+            let src_unode = Blake2::from_thrift(src_unode)
+                .map_err(|e| McErrorKind::Deserialization(e.to_string()))?;
             let entry = CacheableMutableRenameEntry {
                 dst_cs_id,
                 dst_path_hash,
@@ -387,8 +398,13 @@ impl<'a> EntityStore<CachedMutableRenameEntry> for CachedGetMutableRename<'a> {
         self.memcache
     }
 
-    fn cache_determinator(&self, _v: &CachedMutableRenameEntry) -> CacheDisposition {
-        CacheDisposition::Cache(CacheTtl::Ttl(Duration::from_secs(4 * 60 * 60)))
+    fn cache_determinator(
+        &self,
+        _key: &str,
+        _v: &CachedMutableRenameEntry,
+        _serialized_size: &dyn Fn() -> usize,
+    ) -> Result<CacheDisposition, Error> {
+        Ok(CacheDisposition::Cache(CacheTtl::Ttl(Duration::from_secs(4 * 60 * 60))))
     }
 
     caching_ext::impl_singleton_stats!("mutable_renames.get_rename");
@@ -478,14 +494,14 @@ impl MemcacheEntity for ChangesetIdSet {
     }
 
     fn deserialize(bytes: Bytes) -> McResult<Self> {
-        let thrift::ChangesetIdSet { cs_ids } =
-            compact_protocol::deserialize(bytes).map_err(|_| McErrorKind::Deserialization)?;
+        let thrift::ChangesetIdSet { cs_ids } = compact_protocol::deserialize(bytes)
+            .map_err(|e| McErrorKind::Deserialization(e.to_string()))?;
         Ok(Self {
             set: cs_ids
                 .into_iter()
                 .map(ChangesetId::from_thrift)
                 .collect::<Result<Vec<_>, Error>>()
-                .map_err(|_| McErrorKind::Deserialization)?,
+                .map_err(|e| McErrorKind::Deserialization(e.to_string()))?,
         })
     }
 }
@@ -511,8 +527,13 @@ impl<'a> EntityStore<ChangesetIdSet> for CachedGetCsIdsWithRename<'a> {
         self.memcache
     }
 
-    fn cache_determinator(&self, _v: &ChangesetIdSet) -> CacheDisposition {
-        CacheDisposition::Cache(CacheTtl::Ttl(Duration::from_secs(4 * 60 * 60)))
+    fn cache_determinator(
+        &self,
+        _key: &str,
+        _v: &ChangesetIdSet,
+        _serialized_size: &dyn Fn() -> usize,
+    ) -> Result<CacheDisposition, Error> {
+        Ok(CacheDisposition::Cache(CacheTtl::Ttl(Duration::from_secs(4 * 60 * 60))))
     }
 
     caching_ext::impl_singleton_stats!("mutable_renames.get_cs_ids_with_rename");
@@ -6,11 +6,13 @@
  */
 
 use std::marker::PhantomData;
+use std::time::Duration;
 
 use caching_ext::CacheHandlerFactory;
 use caching_ext::CachelibHandler;
 use filenodes::FilenodeInfo;
 use filenodes::FilenodeRange;
+use rand::random;
 use stats::prelude::*;
 
 define_stats! {
@@ -18,6 +20,17 @@ define_stats! {
     fill_cache_fail: timeseries(Sum),
 }
 
+/// Base TTL for locally cached filenode entries, jittered by up to `TTL_SEC_RAND` so that
+/// entries filled at the same time don't all expire at once. Mirrors the TTL used for the
+/// remote (memcache) tier in `remote_cache.rs`, so a cachelib entry can't meaningfully outlive
+/// its memcache counterpart.
+const TTL_SEC: u64 = 8 * 60 * 60;
+const TTL_SEC_RAND: u64 = 30 * 60;
+
+fn jittered_ttl() -> Duration {
+    Duration::from_secs(TTL_SEC + random::<u64>() % TTL_SEC_RAND)
+}
+
 #[derive(Clone)]
 pub struct CacheKey<V> {
     pub key: String,
@@ -59,7 +72,9 @@ impl LocalCache {
     }
 
     pub fn fill_filenode(&self, key: &CacheKey<FilenodeInfo>, value: &FilenodeInfo) {
-        let r = self.filenode_cache.set_cached(&key.key, value, None);
+        let r = self
+            .filenode_cache
+            .set_cached(&key.key, value, Some(jittered_ttl()));
         if r.is_err() {
             STATS::fill_cache_fail.add_value(1);
         }
@@ -73,7 +88,9 @@ impl LocalCache {
     }
 
     pub fn fill_history(&self, key: &CacheKey<FilenodeRange>, value: &FilenodeRange) {
-        let r = self.history_cache.set_cached(&key.key, value, None);
+        let r = self
+            .history_cache
+            .set_cached(&key.key, value, Some(jittered_ttl()));
         if r.is_err() {
             STATS::fill_cache_fail.add_value(1);
         }
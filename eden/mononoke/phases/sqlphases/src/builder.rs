@@ -7,11 +7,13 @@
 
 use std::sync::Arc;
 
+use cacheblob::LeaseOps;
 use caching_ext::CacheHandlerFactory;
 use changeset_fetcher::ArcChangesetFetcher;
 use memcache::KeyGen;
 use mononoke_types::RepositoryId;
 use phases::ArcPhases;
+use phases::PublicationPublisher;
 use sql::Connection;
 use sql_construct::SqlConstruct;
 use sql_construct::SqlConstructFromMetadataDatabaseConfig;
@@ -35,6 +37,7 @@ pub struct SqlPhasesBuilder {
     read_connection: Connection,
     read_master_connection: Connection,
     caches: Arc<Caches>,
+    publisher: Option<Arc<dyn PublicationPublisher>>,
 }
 
 impl SqlPhasesBuilder {
@@ -43,14 +46,27 @@ impl SqlPhasesBuilder {
         self.caches = Arc::new(caches);
     }
 
+    /// Set the transport used to notify downstream indexers and cache
+    /// warmers when commits become public. If not set, publication events
+    /// are discarded.
+    pub fn with_publisher(&mut self, publisher: Arc<dyn PublicationPublisher>) {
+        self.publisher = Some(publisher);
+    }
+
     pub fn build(
         self,
         repo_id: RepositoryId,
         changeset_fetcher: ArcChangesetFetcher,
         heads_fetcher: HeadsFetcher,
+        lease: Arc<dyn LeaseOps>,
     ) -> ArcPhases {
+        let publisher = self.publisher.clone();
         let phases_store = self.phases_store();
-        let phases = SqlPhases::new(phases_store, repo_id, changeset_fetcher, heads_fetcher);
+        let mut phases =
+            SqlPhases::new(phases_store, repo_id, changeset_fetcher, heads_fetcher, lease);
+        if let Some(publisher) = publisher {
+            phases = phases.with_publisher(publisher);
+        }
         Arc::new(phases)
     }
 
@@ -81,6 +97,7 @@ impl SqlConstruct for SqlPhasesBuilder {
             read_connection: connections.read_connection,
             read_master_connection: connections.read_master_connection,
             caches,
+            publisher: None,
         }
     }
 }
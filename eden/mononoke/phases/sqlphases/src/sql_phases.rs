@@ -9,22 +9,29 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fmt;
 use std::sync::Arc;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
 
 use abomonation_derive::Abomonation;
 use anyhow::Error;
 use anyhow::Result;
 use ascii::AsciiString;
 use async_trait::async_trait;
+use cacheblob::LeaseOps;
 use changeset_fetcher::ArcChangesetFetcher;
 use context::CoreContext;
+use futures::channel::oneshot;
 use futures::future::try_join;
 use futures::future::BoxFuture;
 use futures::future::FutureExt;
 use mononoke_types::ChangesetId;
 use mononoke_types::RepositoryId;
+use phases::null_publisher;
 use phases::ArcPhases;
 use phases::Phase;
 use phases::Phases;
+use phases::PublicationEvent;
+use phases::PublicationPublisher;
 use sql::mysql;
 use sql::mysql_async::prelude::ConvIr;
 use sql::mysql_async::prelude::FromValue;
@@ -36,6 +43,13 @@ use stats::prelude::*;
 use crate::errors::SqlPhasesError;
 use crate::sql_store::SqlPhasesStore;
 
+/// How many times to retry delivering a [`PublicationEvent`] before giving up.
+/// Delivery failures are non-fatal to the caller (the commits are already
+/// public either way), so this just bounds how hard we try before logging
+/// and moving on.
+const PUBLISH_RETRY_NUM: usize = 3;
+const PUBLISH_RETRY_BASE_DELAY_MS: u64 = 100;
+
 define_stats! {
     prefix = "mononoke.phases";
     public_heads_fetched: timeseries(Rate, Sum),
@@ -120,6 +134,8 @@ pub struct SqlPhases {
     changeset_fetcher: ArcChangesetFetcher,
     heads_fetcher: HeadsFetcher,
     repo_id: RepositoryId,
+    lease: Arc<dyn LeaseOps>,
+    publisher: Arc<dyn PublicationPublisher>,
 }
 
 impl SqlPhases {
@@ -148,9 +164,49 @@ impl SqlPhases {
         ctx: &CoreContext,
         csids: Vec<ChangesetId>,
     ) -> Result<(), Error> {
+        if csids.is_empty() {
+            return Ok(());
+        }
         self.phases_store
-            .add_public_raw(ctx, self.repo_id, csids)
-            .await
+            .add_public_raw(ctx, self.repo_id, csids.clone())
+            .await?;
+        self.publish_newly_public(ctx, csids).await;
+        Ok(())
+    }
+
+    /// Best-effort notify `self.publisher` that `csids` just became public.
+    /// Retries a bounded number of times, but a publisher that keeps failing
+    /// doesn't fail the publication itself -- the commits are already public
+    /// in the store regardless of whether downstream indexers heard about it
+    /// yet.
+    async fn publish_newly_public(&self, ctx: &CoreContext, changeset_ids: Vec<ChangesetId>) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        let repo_id = self.repo_id;
+        let publisher = &self.publisher;
+
+        let res = retry::retry_always(
+            ctx.logger(),
+            |_attempt| {
+                publisher.publish(PublicationEvent {
+                    repo_id,
+                    changeset_ids: changeset_ids.clone(),
+                    timestamp,
+                })
+            },
+            PUBLISH_RETRY_BASE_DELAY_MS,
+            PUBLISH_RETRY_NUM,
+        )
+        .await;
+
+        if let Err(err) = res {
+            ctx.scuba().clone().log_with_msg(
+                "Failed to publish publication event",
+                Some(err.to_string()),
+            );
+        }
     }
 
     pub async fn list_all_public(&self, ctx: &CoreContext) -> Result<Vec<ChangesetId>, Error> {
@@ -204,14 +260,24 @@ impl SqlPhases {
         repo_id: RepositoryId,
         changeset_fetcher: ArcChangesetFetcher,
         heads_fetcher: HeadsFetcher,
+        lease: Arc<dyn LeaseOps>,
     ) -> Self {
         Self {
             phases_store,
             changeset_fetcher,
             heads_fetcher,
             repo_id,
+            lease,
+            publisher: null_publisher(),
         }
     }
+
+    /// Set the transport used to notify downstream indexers and cache
+    /// warmers when commits become public. Defaults to a no-op publisher.
+    pub fn with_publisher(mut self, publisher: Arc<dyn PublicationPublisher>) -> Self {
+        self.publisher = publisher;
+        self
+    }
 }
 
 #[async_trait]
@@ -263,16 +329,76 @@ impl Phases for SqlPhases {
             changeset_fetcher: self.changeset_fetcher.clone(),
             heads_fetcher,
             repo_id: self.repo_id,
+            lease: self.lease.clone(),
+            publisher: self.publisher.clone(),
         })
     }
 }
 
-/// Mark all commits reachable from `public_heads` as public
+/// Mark all commits reachable from `public_heads` as public.
+///
+/// The ancestor walk this performs is redundant (and, for a busy repo, likely)
+/// to be happening concurrently for the same heads: many requests can discover
+/// the same changesets are not yet public at the same time. Take a lease keyed
+/// by the head changesets first, so only one caller actually walks and writes,
+/// while the others wait for it to finish and then just re-check what became
+/// public in the meantime.
 pub async fn mark_reachable_as_public(
     ctx: &CoreContext,
     phases: &SqlPhases,
     all_heads: &[ChangesetId],
     ephemeral_derive: bool,
+) -> Result<Vec<ChangesetId>, Error> {
+    if all_heads.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let lease_key = mark_reachable_as_public_lease_key(phases.repo_id, all_heads);
+    with_lease(ctx, &phases.lease, &lease_key, || {
+        mark_reachable_as_public_uncontended(ctx, phases, all_heads, ephemeral_derive)
+    })
+    .await
+}
+
+fn mark_reachable_as_public_lease_key(repo_id: RepositoryId, all_heads: &[ChangesetId]) -> String {
+    let mut heads: Vec<String> = all_heads.iter().map(ToString::to_string).collect();
+    heads.sort();
+    format!("mark_reachable_as_public.{}.{}", repo_id, heads.join(","))
+}
+
+/// Run `f` while holding `lease`, waiting for any other holder of the same key to
+/// finish first. This only deduplicates concurrent work; it is not a correctness
+/// requirement, so on lease errors we just run `f` directly rather than failing
+/// the whole operation.
+async fn with_lease<F, Fut, Ret>(
+    ctx: &CoreContext,
+    lease: &Arc<dyn LeaseOps>,
+    key: &str,
+    f: F,
+) -> Result<Ret, Error>
+where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = Result<Ret, Error>>,
+{
+    while !lease.try_add_put_lease(key).await.unwrap_or(true) {
+        lease.wait_for_other_leases(key).await;
+    }
+
+    let (sender, receiver) = oneshot::channel();
+    lease.renew_lease_until(ctx.clone(), key, receiver.map(|_| ()).boxed());
+
+    let result = f().await;
+
+    let _ = sender.send(());
+
+    result
+}
+
+async fn mark_reachable_as_public_uncontended(
+    ctx: &CoreContext,
+    phases: &SqlPhases,
+    all_heads: &[ChangesetId],
+    ephemeral_derive: bool,
 ) -> Result<Vec<ChangesetId>, Error> {
     let changeset_fetcher = &phases.changeset_fetcher;
     let public = phases.get_public_raw(ctx, all_heads).await?;
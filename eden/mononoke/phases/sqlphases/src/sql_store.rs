@@ -42,6 +42,9 @@ use crate::sql_phases::SqlPhase;
 // 6 hours in sec
 pub const TTL_DRAFT_SEC: u64 = 21600;
 
+// A much shorter local TTL than TTL_DRAFT_SEC's Memcache one - see cache_determinator below.
+const TTL_DRAFT_CACHELIB: Duration = Duration::from_secs(60);
+
 define_stats! {
     prefix = "mononoke.phases";
     get_single: timeseries(Rate, Sum),
@@ -184,7 +187,7 @@ impl MemcacheEntity for SqlPhase {
         bytes
             .as_ref()
             .try_into()
-            .map_err(|_| McErrorKind::Deserialization)
+            .map_err(|e: crate::SqlPhasesError| McErrorKind::Deserialization(e.to_string()))
     }
 }
 
@@ -206,14 +209,24 @@ impl EntityStore<SqlPhase> for CacheRequest<'_> {
         &phases.caches.memcache
     }
 
-    fn cache_determinator(&self, phase: &SqlPhase) -> CacheDisposition {
-        let ttl = if phase == &SqlPhase(Phase::Public) {
-            CacheTtl::NoTtl
-        } else {
-            CacheTtl::Ttl(Duration::from_secs(TTL_DRAFT_SEC))
-        };
+    fn cache_determinator(
+        &self,
+        _key: &str,
+        phase: &SqlPhase,
+        _serialized_size: &dyn Fn() -> usize,
+    ) -> Result<CacheDisposition, Error> {
+        if phase == &SqlPhase(Phase::Public) {
+            return Ok(CacheDisposition::Cache(CacheTtl::NoTtl));
+        }
 
-        CacheDisposition::Cache(ttl)
+        // A draft phase can be corrected to public by any host at any time, so hold onto it
+        // locally for much less time than in Memcache: a short cachelib TTL means this host
+        // notices such a correction quickly, while the longer Memcache TTL - shared fleet-wide -
+        // still saves every host's first read of a long-lived draft from hitting the DB.
+        Ok(CacheDisposition::CacheWithTtls {
+            cachelib: CacheTtl::Ttl(TTL_DRAFT_CACHELIB),
+            memcache: CacheTtl::Ttl(Duration::from_secs(TTL_DRAFT_SEC)),
+        })
     }
 
     caching_ext::impl_singleton_stats!("phases");
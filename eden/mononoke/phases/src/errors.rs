@@ -11,4 +11,6 @@ use thiserror::Error;
 pub enum PhasesError {
     #[error("invalid phase enumeration value: {0}")]
     EnumError(u32),
+    #[error("list_all_public is not supported by this Phases implementation")]
+    ListAllPublicUnsupported,
 }
@@ -6,9 +6,11 @@
  */
 
 mod errors;
+mod publisher;
 
 use std::collections::HashSet;
 use std::fmt;
+use std::sync::Arc;
 
 use abomonation_derive::Abomonation;
 use anyhow::Result;
@@ -16,6 +18,10 @@ use async_trait::async_trait;
 use context::CoreContext;
 pub use errors::PhasesError;
 use mononoke_types::ChangesetId;
+pub use publisher::null_publisher;
+pub use publisher::NullPublicationPublisher;
+pub use publisher::PublicationEvent;
+pub use publisher::PublicationPublisher;
 
 #[derive(Abomonation, Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Phase {
@@ -105,8 +111,78 @@ pub trait Phases: Send + Sync {
     fn with_frozen_public_heads(&self, heads: Vec<ChangesetId>) -> ArcPhases;
 }
 
+/// Phases implementation for repos that are known, by configuration, to contain only public
+/// commits (e.g. mirrors of a repo that only ever exposes its public history). Every commit is
+/// answered as public without touching any cache or store, so these repos don't pay the cost of
+/// a real phase lookup on every request.
+///
+/// This is the read path only: selecting it for a given repo based on that repo's config is left
+/// to the repo factory that constructs the [`Phases`] implementation to use.
+#[derive(Clone, Debug, Default)]
+pub struct AllPublicPhases;
+
+impl AllPublicPhases {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl Phases for AllPublicPhases {
+    async fn add_reachable_as_public(
+        &self,
+        _ctx: &CoreContext,
+        _heads: Vec<ChangesetId>,
+    ) -> Result<Vec<ChangesetId>> {
+        // Every commit is already public, so nothing newly becomes public.
+        Ok(Vec::new())
+    }
+
+    async fn add_public_with_known_public_ancestors(
+        &self,
+        _ctx: &CoreContext,
+        _csids: Vec<ChangesetId>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    async fn get_public(
+        &self,
+        _ctx: &CoreContext,
+        csids: Vec<ChangesetId>,
+        _ephemeral_derive: bool,
+    ) -> Result<HashSet<ChangesetId>> {
+        Ok(csids.into_iter().collect())
+    }
+
+    async fn get_cached_public(
+        &self,
+        _ctx: &CoreContext,
+        csids: Vec<ChangesetId>,
+    ) -> Result<HashSet<ChangesetId>> {
+        Ok(csids.into_iter().collect())
+    }
+
+    async fn list_all_public(&self, _ctx: &CoreContext) -> Result<Vec<ChangesetId>> {
+        // Listing every public commit would mean enumerating the repo's changesets, which is
+        // exactly the kind of store access this mode exists to avoid.
+        Err(PhasesError::ListAllPublicUnsupported.into())
+    }
+
+    fn with_frozen_public_heads(&self, _heads: Vec<ChangesetId>) -> ArcPhases {
+        // Every commit is already public regardless of heads, so freezing a set of heads has
+        // no effect here.
+        Arc::new(self.clone())
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use fbinit::FacebookInit;
+    use maplit::hashset;
+    use mononoke_types_mocks::changesetid::ONES_CSID;
+    use mononoke_types_mocks::changesetid::TWOS_CSID;
+
     use super::*;
 
     #[test]
@@ -117,4 +193,28 @@ mod tests {
         assert_eq!(Phase::try_from(1u32), Ok(Phase::Draft));
         assert!(Phase::try_from(2u32).is_err());
     }
+
+    #[fbinit::test]
+    async fn test_all_public_phases_get_public(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        let phases = AllPublicPhases::new();
+        let csids = vec![ONES_CSID, TWOS_CSID];
+        assert_eq!(
+            phases.get_public(&ctx, csids.clone(), false).await?,
+            hashset! {ONES_CSID, TWOS_CSID}
+        );
+        assert_eq!(
+            phases.get_cached_public(&ctx, csids).await?,
+            hashset! {ONES_CSID, TWOS_CSID}
+        );
+        Ok(())
+    }
+
+    #[fbinit::test]
+    async fn test_all_public_phases_list_all_public_unsupported(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        let phases = AllPublicPhases::new();
+        assert!(phases.list_all_public(&ctx).await.is_err());
+        Ok(())
+    }
 }
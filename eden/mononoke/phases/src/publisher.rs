@@ -0,0 +1,58 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use mononoke_types::ChangesetId;
+use mononoke_types::RepositoryId;
+
+/// A batch of changesets that just became public in `repo_id`, at `timestamp`
+/// (seconds since the Unix epoch).
+///
+/// Implementations of [`PublicationPublisher`] are expected to deliver this
+/// event to some external system (e.g. a search indexer or cache warmer) so
+/// that it can react within seconds instead of having to poll for newly
+/// public commits.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicationEvent {
+    pub repo_id: RepositoryId,
+    pub changeset_ids: Vec<ChangesetId>,
+    pub timestamp: i64,
+}
+
+/// Pluggable transport for [`PublicationEvent`]s.
+///
+/// Callers that mark commits as public (see [`crate::Phases`]) are expected
+/// to retry a failed `publish` a bounded number of times (see
+/// `retry::retry_always`) before giving up, so that a transient transport
+/// failure doesn't silently drop an event. This trait itself does not retry:
+/// implementations should treat a single call as "deliver this once" and
+/// return an `Err` if that attempt failed, leaving the retrying to the
+/// caller.
+#[async_trait]
+pub trait PublicationPublisher: Send + Sync {
+    async fn publish(&self, event: PublicationEvent) -> Result<()>;
+}
+
+/// A [`PublicationPublisher`] that discards every event. This is the
+/// default transport for callers that don't need publication events, so
+/// that wiring one up is opt-in.
+#[derive(Clone, Debug, Default)]
+pub struct NullPublicationPublisher;
+
+#[async_trait]
+impl PublicationPublisher for NullPublicationPublisher {
+    async fn publish(&self, _event: PublicationEvent) -> Result<()> {
+        Ok(())
+    }
+}
+
+pub fn null_publisher() -> Arc<dyn PublicationPublisher> {
+    Arc::new(NullPublicationPublisher)
+}
@@ -11,6 +11,7 @@ use std::time::Duration;
 use anyhow::anyhow;
 use anyhow::Context;
 use anyhow::Error;
+use clientinfo::ClientEntryPoint;
 use serde::de::Deserializer;
 use serde::de::Error as _;
 use serde::Deserialize;
@@ -55,6 +56,9 @@ impl TryFrom<rate_limiting_config::Target> for Target {
                     nonce: s.nonce,
                 }))
             }
+            rate_limiting_config::Target::entry_point(e) => Ok(Target::EntryPoint(
+                ClientEntryPoint::try_from(e.as_str()).context("Invalid entry_point")?,
+            )),
             _ => Err(anyhow!("Invalid target")),
         }
     }
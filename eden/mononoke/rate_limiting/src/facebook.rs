@@ -10,6 +10,7 @@ use std::sync::Arc;
 
 use anyhow::Error;
 use async_trait::async_trait;
+use clientinfo::ClientEntryPoint;
 use fbinit::FacebookInit;
 use fbwhoami::FbWhoAmI;
 use permission_checker::MononokeIdentitySet;
@@ -51,13 +52,14 @@ impl RateLimiter for MononokeRateLimits {
         &self,
         metric: Metric,
         identities: &MononokeIdentitySet,
+        entry_point: Option<&ClientEntryPoint>,
     ) -> Result<Result<(), RateLimitReason>, Error> {
         for limit in &self.config.rate_limits {
             if limit.metric != metric {
                 continue;
             }
 
-            if !limit.applies_to_client(identities) {
+            if !limit.applies_to_client(identities, entry_point) {
                 continue;
             }
 
@@ -79,9 +81,13 @@ impl RateLimiter for MononokeRateLimits {
         Ok(Ok(()))
     }
 
-    fn check_load_shed(&self, identities: &MononokeIdentitySet) -> Result<(), RateLimitReason> {
+    fn check_load_shed(
+        &self,
+        identities: &MononokeIdentitySet,
+        entry_point: Option<&ClientEntryPoint>,
+    ) -> Result<(), RateLimitReason> {
         for limit in &self.config.load_shed_limits {
-            limit.should_load_shed(self.fb, Some(identities))?;
+            limit.should_load_shed(self.fb, Some(identities), entry_point)?;
         }
 
         Ok(())
@@ -0,0 +1,227 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! A scheduler that apportions a fixed number of concurrent request slots fairly
+//! across repos or clients sharing a host, instead of first-come-first-served.
+//!
+//! When the host isn't saturated, [`FairScheduler::acquire`] admits immediately,
+//! same as an ordinary semaphore. Once `capacity` slots are all in use, callers
+//! queue by `key` (typically a repo name, or a client identity), and whenever a
+//! slot frees up it is handed to a waiter from whichever key currently holds the
+//! *fewest* in-flight slots, rather than to whichever caller has been waiting
+//! longest. That keeps one bulk consumer hammering a single repo from starving
+//! everyone else on the same host, while still making full use of capacity when
+//! there's no contention.
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use tokio::sync::oneshot;
+
+#[derive(Debug, Clone)]
+pub struct FairSchedulerConfig {
+    /// The maximum number of slots that may be in-flight at once, across all keys.
+    pub capacity: usize,
+}
+
+pub struct FairScheduler {
+    capacity: usize,
+    inner: Mutex<Inner>,
+}
+
+struct Inner {
+    in_flight: HashMap<String, usize>,
+    total_in_flight: usize,
+    waiters: VecDeque<Waiter>,
+}
+
+struct Waiter {
+    key: String,
+    admit: oneshot::Sender<()>,
+}
+
+impl Inner {
+    fn admit(&mut self, key: &str) {
+        *self.in_flight.entry(key.to_string()).or_insert(0) += 1;
+        self.total_in_flight += 1;
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(count) = self.in_flight.get_mut(key) {
+            *count -= 1;
+            if *count == 0 {
+                self.in_flight.remove(key);
+            }
+        }
+        self.total_in_flight -= 1;
+    }
+
+    /// The index of the queued waiter whose key currently holds the fewest
+    /// in-flight slots, breaking ties in favour of whoever queued first.
+    fn fairest_waiter_index(&self) -> Option<usize> {
+        self.waiters
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, waiter)| self.in_flight.get(&waiter.key).copied().unwrap_or(0))
+            .map(|(idx, _)| idx)
+    }
+}
+
+impl FairScheduler {
+    pub fn new(config: FairSchedulerConfig) -> Self {
+        Self {
+            capacity: config.capacity,
+            inner: Mutex::new(Inner {
+                in_flight: HashMap::new(),
+                total_in_flight: 0,
+                waiters: VecDeque::new(),
+            }),
+        }
+    }
+
+    /// The number of slots currently in use, across all keys.
+    pub fn total_in_flight(&self) -> usize {
+        self.inner.lock().expect("lock poisoned").total_in_flight
+    }
+
+    /// Acquire a slot for `key`, waiting if the host is currently saturated.
+    ///
+    /// The returned permit releases its slot, and admits the fairest queued
+    /// waiter (if any), when dropped.
+    pub async fn acquire(&self, key: impl Into<String>) -> FairPermit<'_> {
+        let key = key.into();
+        let rx = {
+            let mut inner = self.inner.lock().expect("lock poisoned");
+            if inner.total_in_flight < self.capacity {
+                inner.admit(&key);
+                None
+            } else {
+                let (admit, rx) = oneshot::channel();
+                inner.waiters.push_back(Waiter {
+                    key: key.clone(),
+                    admit,
+                });
+                Some(rx)
+            }
+        };
+
+        if let Some(rx) = rx {
+            // `release` only ever drops the sender after sending, so a closed
+            // channel here would mean we raced a shutdown; either way, treat it
+            // the same as being admitted rather than propagating an error the
+            // caller has no useful way to act on.
+            let _ = rx.await;
+        }
+
+        FairPermit {
+            scheduler: self,
+            key,
+        }
+    }
+
+    fn release(&self, key: &str) {
+        let mut inner = self.inner.lock().expect("lock poisoned");
+        inner.remove(key);
+
+        while let Some(idx) = inner.fairest_waiter_index() {
+            let waiter = inner
+                .waiters
+                .remove(idx)
+                .expect("index from fairest_waiter_index is always valid");
+            inner.admit(&waiter.key);
+            if waiter.admit.send(()).is_ok() {
+                break;
+            }
+            // The waiter's future was dropped (e.g. the caller's request was
+            // cancelled) before we could admit it. Give the slot back and try the
+            // next fairest waiter instead.
+            inner.remove(&waiter.key);
+        }
+    }
+}
+
+pub struct FairPermit<'a> {
+    scheduler: &'a FairScheduler,
+    key: String,
+}
+
+impl FairPermit<'_> {
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl Drop for FairPermit<'_> {
+    fn drop(&mut self) {
+        self.scheduler.release(&self.key);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use tokio::sync::Notify;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_immediate_admission_within_capacity() {
+        let scheduler = FairScheduler::new(FairSchedulerConfig { capacity: 2 });
+        let _p1 = scheduler.acquire("a").await;
+        let _p2 = scheduler.acquire("b").await;
+        assert_eq!(scheduler.total_in_flight(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_fairness_beats_fifo_order() {
+        let scheduler = Arc::new(FairScheduler::new(FairSchedulerConfig { capacity: 2 }));
+
+        // "a" holds both slots, so the host is saturated.
+        let p1 = scheduler.acquire("a").await;
+        let p2 = scheduler.acquire("a").await;
+
+        // "a" queues for a third slot first...
+        let scheduler_a = scheduler.clone();
+        let a_waiter = tokio::spawn(async move {
+            let _permit = scheduler_a.acquire("a").await;
+        });
+        tokio::task::yield_now().await;
+
+        // ...and "b" queues second, but holds no in-flight slots yet.
+        let hold = Arc::new(Notify::new());
+        let scheduler_b = scheduler.clone();
+        let hold_b = hold.clone();
+        let b_waiter = tokio::spawn(async move {
+            let _permit = scheduler_b.acquire("b").await;
+            hold_b.notified().await;
+        });
+        tokio::task::yield_now().await;
+
+        // Free up one slot. Fairness should admit "b", even though "a" queued
+        // first, because "a" already holds a slot and "b" holds none.
+        drop(p1);
+        for _ in 0..5 {
+            tokio::task::yield_now().await;
+        }
+
+        assert_eq!(scheduler.total_in_flight(), 2);
+        assert!(
+            !a_waiter.is_finished(),
+            "the longer-queued \"a\" waiter should still be blocked"
+        );
+
+        hold.notify_one();
+        b_waiter.await.unwrap();
+
+        // With "b" gone, "a"'s queued request is now the fairest (only) waiter.
+        a_waiter.await.unwrap();
+
+        drop(p2);
+    }
+}
@@ -14,6 +14,10 @@ use anyhow::anyhow;
 use anyhow::Error;
 use async_trait::async_trait;
 use cached_config::ConfigHandle;
+use chrono::Datelike;
+use chrono::Local;
+use chrono::Weekday;
+use clientinfo::ClientEntryPoint;
 use fbinit::FacebookInit;
 use permission_checker::MononokeIdentity;
 use permission_checker::MononokeIdentitySet;
@@ -23,6 +27,7 @@ use thiserror::Error;
 
 #[cfg(fbcode_build)]
 mod facebook;
+mod fair_scheduler;
 #[cfg(not(fbcode_build))]
 mod oss;
 
@@ -37,6 +42,9 @@ pub use oss::get_region_capacity;
 pub use rate_limiting_config::RateLimitStatus;
 
 pub mod config;
+pub use fair_scheduler::FairPermit;
+pub use fair_scheduler::FairScheduler;
+pub use fair_scheduler::FairSchedulerConfig;
 
 pub type LoadCost = f64;
 pub type BoxRateLimiter = Box<dyn RateLimiter + Send + Sync + 'static>;
@@ -47,9 +55,14 @@ pub trait RateLimiter {
         &self,
         metric: Metric,
         identities: &MononokeIdentitySet,
+        entry_point: Option<&ClientEntryPoint>,
     ) -> Result<Result<(), RateLimitReason>, Error>;
 
-    fn check_load_shed(&self, identities: &MononokeIdentitySet) -> Result<(), RateLimitReason>;
+    fn check_load_shed(
+        &self,
+        identities: &MononokeIdentitySet,
+        entry_point: Option<&ClientEntryPoint>,
+    ) -> Result<(), RateLimitReason>;
 
     fn bump_load(&self, metric: Metric, load: LoadCost);
 
@@ -119,11 +132,15 @@ pub struct RateLimit {
 
 #[cfg(fbcode_build)]
 impl RateLimit {
-    fn applies_to_client(&self, identities: &MononokeIdentitySet) -> bool {
+    fn applies_to_client(
+        &self,
+        identities: &MononokeIdentitySet,
+        entry_point: Option<&ClientEntryPoint>,
+    ) -> bool {
         match &self.target {
             // TODO (harveyhunt): Pass identities rather than Some(identities) once LFS server has
             // been updated to require certs.
-            Some(t) => t.matches_client(Some(identities)),
+            Some(t) => t.matches_client(Some(identities), entry_point),
             None => true,
         }
     }
@@ -135,9 +152,10 @@ impl LoadShedLimit {
         &self,
         fb: FacebookInit,
         identities: Option<&MononokeIdentitySet>,
+        entry_point: Option<&ClientEntryPoint>,
     ) -> Result<(), RateLimitReason> {
         let applies_to_client = match &self.target {
-            Some(t) => t.matches_client(identities),
+            Some(t) => t.matches_client(identities, entry_point),
             None => true,
         };
 
@@ -146,23 +164,36 @@ impl LoadShedLimit {
         }
 
         let metric = self.raw_config.metric.to_string();
+        let limit = self.effective_limit(Local::now().weekday());
 
         match STATS::load_shed_counter.get_value(fb, (metric.clone(),)) {
-            Some(value) if value > self.raw_config.limit => match self.raw_config.status {
+            Some(value) if value > limit => match self.raw_config.status {
                 RateLimitStatus::Disabled => Ok(()),
                 // TODO (liubovd): add logging to scuba for reached limits
                 RateLimitStatus::Tracked => Ok(()),
-                RateLimitStatus::Enforced => Err(RateLimitReason::LoadShedMetric(
-                    metric,
-                    value,
-                    self.raw_config.limit,
-                )),
+                RateLimitStatus::Enforced => {
+                    Err(RateLimitReason::LoadShedMetric(metric, value, limit))
+                }
                 // NOTE: Thrift enums aren't real enums once in Rust. We have to account for other values here.
                 _ => Ok(()),
             },
             _ => Ok(()),
         }
     }
+
+    /// The limit to enforce right now: `off_peak_limit` on weekends, when configured, and
+    /// `limit` the rest of the time. Background-job-heavy weekend traffic shouldn't be shed by
+    /// a threshold tuned for weekday interactive peak load.
+    fn effective_limit(&self, today: Weekday) -> i64 {
+        match self.raw_config.off_peak_limit {
+            Some(off_peak_limit) if is_weekend(today) => off_peak_limit,
+            _ => self.raw_config.limit,
+        }
+    }
+}
+
+fn is_weekend(day: Weekday) -> bool {
+    matches!(day, Weekday::Sat | Weekday::Sun)
 }
 
 #[derive(Debug, Clone)]
@@ -188,6 +219,29 @@ pub enum RateLimitReason {
     LoadShedMetric(String, i64, i64),
 }
 
+impl RateLimitReason {
+    /// A short, stable, machine-readable identifier for this reason, suitable for
+    /// exposing to clients so they can distinguish throttling from other failures
+    /// without parsing the human-readable message.
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            RateLimitReason::RateLimitedMetric(..) => "rate_limited",
+            RateLimitReason::LoadShedMetric(..) => "load_shed",
+        }
+    }
+
+    /// How long a client should wait before retrying, if known.
+    ///
+    /// Load shedding is a point-in-time decision based on current load rather than a
+    /// fixed window, so there is no meaningful retry hint to give in that case.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            RateLimitReason::RateLimitedMetric(_metric, window) => Some(*window),
+            RateLimitReason::LoadShedMetric(..) => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Target {
     NotTarget(Box<Target>),
@@ -195,6 +249,10 @@ pub enum Target {
     OrTarget(Vec<Target>),
     Identity(MononokeIdentity),
     StaticSlice(StaticSlice),
+    /// Matches requests that came in through a specific serving surface (hg wireproto, EdenAPI,
+    /// git, SCS, ...), so a limit can target e.g. git-packfile egress without constraining
+    /// EdenAPI traffic under the same metric.
+    EntryPoint(ClientEntryPoint),
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -221,16 +279,25 @@ pub struct StaticSlice {
 }
 
 impl Target {
-    pub fn matches_client(&self, identities: Option<&MononokeIdentitySet>) -> bool {
+    pub fn matches_client(
+        &self,
+        identities: Option<&MononokeIdentitySet>,
+        entry_point: Option<&ClientEntryPoint>,
+    ) -> bool {
         match self {
-            Self::NotTarget(t) => !t.matches_client(identities),
-            Self::AndTarget(ts) => ts.iter().all(|t| t.matches_client(identities)),
-            Self::OrTarget(ts) => ts.iter().any(|t| t.matches_client(identities)),
+            Self::NotTarget(t) => !t.matches_client(identities, entry_point),
+            Self::AndTarget(ts) => ts
+                .iter()
+                .all(|t| t.matches_client(identities, entry_point)),
+            Self::OrTarget(ts) => ts
+                .iter()
+                .any(|t| t.matches_client(identities, entry_point)),
             Self::Identity(i) => match identities {
                 Some(client_idents) => client_idents.contains(i),
                 None => false,
             },
             Self::StaticSlice(s) => in_throttled_slice(identities, s.slice_pct, &s.nonce),
+            Self::EntryPoint(target_entry_point) => entry_point == Some(target_entry_point),
         }
     }
 }
@@ -257,6 +324,14 @@ fn in_throttled_slice(
 mod test {
     use super::*;
 
+    #[test]
+    fn test_is_weekend() {
+        assert!(!is_weekend(Weekday::Mon));
+        assert!(!is_weekend(Weekday::Fri));
+        assert!(is_weekend(Weekday::Sat));
+        assert!(is_weekend(Weekday::Sun));
+    }
+
     #[test]
     fn test_target_matches() {
         let test_ident = MononokeIdentity::new("USER", "foo");
@@ -268,25 +343,25 @@ mod test {
         let ident3_target = Target::Identity(test3_ident.clone());
         let empty_idents = Some(MononokeIdentitySet::new());
 
-        assert!(!ident_target.matches_client(empty_idents.as_ref()));
+        assert!(!ident_target.matches_client(empty_idents.as_ref(), None));
 
         let mut idents = MononokeIdentitySet::new();
         idents.insert(test_ident);
         idents.insert(test3_ident);
         let idents = Some(idents);
 
-        assert!(ident_target.matches_client(idents.as_ref()));
+        assert!(ident_target.matches_client(idents.as_ref(), None));
 
         let and_target = Target::AndTarget(vec![ident_target.clone(), ident3_target]);
 
-        assert!(and_target.matches_client(idents.as_ref()));
+        assert!(and_target.matches_client(idents.as_ref(), None));
 
         let or_target = Target::OrTarget(vec![ident_target, ident2_target.clone()]);
 
-        assert!(or_target.matches_client(idents.as_ref()));
+        assert!(or_target.matches_client(idents.as_ref(), None));
 
         let not_target = Target::NotTarget(Box::new(ident2_target));
-        assert!(not_target.matches_client(idents.as_ref()));
+        assert!(not_target.matches_client(idents.as_ref(), None));
     }
 
     #[test]
@@ -353,26 +428,26 @@ mod test {
         let idents2 = Some(idents);
 
         // All of SERVICE_IDENTITY: bar
-        assert!(ident_target.matches_client(idents1.as_ref()));
+        assert!(ident_target.matches_client(idents1.as_ref(), None));
 
         // 20% of SERVICE_IDENTITY: bar. ratelimited host
         let twenty_pct_service_identity =
             Target::AndTarget(vec![ident_target.clone(), twenty_pct_target.clone()]);
-        assert!(twenty_pct_service_identity.matches_client(idents1.as_ref()));
+        assert!(twenty_pct_service_identity.matches_client(idents1.as_ref(), None));
 
         // 20% of SERVICE_IDENTITY: bar. not ratelimited host
         let twenty_pct_service_identity =
             Target::AndTarget(vec![ident_target.clone(), twenty_pct_target]);
-        assert!(!twenty_pct_service_identity.matches_client(idents2.as_ref()));
+        assert!(!twenty_pct_service_identity.matches_client(idents2.as_ref(), None));
 
         // 100% of SERVICE_IDENTITY: bar
         let hundred_pct_service_identity =
             Target::AndTarget(vec![ident_target.clone(), hundred_pct_target.clone()]);
-        assert!(hundred_pct_service_identity.matches_client(idents1.as_ref()));
+        assert!(hundred_pct_service_identity.matches_client(idents1.as_ref(), None));
 
         // 100% of SERVICE_IDENTITY: bar
         let hundred_pct_service_identity =
             Target::AndTarget(vec![ident_target.clone(), hundred_pct_target]);
-        assert!(hundred_pct_service_identity.matches_client(idents2.as_ref()));
+        assert!(hundred_pct_service_identity.matches_client(idents2.as_ref(), None));
     }
 }
@@ -10,6 +10,7 @@ use std::sync::Arc;
 
 use anyhow::Error;
 use async_trait::async_trait;
+use clientinfo::ClientEntryPoint;
 use fbinit::FacebookInit;
 use permission_checker::MononokeIdentitySet;
 
@@ -44,11 +45,16 @@ impl RateLimiter for FakeLimiter {
         &self,
         _metric: Metric,
         _identities: &MononokeIdentitySet,
+        _entry_point: Option<&ClientEntryPoint>,
     ) -> Result<Result<(), RateLimitReason>, Error> {
         Ok(Ok(()))
     }
 
-    fn check_load_shed(&self, _identities: &MononokeIdentitySet) -> Result<(), RateLimitReason> {
+    fn check_load_shed(
+        &self,
+        _identities: &MononokeIdentitySet,
+        _entry_point: Option<&ClientEntryPoint>,
+    ) -> Result<(), RateLimitReason> {
         Ok(())
     }
 
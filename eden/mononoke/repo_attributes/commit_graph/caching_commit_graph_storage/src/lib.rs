@@ -249,7 +249,7 @@ impl MemcacheEntity for CachedPrefetchedChangesetEdges {
     fn deserialize(bytes: Bytes) -> McResult<Self> {
         compact_protocol::deserialize(bytes)
             .and_then(CachedPrefetchedChangesetEdges::from_thrift)
-            .map_err(|_| McErrorKind::Deserialization)
+            .map_err(|e| McErrorKind::Deserialization(e.to_string()))
     }
 }
 
@@ -292,8 +292,13 @@ impl EntityStore<CachedPrefetchedChangesetEdges> for CacheRequest<'_> {
         }
     }
 
-    fn cache_determinator(&self, _: &CachedPrefetchedChangesetEdges) -> CacheDisposition {
-        CacheDisposition::Cache(CacheTtl::NoTtl)
+    fn cache_determinator(
+        &self,
+        _key: &str,
+        _: &CachedPrefetchedChangesetEdges,
+        _serialized_size: &dyn Fn() -> usize,
+    ) -> Result<CacheDisposition> {
+        Ok(CacheDisposition::Cache(CacheTtl::NoTtl))
     }
 
     caching_ext::impl_singleton_stats!("commit_graph");
@@ -203,6 +203,7 @@ use wireproto_handler::TargetRepoDbs;
 use zelos_queue::zelos_derivation_queues;
 
 const DERIVED_DATA_LEASE: &str = "derived-data-lease";
+const PHASES_LEASE: &str = "phases-lease";
 
 #[derive(Clone)]
 struct RepoFactoryCache<K: Clone + Eq + Hash, V: Clone> {
@@ -860,7 +861,13 @@ impl RepoFactory {
             sql_phases_builder.enable_caching(cache_handler_factory);
         }
         let heads_fetcher = bookmark_heads_fetcher(bookmarks.clone());
-        Ok(sql_phases_builder.build(repo_identity.id(), changeset_fetcher.clone(), heads_fetcher))
+        let lease = self.lease(PHASES_LEASE)?;
+        Ok(sql_phases_builder.build(
+            repo_identity.id(),
+            changeset_fetcher.clone(),
+            heads_fetcher,
+            lease,
+        ))
     }
 
     pub async fn bonsai_hg_mapping(
@@ -459,7 +459,12 @@ impl TestRepoFactory {
     ) -> ArcPhases {
         let sql_phases_builder = SqlPhasesBuilder::from_sql_connections(self.metadata_db.clone());
         let heads_fetcher = bookmark_heads_fetcher(bookmarks.clone());
-        sql_phases_builder.build(repo_identity.id(), changeset_fetcher.clone(), heads_fetcher)
+        sql_phases_builder.build(
+            repo_identity.id(),
+            changeset_fetcher.clone(),
+            heads_fetcher,
+            Arc::new(InProcessLease::new()),
+        )
     }
 
     /// Construct Bonsai Hg Mapping using the in-memory metadata database.
@@ -185,7 +185,7 @@ impl MemcacheEntity for ChangesetIdWrapper {
     fn deserialize(bytes: Bytes) -> McResult<Self> {
         match ChangesetId::from_bytes(&bytes) {
             Ok(cs_id) => Ok(ChangesetIdWrapper(cs_id)),
-            Err(_) => Err(McErrorKind::Deserialization),
+            Err(e) => Err(McErrorKind::Deserialization(e.to_string())),
         }
     }
 }
@@ -206,8 +206,13 @@ impl EntityStore<ChangesetIdWrapper> for ChangesetIdCacheRequest<'_> {
         &bag.cache_handlers.memcache
     }
 
-    fn cache_determinator(&self, _: &ChangesetIdWrapper) -> CacheDisposition {
-        CacheDisposition::Cache(CacheTtl::NoTtl)
+    fn cache_determinator(
+        &self,
+        _key: &str,
+        _: &ChangesetIdWrapper,
+        _serialized_size: &dyn Fn() -> usize,
+    ) -> Result<CacheDisposition> {
+        Ok(CacheDisposition::Cache(CacheTtl::NoTtl))
     }
 
     caching_ext::impl_singleton_stats!("segmented_changelog.idmap.dag2cs");
@@ -260,10 +265,12 @@ impl MemcacheEntity for DagIdWrapper {
     }
 
     fn deserialize(bytes: Bytes) -> McResult<Self> {
-        let arr = bytes
-            .as_ref()
-            .try_into()
-            .map_err(|_| McErrorKind::Deserialization)?;
+        let arr = bytes.as_ref().try_into().map_err(|_| {
+            McErrorKind::Deserialization(format!(
+                "expected 8 bytes for a DagId, got {}",
+                bytes.len()
+            ))
+        })?;
         Ok(DagIdWrapper(DagId(u64::from_be_bytes(arr))))
     }
 }
@@ -284,8 +291,13 @@ impl EntityStore<DagIdWrapper> for DagIdCacheRequest<'_> {
         &bag.cache_handlers.memcache
     }
 
-    fn cache_determinator(&self, _: &DagIdWrapper) -> CacheDisposition {
-        CacheDisposition::Cache(CacheTtl::NoTtl)
+    fn cache_determinator(
+        &self,
+        _key: &str,
+        _: &DagIdWrapper,
+        _serialized_size: &dyn Fn() -> usize,
+    ) -> Result<CacheDisposition> {
+        Ok(CacheDisposition::Cache(CacheTtl::NoTtl))
     }
 
     caching_ext::impl_singleton_stats!("segmented_changelog.idmap.cs2dag");
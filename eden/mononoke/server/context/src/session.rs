@@ -108,16 +108,24 @@ impl SessionContainer {
     }
 
     pub fn check_load_shed(&self) -> Result<(), RateLimitReason> {
+        let entry_point = self
+            .metadata()
+            .client_request_info()
+            .map(|cri| &cri.entry_point);
         match &self.inner.rate_limiter {
-            Some(limiter) => limiter.check_load_shed(self.metadata().identities()),
+            Some(limiter) => limiter.check_load_shed(self.metadata().identities(), entry_point),
             None => Ok(()),
         }
     }
 
     pub async fn check_rate_limit(&self, metric: Metric) -> Result<(), RateLimitReason> {
+        let entry_point = self
+            .metadata()
+            .client_request_info()
+            .map(|cri| &cri.entry_point);
         match &self.inner.rate_limiter {
             Some(limiter) => limiter
-                .check_rate_limit(metric, self.metadata().identities())
+                .check_rate_limit(metric, self.metadata().identities(), entry_point)
                 .await
                 .unwrap_or(Ok(())),
             None => Ok(()),
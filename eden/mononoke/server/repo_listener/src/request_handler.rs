@@ -119,7 +119,8 @@ pub async fn request_handler(
 
     let rate_limiter = rate_limiter.map(|r| r.get_rate_limiter());
     if let Some(ref rate_limiter) = rate_limiter {
-        if let Err(err) = rate_limiter.check_load_shed(metadata.identities()) {
+        let entry_point = metadata.client_request_info().map(|cri| &cri.entry_point);
+        if let Err(err) = rate_limiter.check_load_shed(metadata.identities(), entry_point) {
             scuba.log_with_msg("Request rejected due to load shedding", format!("{}", err));
             error!(conn_log, "Request rejected due to load shedding: {}", err; "remote" => "true");
 
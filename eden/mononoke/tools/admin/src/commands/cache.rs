@@ -0,0 +1,32 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+mod bonsai_hg_mapping;
+
+use anyhow::Result;
+use clap::Parser;
+use clap::Subcommand;
+use mononoke_app::MononokeApp;
+
+/// Inspect or purge entries in caches built on caching_ext.
+#[derive(Parser)]
+pub struct CommandArgs {
+    #[clap(subcommand)]
+    store: StoreSubcommand,
+}
+
+#[derive(Subcommand)]
+pub enum StoreSubcommand {
+    /// Inspect or purge the bonsai/hg changeset id mapping cache
+    BonsaiHgMapping(bonsai_hg_mapping::CommandArgs),
+}
+
+pub async fn run(app: MononokeApp, args: CommandArgs) -> Result<()> {
+    match args.store {
+        StoreSubcommand::BonsaiHgMapping(args) => bonsai_hg_mapping::run(app, args).await,
+    }
+}
@@ -0,0 +1,131 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::sync::Arc;
+
+use anyhow::Context;
+use anyhow::Result;
+use bonsai_hg_mapping::BonsaiOrHgChangesetId;
+use bonsai_hg_mapping::CachingBonsaiHgMapping;
+use bonsai_hg_mapping::SqlBonsaiHgMappingBuilder;
+use caching_ext::CacheHandlerFactory;
+use clap::ArgGroup;
+use clap::Args;
+use clap::Parser;
+use clap::Subcommand;
+use environment::Caching;
+use memcache::MemcacheClient;
+use mercurial_types::HgChangesetId;
+use metaconfig_types::RepoConfig;
+use metaconfig_types::RepoConfigRef;
+use mononoke_app::args::RepoArgs;
+use mononoke_app::MononokeApp;
+use mononoke_types::ChangesetId;
+use repo_identity::RepoIdentity;
+use repo_identity::RepoIdentityRef;
+
+/// Inspect or purge the cache in front of the bonsai/hg changeset id mapping.
+#[derive(Parser)]
+pub struct CommandArgs {
+    #[clap(flatten)]
+    repo: RepoArgs,
+
+    #[clap(subcommand)]
+    action: Action,
+}
+
+#[derive(Subcommand)]
+pub enum Action {
+    /// Report what is cached for a changeset id, without going to the database
+    Get(ChangesetIdArgs),
+    /// Evict the cachelib entry for a changeset id
+    Purge(ChangesetIdArgs),
+}
+
+#[derive(Args)]
+#[clap(group(ArgGroup::new("id").args(&["bonsai_id", "hg_id"]).required(true)))]
+pub struct ChangesetIdArgs {
+    /// Bonsai changeset id to look up
+    #[clap(long)]
+    bonsai_id: Option<ChangesetId>,
+
+    /// Mercurial changeset id to look up
+    #[clap(long)]
+    hg_id: Option<HgChangesetId>,
+}
+
+impl ChangesetIdArgs {
+    fn id(&self) -> BonsaiOrHgChangesetId {
+        match (self.bonsai_id, self.hg_id) {
+            (Some(id), None) => BonsaiOrHgChangesetId::Bonsai(id),
+            (None, Some(id)) => BonsaiOrHgChangesetId::Hg(id),
+            _ => unreachable!("--bonsai-id and --hg-id are mutually exclusive and required"),
+        }
+    }
+}
+
+#[facet::container]
+pub struct Repo {
+    #[facet]
+    repo_identity: RepoIdentity,
+
+    #[facet]
+    repo_config: RepoConfig,
+}
+
+pub async fn run(app: MononokeApp, args: CommandArgs) -> Result<()> {
+    let ctx = app.new_basic_context();
+    let repo: Repo = app
+        .open_repo(&args.repo)
+        .await
+        .context("Failed to open repo")?;
+
+    let sql_mapping = app
+        .repo_factory()
+        .sql_factory(&repo.repo_config().storage_config.metadata)
+        .await?
+        .open::<SqlBonsaiHgMappingBuilder>()
+        .await?
+        .build(
+            repo.repo_identity().id(),
+            app.environment().rendezvous_options,
+        );
+
+    let cache_handler_factory = match app.environment().caching {
+        Caching::Enabled(_) => CacheHandlerFactory::Shared {
+            cachelib_pool: cachelib::get_volatile_pool("bonsai_hg_mapping")?
+                .context("Missing bonsai_hg_mapping cache pool")?,
+            memcache_client: MemcacheClient::new(ctx.fb)
+                .context("Failed to initialize memcache client")?,
+        },
+        Caching::LocalOnly(_) => CacheHandlerFactory::Local {
+            cachelib_pool: cachelib::get_volatile_pool("bonsai_hg_mapping")?
+                .context("Missing bonsai_hg_mapping cache pool")?,
+        },
+        Caching::Disabled => {
+            anyhow::bail!("Caching is disabled, so there is no bonsai_hg_mapping cache to inspect")
+        }
+    };
+
+    let mapping = CachingBonsaiHgMapping::new(Arc::new(sql_mapping), cache_handler_factory)?;
+
+    match args.action {
+        Action::Get(id_args) => {
+            let summary = mapping.cache_summary(id_args.id()).await?;
+            println!("cachelib: {:?}", summary.cachelib);
+            println!("memcache: {:?}", summary.memcache);
+        }
+        Action::Purge(id_args) => {
+            mapping.purge_cachelib(id_args.id())?;
+            println!(
+                "Purged cachelib entry (memcache entries have no removal API and are left to expire on their own TTL)"
+            );
+        }
+    }
+
+    Ok(())
+}